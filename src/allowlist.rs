@@ -0,0 +1,101 @@
+use semver::Version;
+use serde_json::Value;
+use std::{collections::HashMap, error::Error, fs, path::Path};
+
+use crate::version::NpmRange;
+
+/// The approved version range of each package, from --allowlist-file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Allowlist {
+    ranges: HashMap<String, NpmRange>,
+}
+
+impl Allowlist {
+    /// Parses a JSON object of package names to npm version ranges, e.g. `{ "react": "^18.0.0" }`
+    pub fn parse(src: &str) -> Result<Allowlist, Box<dyn Error>> {
+        let entries: HashMap<String, Value> = serde_json::from_str(src)?;
+        let mut ranges = HashMap::new();
+
+        for (name, range) in entries {
+            let range = range
+                .as_str()
+                .ok_or_else(|| format!("The allowlisted range of {} isn't a string", name))?;
+            let req = NpmRange::parse(range).map_err(|err| {
+                format!(
+                    "Invalid allowlisted range for {} ({}) - {}",
+                    name, range, err
+                )
+            })?;
+
+            ranges.insert(name, req);
+        }
+
+        Ok(Allowlist { ranges })
+    }
+
+    pub fn load(path: &Path) -> Result<Allowlist, Box<dyn Error>> {
+        let src = fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read {} - {}", path.display(), err))?;
+
+        Allowlist::parse(&src)
+    }
+
+    /// Whether bumping to `version` is approved. Packages which aren't listed, or versions
+    /// which aren't valid semver, never are
+    pub fn allows(&self, name: &str, version: &str) -> bool {
+        match (self.ranges.get(name), Version::parse(version)) {
+            (Some(req), Ok(version)) => req.matches(&version),
+            _ => false,
+        }
+    }
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod allowlist_tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{
+        "react": "^18.0.0",
+        "debug": ">=3.2.7 <4.3.0",
+        "@jonshort/cenv": "1.0.3",
+        "polished": "3.x || 4.0.0 - 4.1.0"
+    }"#;
+
+    #[test]
+    fn allows_versions_in_range() {
+        let allowlist = Allowlist::parse(SAMPLE).unwrap();
+
+        assert!(allowlist.allows("react", "18.2.0"));
+        assert!(allowlist.allows("debug", "4.2.0"));
+        assert!(allowlist.allows("@jonshort/cenv", "1.0.3"));
+        assert!(allowlist.allows("polished", "3.7.2"));
+        assert!(allowlist.allows("polished", "4.1.0"));
+    }
+
+    #[test]
+    fn rejects_versions_out_of_range() {
+        let allowlist = Allowlist::parse(SAMPLE).unwrap();
+
+        assert!(!allowlist.allows("react", "19.0.0"));
+        assert!(!allowlist.allows("debug", "4.3.4"));
+        assert!(!allowlist.allows("@jonshort/cenv", "1.0.4"));
+        assert!(!allowlist.allows("polished", "4.2.2"));
+    }
+
+    #[test]
+    fn rejects_unlisted_packages_and_invalid_versions() {
+        let allowlist = Allowlist::parse(SAMPLE).unwrap();
+
+        assert!(!allowlist.allows("lodash", "4.17.21"));
+        assert!(!allowlist.allows("react", "MISSING"));
+    }
+
+    #[test]
+    fn errors_on_invalid_ranges() {
+        assert!(Allowlist::parse(r#"{ "react": "not a range" }"#).is_err());
+        assert!(Allowlist::parse(r#"{ "react": 18 }"#).is_err());
+        assert!(Allowlist::parse(r#"["react"]"#).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use serde_json::Value;
+
+use crate::package::{Package, SkipReason};
+use crate::utility::UpgradeStyle;
+
+/// A package npm audit says can be bumped to fix a vulnerability, with the version it needs to
+/// reach when that's outside the declared range
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecurityFix {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+/// Parses the output of `npm audit --json` (npm 7+), returning the packages to bump to fix the
+/// reported vulnerabilities
+pub fn parse_audit_report(src: &str) -> Result<Vec<SecurityFix>, serde_json::Error> {
+    let report: Value = serde_json::from_str(src)?;
+    let mut fixes: Vec<SecurityFix> = vec![];
+
+    let vulnerabilities = match report.get("vulnerabilities").and_then(|v| v.as_object()) {
+        Some(v) => v,
+        None => return Ok(fixes),
+    };
+
+    for (name, vulnerability) in vulnerabilities.iter() {
+        let fix = match vulnerability.get("fixAvailable") {
+            // the fix is within the declared range
+            Some(Value::Bool(true)) => SecurityFix {
+                name: name.into(),
+                version: None,
+            },
+            // the fix is often a bump of whatever depends on the vulnerable package
+            Some(Value::Object(fix)) => {
+                let fix_name = fix.get("name").and_then(|n| n.as_str());
+                let fix_version = fix.get("version").and_then(|v| v.as_str());
+
+                match (fix_name, fix_version) {
+                    (Some(n), Some(v)) => SecurityFix {
+                        name: n.into(),
+                        version: Some(v.into()),
+                    },
+                    _ => continue,
+                }
+            }
+            _ => continue,
+        };
+
+        if !fixes.contains(&fix) {
+            fixes.push(fix);
+        }
+    }
+
+    Ok(fixes)
+}
+
+/// Keeps only the outdated packages which fix a vulnerability, retargeting them at the fixed
+/// version. A fix outside the declared range brings back packages skipped for only having newer
+/// versions out of range
+pub fn restrict_to_fixes(
+    packages: Vec<Package>,
+    skipped: &mut Vec<(Package, SkipReason)>,
+    fixes: &[SecurityFix],
+    upgrade_style: &UpgradeStyle,
+) -> Vec<Package> {
+    let fix_version = |pkg: &Package| {
+        fixes
+            .iter()
+            .find(|fix| fix.name == pkg.name)
+            .and_then(|fix| fix.version.clone())
+    };
+
+    let (out_of_range, still_skipped): (Vec<_>, Vec<_>) = skipped
+        .drain(..)
+        .partition(|(pkg, reason)| *reason == SkipReason::OutOfRange && fix_version(pkg).is_some());
+    *skipped = still_skipped;
+
+    packages
+        .into_iter()
+        .filter(|pkg| fixes.iter().any(|fix| fix.name == pkg.name))
+        .chain(out_of_range.into_iter().map(|(mut pkg, _)| {
+            pkg.skip = None;
+            pkg
+        }))
+        .map(|mut pkg| {
+            if let Some(version) = fix_version(&pkg) {
+                pkg.retarget(&version, upgrade_style);
+            }
+
+            pkg
+        })
+        .collect()
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod parse_audit_report_tests {
+    use super::*;
+
+    #[test]
+    fn finds_fixable_packages() {
+        let src = include_str!("test_files/npm_audit.json");
+        let expected = vec![
+            SecurityFix {
+                name: String::from("debug"),
+                version: None,
+            },
+            SecurityFix {
+                name: String::from("mkdirp"),
+                version: Some(String::from("1.0.4")),
+            },
+        ];
+
+        assert_eq!(parse_audit_report(src).unwrap(), expected);
+    }
+
+    #[test]
+    fn handles_no_vulnerabilities() {
+        assert_eq!(
+            parse_audit_report(r#"{ "auditReportVersion": 2, "vulnerabilities": {} }"#).unwrap(),
+            vec![]
+        );
+        assert_eq!(parse_audit_report("{}").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn err_on_invalid_json() {
+        assert!(parse_audit_report("npm error code ENOLOCK").is_err());
+    }
+}
+
+#[cfg(test)]
+mod restrict_to_fixes_tests {
+    use super::*;
+    use crate::package::UpgradeType;
+    use crate::utility::Config;
+
+    fn fixes() -> Vec<SecurityFix> {
+        vec![
+            SecurityFix {
+                name: String::from("debug"),
+                version: None,
+            },
+            SecurityFix {
+                name: String::from("mkdirp"),
+                version: Some(String::from("1.0.4")),
+            },
+        ]
+    }
+
+    fn packages(lines: &[&str], config: &Config) -> Vec<Package> {
+        lines
+            .iter()
+            .map(|s| Package::new(String::from(*s), config).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn keeps_fixing_packages_at_fixed_version() {
+        let config = Config::default();
+        let packages = packages(
+            &[
+                "location:debug@4.3.4:debug@4.3.0:debug@4.3.4:test",
+                "location:mkdirp@0.5.6:mkdirp@0.5.1:mkdirp@3.0.1:test",
+                "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            ],
+            &config,
+        );
+
+        let install_cmds: Vec<String> =
+            restrict_to_fixes(packages, &mut vec![], &fixes(), &config.upgrade_style)
+                .into_iter()
+                .map(|pkg| pkg.install_spec(&config).to_string())
+                .collect();
+
+        assert_eq!(install_cmds, vec!["debug@4.3.4", "mkdirp@1.0.4"]);
+    }
+
+    #[test]
+    fn targets_the_fixed_version() {
+        let config = Config::default();
+        let packages = packages(
+            &["location:mkdirp@0.5.6:mkdirp@0.5.1:mkdirp@3.0.1:test"],
+            &config,
+        );
+
+        let fixed = restrict_to_fixes(packages, &mut vec![], &fixes(), &config.upgrade_style);
+
+        assert_eq!(fixed[0].upgrade_version(&config.upgrade_style), "1.0.4");
+        assert_eq!(fixed[0].upgrade_type, UpgradeType::Major);
+    }
+
+    #[test]
+    fn brings_back_packages_skipped_as_out_of_range() {
+        let config = Config::default();
+        let mut skipped: Vec<(Package, SkipReason)> = packages(
+            &[
+                "location:debug@2.6.9:debug@2.6.9:debug@4.3.4:test",
+                "location:mkdirp@0.5.6:mkdirp@0.5.6:mkdirp@3.0.1:test",
+            ],
+            &config,
+        )
+        .into_iter()
+        .map(|pkg| (pkg, SkipReason::OutOfRange))
+        .collect();
+
+        let fixed = restrict_to_fixes(vec![], &mut skipped, &fixes(), &config.upgrade_style);
+
+        // debug's fix is within its range, which has nothing newer to bump to
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].0.name, "debug");
+        assert_eq!(fixed.len(), 1);
+        assert_eq!(fixed[0].install_spec(&config).to_string(), "mkdirp@1.0.4");
+        assert_eq!(fixed[0].upgrade_type, UpgradeType::Major);
+        assert_eq!(fixed[0].skip, None);
+    }
+}
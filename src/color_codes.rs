@@ -1,2 +1,31 @@
 pub const CYAN: u8 = 36;
+pub const GREEN: u8 = 32;
 pub const YELLOW: u8 = 33;
+
+pub fn paint(text: &str, color: u8, is_enabled: bool) -> String {
+    if is_enabled {
+        format!("\x1b[{}m{}\x1b[0m", color, text)
+    } else {
+        String::from(text)
+    }
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod paint_tests {
+    use super::*;
+
+    #[test]
+    fn wraps_in_color_codes() {
+        assert_eq!(
+            paint("1.2.3", CYAN, true),
+            String::from("\x1b[36m1.2.3\x1b[0m")
+        );
+    }
+
+    #[test]
+    fn plain_when_disabled() {
+        assert_eq!(paint("1.2.3", CYAN, false), String::from("1.2.3"));
+    }
+}
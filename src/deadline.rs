@@ -0,0 +1,159 @@
+use std::{
+    error::Error,
+    fmt,
+    process::{Child, ExitStatus},
+    thread,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Exit code used when the maximum runtime is exceeded, matching coreutils' timeout
+pub const TIMED_OUT_EXIT_CODE: i32 = 124;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimedOut;
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Exceeded the maximum runtime")
+    }
+}
+
+impl Error for TimedOut {}
+
+pub fn is_timeout(err: &(dyn Error + 'static)) -> bool {
+    err.downcast_ref::<TimedOut>().is_some()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Deadline {
+    end: Option<Instant>,
+}
+
+impl Deadline {
+    /// A deadline `max_runtime` from now, or one which never expires
+    pub fn new(max_runtime: Option<Duration>) -> Deadline {
+        Deadline {
+            end: max_runtime.and_then(|max| Instant::now().checked_add(max)),
+        }
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.end
+            .map(|end| end.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining() == Some(Duration::ZERO)
+    }
+
+    pub fn check(&self) -> Result<(), TimedOut> {
+        if self.is_expired() {
+            Err(TimedOut)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Waits for the child to exit, killing it if the deadline passes first
+    pub fn wait(&self, child: &mut Child) -> Result<ExitStatus, Box<dyn Error>> {
+        if self.end.is_none() {
+            return Ok(child.wait()?);
+        }
+
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+
+            if self.is_expired() {
+                child.kill()?;
+                child.wait()?;
+                return Err(TimedOut.into());
+            }
+
+            thread::sleep(POLL_INTERVAL.min(self.remaining().unwrap_or(POLL_INTERVAL)));
+        }
+    }
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod deadline_tests {
+    use super::*;
+
+    #[test]
+    fn unbounded_never_expires() {
+        let deadline = Deadline::new(None);
+
+        assert_eq!(deadline.remaining(), None);
+        assert!(!deadline.is_expired());
+        assert_eq!(deadline.check(), Ok(()));
+    }
+
+    #[test]
+    fn expires_after_max_runtime() {
+        let deadline = Deadline::new(Some(Duration::from_millis(20)));
+
+        assert!(!deadline.is_expired());
+        assert!(deadline.remaining().unwrap() <= Duration::from_millis(20));
+
+        thread::sleep(Duration::from_millis(30));
+
+        assert!(deadline.is_expired());
+        assert_eq!(deadline.remaining(), Some(Duration::ZERO));
+        assert_eq!(deadline.check(), Err(TimedOut));
+    }
+
+    #[test]
+    fn zero_runtime_is_expired_immediately() {
+        assert!(Deadline::new(Some(Duration::ZERO)).is_expired());
+    }
+
+    #[test]
+    fn detects_timeout_errors() {
+        let timeout: Box<dyn Error> = TimedOut.into();
+        let other: Box<dyn Error> = "something else".into();
+
+        assert!(is_timeout(timeout.as_ref()));
+        assert!(!is_timeout(other.as_ref()));
+    }
+}
+
+#[cfg(all(test, unix))]
+mod wait_tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn kills_child_past_deadline() {
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        let started = Instant::now();
+
+        let result = Deadline::new(Some(Duration::from_millis(100))).wait(&mut child);
+
+        assert!(is_timeout(result.unwrap_err().as_ref()));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(child.try_wait().unwrap().is_some());
+    }
+
+    #[test]
+    fn returns_status_within_deadline() {
+        let mut child = Command::new("true").spawn().unwrap();
+        let status = Deadline::new(Some(Duration::from_secs(5)))
+            .wait(&mut child)
+            .unwrap();
+
+        assert!(status.success());
+    }
+
+    #[test]
+    fn waits_without_deadline() {
+        let mut child = Command::new("false").spawn().unwrap();
+        let status = Deadline::new(None).wait(&mut child).unwrap();
+
+        assert!(!status.success());
+    }
+}
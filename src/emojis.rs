@@ -1,6 +1,112 @@
-pub const CROSS: char = '\u{274C}';
-pub const DIZZY: char = '\u{1F4AB}';
-pub const MAGNIFYING_GLASS: char = '\u{1F50D}';
-pub const POINT_RIGHT: char = '\u{1F449}';
-pub const ROCKET: char = '\u{1F680}';
-pub const TROPHY: char = '\u{1F3C6}';
+use clap::ValueEnum;
+use serde::Serialize;
+
+pub const CROSS: &str = "\u{274C}";
+pub const DIZZY: &str = "\u{1F4AB}";
+pub const MAGNIFYING_GLASS: &str = "\u{1F50D}";
+pub const POINT_RIGHT: &str = "\u{1F449}";
+pub const ROCKET: &str = "\u{1F680}";
+pub const TROPHY: &str = "\u{1F3C6}";
+pub const WARNING: &str = "\u{26A0}";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Icon {
+    Cross,
+    Dizzy,
+    MagnifyingGlass,
+    PointRight,
+    Rocket,
+    Trophy,
+    Warning,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Emoji markers
+    #[default]
+    Emoji,
+    /// ASCII markers, for terminals without emoji support
+    Minimal,
+}
+
+impl Theme {
+    pub fn icon(&self, icon: Icon) -> &'static str {
+        match self {
+            Theme::Emoji => match icon {
+                Icon::Cross => CROSS,
+                Icon::Dizzy => DIZZY,
+                Icon::MagnifyingGlass => MAGNIFYING_GLASS,
+                Icon::PointRight => POINT_RIGHT,
+                Icon::Rocket => ROCKET,
+                Icon::Trophy => TROPHY,
+                Icon::Warning => WARNING,
+            },
+            Theme::Minimal => match icon {
+                Icon::Cross => "!!",
+                Icon::Dizzy => "**",
+                Icon::MagnifyingGlass => "..",
+                Icon::PointRight => "->",
+                Icon::Rocket | Icon::Trophy => "OK",
+                Icon::Warning => "!",
+            },
+        }
+    }
+
+    /// Wraps a message in its emoji, or prefixes it with the ASCII marker
+    pub fn decorate(&self, message: &str, icon: Icon) -> String {
+        match self {
+            Theme::Emoji => format!("{0} {1} {0}", self.icon(icon), message),
+            Theme::Minimal => format!("{} {}", self.icon(icon), message),
+        }
+    }
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod theme_tests {
+    use super::*;
+
+    const ICONS: [Icon; 7] = [
+        Icon::Cross,
+        Icon::Dizzy,
+        Icon::MagnifyingGlass,
+        Icon::PointRight,
+        Icon::Rocket,
+        Icon::Trophy,
+        Icon::Warning,
+    ];
+
+    #[test]
+    fn minimal_is_ascii() {
+        for icon in ICONS {
+            assert!(Theme::Minimal.icon(icon).is_ascii());
+        }
+
+        assert_eq!(Theme::Minimal.icon(Icon::PointRight), "->");
+        assert_eq!(Theme::Minimal.icon(Icon::Trophy), "OK");
+        assert_eq!(Theme::Minimal.icon(Icon::Cross), "!!");
+    }
+
+    #[test]
+    fn emoji_is_default() {
+        assert_eq!(Theme::default(), Theme::Emoji);
+
+        for icon in ICONS {
+            assert!(!Theme::Emoji.icon(icon).is_ascii());
+        }
+    }
+
+    #[test]
+    fn decorates_messages() {
+        assert_eq!(
+            Theme::Emoji.decorate("All packages bumped", Icon::Trophy),
+            format!("{0} All packages bumped {0}", TROPHY)
+        );
+        assert_eq!(
+            Theme::Minimal.decorate("All packages bumped", Icon::Trophy),
+            String::from("OK All packages bumped")
+        );
+    }
+}
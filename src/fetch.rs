@@ -0,0 +1,846 @@
+use crate::manifest;
+use crate::npm_cmd::{self, MajorHints, ViewOptions};
+use crate::package::Package;
+use crate::utility::Config;
+use semver::Version;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs,
+    io::{self, IsTerminal},
+    sync::{Condvar, Mutex},
+    thread,
+};
+
+const DEFAULT_REGISTRY: &str = "registry.npmjs.org";
+const DOWNLOADS_HOST: &str = "api.npmjs.org";
+
+/// Looks up registry metadata for a single package version
+pub trait MetadataClient: Sync {
+    type Metadata: Send;
+
+    fn fetch(&self, name: &str, version: &str) -> Self::Metadata;
+
+    /// The registry host the package is fetched from, used to limit requests per registry
+    fn host(&self, name: &str) -> String;
+}
+
+/// The host part of a registry url, e.g. registry.npmjs.org for https://registry.npmjs.org/
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split('/').next().unwrap_or(rest)
+}
+
+/// Registry hosts from `.npmrc` entries, i.e. `registry=` and `@scope:registry=`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Registries {
+    default: Option<String>,
+    scoped: HashMap<String, String>,
+}
+
+impl Registries {
+    pub fn parse(npmrc: &str) -> Registries {
+        let mut registries = Registries::default();
+
+        for line in npmrc.lines() {
+            let (key, value) = match line.trim().split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+
+            if key == "registry" {
+                registries.default = Some(url_host(value).into());
+            } else if let Some(scope) = key.strip_suffix(":registry") {
+                registries
+                    .scoped
+                    .insert(scope.into(), url_host(value).into());
+            }
+        }
+
+        registries
+    }
+
+    pub fn host(&self, name: &str) -> String {
+        let scoped = name
+            .split_once('/')
+            .filter(|(scope, _)| scope.starts_with('@'))
+            .and_then(|(scope, _)| self.scoped.get(scope));
+
+        scoped
+            .or(self.default.as_ref())
+            .map_or(DEFAULT_REGISTRY, |host| host.as_str())
+            .into()
+    }
+}
+
+/// What every `npm view` client is set up with: the registries configured in the current
+/// directory's `.npmrc`, if any, and the timeout and TLS settings from `config`
+#[derive(Default)]
+pub struct ClientSettings {
+    registries: Registries,
+    options: ViewOptions,
+}
+
+impl ClientSettings {
+    pub fn new(config: &Config) -> ClientSettings {
+        ClientSettings {
+            registries: Registries::parse(&fs::read_to_string(".npmrc").unwrap_or_default()),
+            options: ViewOptions::new(config),
+        }
+    }
+}
+
+/// Fetches the changelog/homepage/issues links with `npm view`
+#[derive(Default)]
+pub struct MajorHintsClient {
+    settings: ClientSettings,
+}
+
+impl MajorHintsClient {
+    pub fn new(config: &Config) -> MajorHintsClient {
+        MajorHintsClient {
+            settings: ClientSettings::new(config),
+        }
+    }
+}
+
+impl MetadataClient for MajorHintsClient {
+    type Metadata = MajorHints;
+
+    fn host(&self, name: &str) -> String {
+        self.settings.registries.host(name)
+    }
+
+    fn fetch(&self, name: &str, version: &str) -> MajorHints {
+        // offline, timed out (or unpublished metadata) just leaves the defaults
+        npm_cmd::view_major_hints(name, version, &self.settings.options).unwrap_or_default()
+    }
+}
+
+/// Fetches the deprecation message of a version with `npm view`
+#[derive(Default)]
+pub struct DeprecationClient {
+    settings: ClientSettings,
+}
+
+impl DeprecationClient {
+    pub fn new(config: &Config) -> DeprecationClient {
+        DeprecationClient {
+            settings: ClientSettings::new(config),
+        }
+    }
+}
+
+impl MetadataClient for DeprecationClient {
+    type Metadata = Option<String>;
+
+    fn host(&self, name: &str) -> String {
+        self.settings.registries.host(name)
+    }
+
+    fn fetch(&self, name: &str, version: &str) -> Option<String> {
+        // a version which can't be checked isn't treated as deprecated
+        npm_cmd::view_deprecated(name, version, &self.settings.options)
+            .ok()
+            .flatten()
+    }
+}
+
+/// Fetches the day a version was published with `npm view`
+#[derive(Default)]
+pub struct PublishDayClient {
+    settings: ClientSettings,
+}
+
+impl PublishDayClient {
+    pub fn new(config: &Config) -> PublishDayClient {
+        PublishDayClient {
+            settings: ClientSettings::new(config),
+        }
+    }
+}
+
+impl MetadataClient for PublishDayClient {
+    type Metadata = Option<i64>;
+
+    fn host(&self, name: &str) -> String {
+        self.settings.registries.host(name)
+    }
+
+    fn fetch(&self, name: &str, version: &str) -> Option<i64> {
+        // a version which can't be checked (e.g. a prerelease) isn't treated as old
+        let version = Version::parse(version).ok()?;
+        let releases = npm_cmd::view_release_days(name, &self.settings.options).ok()?;
+
+        releases.get(&version).copied()
+    }
+}
+
+/// Fetches how often a package was downloaded over the last week, from npm's download counts
+#[derive(Default)]
+pub struct DownloadsClient {
+    options: ViewOptions,
+}
+
+impl DownloadsClient {
+    /// Uses the timeout and TLS settings from `config`
+    pub fn new(config: &Config) -> DownloadsClient {
+        DownloadsClient {
+            options: ViewOptions::new_for_curl(config),
+        }
+    }
+}
+
+impl MetadataClient for DownloadsClient {
+    type Metadata = Option<u64>;
+
+    // counts come from the one api, whichever registry the package is installed from
+    fn host(&self, _name: &str) -> String {
+        String::from(DOWNLOADS_HOST)
+    }
+
+    fn fetch(&self, name: &str, _version: &str) -> Option<u64> {
+        // offline, timed out (or a private package) just leaves the count unknown
+        npm_cmd::last_week_downloads(name, &self.options).ok()
+    }
+}
+
+/// An installed version published longer ago than --max-outdated-age-fail allows
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AgedDependency {
+    pub spec: String,
+    pub age_days: i64,
+}
+
+/// The `(name, version)` targets published more than `max_age_days` before `today`, going by
+/// their fetched publish day
+pub fn aged_dependencies(
+    targets: &[(&str, &str)],
+    publish_days: &[Option<i64>],
+    today: i64,
+    max_age_days: u64,
+) -> Vec<AgedDependency> {
+    targets
+        .iter()
+        .zip(publish_days)
+        .filter_map(|((name, version), day)| {
+            let age_days = today - (*day)?;
+
+            (age_days > max_age_days as i64).then(|| AgedDependency {
+                spec: format!("{}@{}", name, version),
+                age_days,
+            })
+        })
+        .collect()
+}
+
+/// The installed versions (going by the lockfile) of the declared dependencies published more
+/// than `max_age_days` before `today`, whether or not there's anything newer to bump to
+pub fn aged_installed<C: MetadataClient<Metadata = Option<i64>>>(
+    client: &C,
+    lockfile: &str,
+    declared: &BTreeMap<String, String>,
+    today: i64,
+    max_age_days: u64,
+    limits: Limits,
+) -> Result<Vec<AgedDependency>, serde_json::Error> {
+    let installed: Vec<(String, String)> = manifest::lockfile_versions(lockfile)?
+        .into_iter()
+        .filter(|(name, _)| declared.contains_key(name))
+        .collect();
+    let targets: Vec<(&str, &str)> = installed
+        .iter()
+        .map(|(name, version)| (name.as_str(), version.as_str()))
+        .collect();
+    let publish_days = fetch_targets(client, &targets, limits);
+
+    Ok(aged_dependencies(
+        &targets,
+        &publish_days,
+        today,
+        max_age_days,
+    ))
+}
+
+/// A package whose target version has been deprecated by its maintainers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeprecatedTarget {
+    pub spec: String,
+    pub message: String,
+}
+
+/// The packages whose fetched deprecation message marks their target version as deprecated
+pub fn deprecated_targets(
+    checked: &[(&Package, Option<String>)],
+    version: impl Fn(&Package) -> &str,
+) -> Vec<DeprecatedTarget> {
+    checked
+        .iter()
+        .filter_map(|(pkg, message)| {
+            Some(DeprecatedTarget {
+                spec: format!("{}@{}", pkg.name, version(pkg)),
+                message: message.clone()?,
+            })
+        })
+        .collect()
+}
+
+/// How many fetches can run at once, overall and against a single registry host
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    pub parallelism: usize,
+    pub per_host: Option<usize>,
+}
+
+// The queue of targets not yet started, and how many fetches are running per host
+struct Schedule {
+    pending: VecDeque<usize>,
+    active: HashMap<String, usize>,
+}
+
+impl Schedule {
+    // The next pending target whose host has room for another fetch
+    fn take(&mut self, hosts: &[String], per_host: usize) -> Option<usize> {
+        let position = self
+            .pending
+            .iter()
+            .position(|&i| self.active.get(&hosts[i]).copied().unwrap_or(0) < per_host)?;
+        let index = self.pending.remove(position)?;
+        *self.active.entry(hosts[index].clone()).or_insert(0) += 1;
+
+        Some(index)
+    }
+}
+
+/// Fetches metadata for every `(name, version)`, running at most `limits.parallelism` fetches
+/// at once, and no more than `limits.per_host` against the same registry. `on_fetched` is
+/// called with the number finished so far as each one completes.
+/// The results are in the same order as `targets`
+pub fn fetch_all<C: MetadataClient>(
+    client: &C,
+    targets: &[(&str, &str)],
+    limits: Limits,
+    on_fetched: impl Fn(usize) + Sync,
+) -> Vec<C::Metadata> {
+    let hosts: Vec<String> = targets.iter().map(|(name, _)| client.host(name)).collect();
+    let per_host = limits.per_host.unwrap_or(usize::MAX).max(1);
+    let workers = limits.parallelism.clamp(1, targets.len().max(1));
+
+    let schedule = Mutex::new(Schedule {
+        pending: (0..targets.len()).collect(),
+        active: HashMap::new(),
+    });
+    // signalled whenever a fetch finishes, freeing a slot for its host
+    let finished = Condvar::new();
+    let results = Mutex::new(Vec::with_capacity(targets.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = {
+                    let mut schedule = schedule.lock().unwrap();
+
+                    loop {
+                        if schedule.pending.is_empty() {
+                            break None;
+                        }
+
+                        match schedule.take(&hosts, per_host) {
+                            Some(index) => break Some(index),
+                            None => schedule = finished.wait(schedule).unwrap(),
+                        }
+                    }
+                };
+
+                let index = match index {
+                    Some(index) => index,
+                    None => break,
+                };
+
+                let (name, version) = targets[index];
+                let metadata = client.fetch(name, version);
+                {
+                    // reported while holding the lock, so the counts arrive in order
+                    let mut results = results.lock().unwrap();
+                    results.push((index, metadata));
+                    on_fetched(results.len());
+                }
+
+                if let Some(active) = schedule.lock().unwrap().active.get_mut(&hosts[index]) {
+                    *active -= 1;
+                }
+                finished.notify_all();
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, metadata)| metadata).collect()
+}
+
+/// Fetches metadata for the target version of each package, paired with the package
+pub fn attach<'a, C: MetadataClient>(
+    client: &C,
+    packages: &[&'a Package],
+    version: impl Fn(&Package) -> &str,
+    limits: Limits,
+) -> Vec<(&'a Package, C::Metadata)> {
+    let targets: Vec<(&str, &str)> = packages
+        .iter()
+        .map(|pkg| (pkg.name.as_str(), version(pkg)))
+        .collect();
+
+    let metadata = fetch_targets(client, &targets, limits);
+    packages.iter().copied().zip(metadata).collect()
+}
+
+/// Fetches metadata for each `(name, version)`, reporting progress as with `attach`
+pub fn fetch_targets<C: MetadataClient>(
+    client: &C,
+    targets: &[(&str, &str)],
+    limits: Limits,
+) -> Vec<C::Metadata> {
+    fetch_all(client, targets, limits, progress(targets.len()))
+}
+
+pub fn progress_line(fetched: usize, total: usize) -> String {
+    format!("Fetched {}/{} metadata", fetched, total)
+}
+
+/// Reports on stderr as fetches finish, so a long run doesn't look hung. Left out when stderr
+/// isn't a terminal (e.g. CI logs), where the overwritten line would only be noise
+fn progress(total: usize) -> impl Fn(usize) + Sync {
+    let is_shown = io::stderr().is_terminal();
+
+    move |fetched| {
+        if !is_shown {
+            return;
+        }
+
+        eprint!("\r{}", progress_line(fetched, total));
+
+        if fetched == total {
+            eprintln!();
+        }
+    }
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod client_tests {
+    use super::*;
+    use crate::utility::Args;
+    use clap::Parser;
+    use std::{path::PathBuf, time::Duration};
+
+    #[test]
+    fn verifies_tls_by_default() {
+        let config = Config::new_from_args(Args::default());
+
+        assert_eq!(ClientSettings::new(&config).options, ViewOptions::default());
+        assert_eq!(
+            DownloadsClient::new(&config).options,
+            ViewOptions::default()
+        );
+    }
+
+    #[test]
+    fn applies_tls_settings() {
+        let mut config = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--insecure",
+            "--registry-timeout",
+            "2000",
+        ]));
+        config.cacert = Some(PathBuf::from("/certs/proxy.pem"));
+        let expected = ViewOptions {
+            timeout: Some(Duration::from_millis(2000)),
+            flags: vec![
+                String::from("--strict-ssl=false"),
+                String::from("--cafile=/certs/proxy.pem"),
+            ],
+        };
+
+        assert_eq!(ClientSettings::new(&config).options, expected);
+        assert_eq!(
+            DownloadsClient::new(&config).options,
+            ViewOptions {
+                timeout: Some(Duration::from_millis(2000)),
+                flags: vec![
+                    String::from("--insecure"),
+                    String::from("--cacert=/certs/proxy.pem"),
+                ],
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod fetch_all_tests {
+    use super::*;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    #[derive(Default)]
+    struct MockClient {
+        active: AtomicUsize,
+        max_active: AtomicUsize,
+        calls: AtomicUsize,
+        // running and peak fetches for each host
+        hosts: Mutex<HashMap<String, (usize, usize)>>,
+    }
+
+    impl MockClient {
+        fn max_for_host(&self, host: &str) -> usize {
+            self.hosts
+                .lock()
+                .unwrap()
+                .get(host)
+                .map_or(0, |(_, max)| *max)
+        }
+    }
+
+    impl MetadataClient for MockClient {
+        type Metadata = String;
+
+        fn fetch(&self, name: &str, version: &str) -> String {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            {
+                let mut hosts = self.hosts.lock().unwrap();
+                let (running, max) = hosts.entry(self.host(name)).or_insert((0, 0));
+                *running += 1;
+                *max = (*max).max(*running);
+            }
+
+            thread::sleep(Duration::from_millis(20));
+
+            if let Some((running, _)) = self.hosts.lock().unwrap().get_mut(&self.host(name)) {
+                *running -= 1;
+            }
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            format!("{}@{}", name, version)
+        }
+
+        // the mock's packages are named host/pkg
+        fn host(&self, name: &str) -> String {
+            name.split('/').next().unwrap_or(name).into()
+        }
+    }
+
+    fn limits(parallelism: usize) -> Limits {
+        Limits {
+            parallelism,
+            per_host: None,
+        }
+    }
+
+    fn targets(count: usize) -> Vec<(String, String)> {
+        (0..count)
+            .map(|i| (format!("pkg-{}", i), format!("1.0.{}", i)))
+            .collect()
+    }
+
+    fn run_targets(client: &MockClient, owned: &[(String, String)], limits: Limits) -> Vec<String> {
+        let targets: Vec<(&str, &str)> = owned
+            .iter()
+            .map(|(name, version)| (name.as_str(), version.as_str()))
+            .collect();
+
+        fetch_all(client, &targets, limits, |_| {})
+    }
+
+    fn run(client: &MockClient, count: usize, parallelism: usize) -> Vec<String> {
+        run_targets(client, &targets(count), limits(parallelism))
+    }
+
+    fn hosted_targets(hosts: &[&str], per_host: usize) -> Vec<(String, String)> {
+        hosts
+            .iter()
+            .flat_map(|host| {
+                (0..per_host).map(move |i| (format!("{}/pkg-{}", host, i), String::from("1.0.0")))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn never_exceeds_parallelism() {
+        let client = MockClient::default();
+        run(&client, 12, 3);
+
+        assert_eq!(client.calls.load(Ordering::SeqCst), 12);
+        assert!(client.max_active.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn reports_progress_as_fetches_finish() {
+        let client = MockClient::default();
+        let owned = targets(6);
+        let targets: Vec<(&str, &str)> = owned
+            .iter()
+            .map(|(name, version)| (name.as_str(), version.as_str()))
+            .collect();
+        let reported = Mutex::new(vec![]);
+
+        fetch_all(&client, &targets, limits(3), |fetched| {
+            reported.lock().unwrap().push(fetched)
+        });
+
+        assert_eq!(reported.into_inner().unwrap(), vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn shows_progress() {
+        assert_eq!(progress_line(12, 40), "Fetched 12/40 metadata");
+    }
+
+    #[test]
+    fn fetches_concurrently() {
+        let client = MockClient::default();
+        run(&client, 8, 4);
+
+        assert!(client.max_active.load(Ordering::SeqCst) > 1);
+    }
+
+    #[test]
+    fn zero_parallelism_runs_serially() {
+        let client = MockClient::default();
+        run(&client, 3, 0);
+
+        assert_eq!(client.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(client.max_active.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn keeps_target_order() {
+        let client = MockClient::default();
+
+        assert_eq!(
+            run(&client, 5, 8),
+            vec![
+                "pkg-0@1.0.0",
+                "pkg-1@1.0.1",
+                "pkg-2@1.0.2",
+                "pkg-3@1.0.3",
+                "pkg-4@1.0.4"
+            ]
+        );
+    }
+
+    #[test]
+    fn same_host_respects_cap() {
+        let client = MockClient::default();
+        let targets = hosted_targets(&["registry.npmjs.org"], 8);
+        run_targets(
+            &client,
+            &targets,
+            Limits {
+                parallelism: 8,
+                per_host: Some(2),
+            },
+        );
+
+        assert_eq!(client.calls.load(Ordering::SeqCst), 8);
+        assert!(client.max_for_host("registry.npmjs.org") <= 2);
+    }
+
+    #[test]
+    fn different_hosts_run_in_parallel() {
+        let client = MockClient::default();
+        let targets = hosted_targets(
+            &["registry.npmjs.org", "npm.pkg.github.com", "npm.internal"],
+            3,
+        );
+        let results = run_targets(
+            &client,
+            &targets,
+            Limits {
+                parallelism: 8,
+                per_host: Some(1),
+            },
+        );
+
+        assert_eq!(results.len(), 9);
+        assert_eq!(results[4], "npm.pkg.github.com/pkg-1@1.0.0");
+        for host in ["registry.npmjs.org", "npm.pkg.github.com", "npm.internal"] {
+            assert_eq!(client.max_for_host(host), 1);
+        }
+        assert!(client.max_active.load(Ordering::SeqCst) > 1);
+    }
+
+    #[test]
+    fn handles_no_targets() {
+        let client = MockClient::default();
+
+        assert!(run(&client, 0, 8).is_empty());
+        assert_eq!(client.calls.load(Ordering::SeqCst), 0);
+    }
+}
+
+#[cfg(test)]
+mod deprecated_targets_tests {
+    use super::*;
+    use crate::utility::{Args, Config};
+
+    #[test]
+    fn lists_deprecated_targets() {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = None;
+        let request = Package::new(
+            String::from("location:request@2.88.2:request@2.88.0:request@2.88.2:test"),
+            &config,
+        )
+        .unwrap();
+        let debug = Package::new(
+            String::from("location:debug@4.3.4:debug@4.3.2:debug@4.3.4:test"),
+            &config,
+        )
+        .unwrap();
+        let checked = vec![
+            (&request, Some(String::from("request has been deprecated"))),
+            (&debug, None),
+        ];
+
+        assert_eq!(
+            deprecated_targets(&checked, |pkg| pkg.install_version.as_str()),
+            vec![DeprecatedTarget {
+                spec: String::from("request@2.88.2"),
+                message: String::from("request has been deprecated"),
+            }]
+        );
+    }
+
+    #[test]
+    fn nothing_deprecated() {
+        assert!(deprecated_targets(&[], |pkg| pkg.install_version.as_str()).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod aged_dependencies_tests {
+    use super::*;
+
+    const TARGETS: [(&str, &str); 3] = [
+        ("request", "2.88.2"),
+        ("debug", "4.3.4"),
+        ("left-pad", "1.3.0"),
+    ];
+
+    #[test]
+    fn lists_versions_past_the_max_age() {
+        let publish_days = [Some(100), Some(900), Some(600)];
+
+        assert_eq!(
+            aged_dependencies(&TARGETS, &publish_days, 1000, 500),
+            vec![AgedDependency {
+                spec: String::from("request@2.88.2"),
+                age_days: 900,
+            },]
+        );
+    }
+
+    #[test]
+    fn exactly_the_max_age_is_fine() {
+        let publish_days = [Some(500), None, None];
+
+        assert!(aged_dependencies(&TARGETS, &publish_days, 1000, 500).is_empty());
+    }
+
+    #[test]
+    fn unknown_publish_days_are_ignored() {
+        let publish_days = [None, None, None];
+
+        assert!(aged_dependencies(&TARGETS, &publish_days, 1000, 0).is_empty());
+    }
+
+    struct PublishDays(HashMap<&'static str, i64>);
+
+    impl MetadataClient for PublishDays {
+        type Metadata = Option<i64>;
+
+        fn fetch(&self, name: &str, _version: &str) -> Option<i64> {
+            self.0.get(name).copied()
+        }
+
+        fn host(&self, _name: &str) -> String {
+            String::from(DEFAULT_REGISTRY)
+        }
+    }
+
+    #[test]
+    fn checks_installed_versions_with_nothing_outdated() {
+        // every dependency is at its latest version, so npm outdated would report nothing
+        let lockfile = r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app" },
+                "node_modules/request": { "version": "2.88.2" },
+                "node_modules/debug": { "version": "4.3.4" },
+                "node_modules/ms": { "version": "2.1.3" }
+            }
+        }"#;
+        let declared: BTreeMap<String, String> = [("request", "^2.88.2"), ("debug", "^4.3.4")]
+            .iter()
+            .map(|(name, range)| (String::from(*name), String::from(*range)))
+            .collect();
+        // ms is old too, but only a dependency of debug
+        let client = PublishDays(
+            [("request", 100), ("debug", 900), ("ms", 50)]
+                .iter()
+                .copied()
+                .collect(),
+        );
+        let limits = Limits {
+            parallelism: 2,
+            per_host: None,
+        };
+
+        assert_eq!(
+            aged_installed(&client, lockfile, &declared, 1000, 500, limits).unwrap(),
+            vec![AgedDependency {
+                spec: String::from("request@2.88.2"),
+                age_days: 900,
+            }]
+        );
+    }
+}
+
+#[cfg(test)]
+mod registries_tests {
+    use super::*;
+
+    const NPMRC: &str = "
+        # company packages come from the internal registry
+        @acme:registry=https://npm.acme.dev/api/npm/
+        //npm.acme.dev/:_authToken=${NPM_TOKEN}
+        save-exact=true
+    ";
+
+    #[test]
+    fn default_without_npmrc() {
+        let registries = Registries::parse("");
+
+        assert_eq!(registries.host("debug"), "registry.npmjs.org");
+        assert_eq!(registries.host("@acme/ui"), "registry.npmjs.org");
+    }
+
+    #[test]
+    fn scoped_registry() {
+        let registries = Registries::parse(NPMRC);
+
+        assert_eq!(registries.host("@acme/ui"), "npm.acme.dev");
+        assert_eq!(registries.host("@babel/core"), "registry.npmjs.org");
+        assert_eq!(registries.host("debug"), "registry.npmjs.org");
+    }
+
+    #[test]
+    fn overridden_default_registry() {
+        let registries = Registries::parse("registry=http://localhost:4873");
+
+        assert_eq!(registries.host("debug"), "localhost:4873");
+    }
+}
@@ -0,0 +1,1374 @@
+use clap::ValueEnum;
+use handlebars::{no_escape, Handlebars, RenderError};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::color_codes::{self, paint};
+use crate::emojis::Icon;
+use crate::manifest::{bumped_range, DependencyChange, DEPENDENCY_SECTIONS};
+use crate::npm_cmd::{MajorHints, PeerConflict};
+use crate::outcome::RunOutcome;
+use crate::package::{NothingToBump, Package, SkipReason, UpgradeType};
+use crate::plan_diff::PlanDiff;
+use crate::utility::{self, Config, UpgradeStyle};
+use crate::version;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// List each update as "name current -> target"
+    #[default]
+    Text,
+    /// Show the updates as a unified diff of package.json
+    Diff,
+    /// Print the plan as JSON, e.g. for piping into jq
+    Json,
+    /// List the updates as a Markdown table, e.g. for a pull request description
+    Markdown,
+    /// Print the counts and bumped packages as KEY=value lines, e.g. for eval in a shell
+    Env,
+}
+
+/// The packages to list, only the majors with --report-only-major
+pub fn report_packages(packages: &[Package], config: &Config) -> Vec<Package> {
+    packages
+        .iter()
+        .filter(|pkg| !config.is_report_only_major || pkg.upgrade_type == UpgradeType::Major)
+        .cloned()
+        .collect()
+}
+
+/// Each update, followed by how the target relates to its declared range when `declared` (the
+/// ranges from package.json) is given, e.g. "(^3.2.6, out of range)"
+pub fn text(
+    packages: &[Package],
+    declared: Option<&BTreeMap<String, String>>,
+    config: &Config,
+) -> String {
+    let mut output = String::from("Updates required\n");
+
+    for pkg in packages.iter() {
+        let color = match pkg.upgrade_type {
+            UpgradeType::Safe => color_codes::CYAN,
+            UpgradeType::Major => color_codes::YELLOW,
+        };
+        let target = pkg.upgrade_version(&config.upgrade_style);
+
+        output.push_str(&format!(
+            "{} {} {} -> {}",
+            config.theme.icon(Icon::PointRight),
+            pkg.name,
+            pkg.current_version,
+            paint(target, color, config.is_color_enabled)
+        ));
+
+        if let Some(range) = declared.and_then(|declared| declared.get(&pkg.name)) {
+            match version::range_fit(range, target) {
+                Some(fit) => output.push_str(&format!(" ({}, {})", range, fit)),
+                None => output.push_str(&format!(" ({})", range)),
+            }
+        }
+
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Each update rendered with the `--template`, one per line. Unknown fields are an error rather
+/// than left blank
+pub fn templated(
+    packages: &[Package],
+    template: &str,
+    config: &Config,
+) -> Result<String, RenderError> {
+    let mut registry = Handlebars::new();
+    registry.set_strict_mode(true);
+    registry.register_escape_fn(no_escape);
+
+    let mut output = String::new();
+
+    for pkg in packages.iter() {
+        output.push_str(&registry.render_template(template, &template_fields(pkg, config))?);
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+/// The --commit-per-package message for a package, rendered like `templated`
+pub fn commit_message(
+    pkg: &Package,
+    template: &str,
+    config: &Config,
+) -> Result<String, RenderError> {
+    let mut registry = Handlebars::new();
+    registry.set_strict_mode(true);
+    registry.register_escape_fn(no_escape);
+
+    registry.render_template(template, &template_fields(pkg, config))
+}
+
+fn template_fields(pkg: &Package, config: &Config) -> Value {
+    json!({
+        "name": pkg.name,
+        "current": pkg.current_version,
+        "wanted": pkg.wanted_version,
+        "latest": pkg.latest_version,
+        "target": pkg.upgrade_version(&config.upgrade_style),
+        "type": match pkg.upgrade_type {
+            UpgradeType::Safe => "safe",
+            UpgradeType::Major => "major",
+        },
+    })
+}
+
+/// A Markdown table of the updates, without color codes so it can be pasted as is
+pub fn markdown(packages: &[Package], config: &Config) -> String {
+    let mut output =
+        String::from("| Package | Current | Target | Type |\n| --- | --- | --- | --- |\n");
+
+    for pkg in packages.iter() {
+        output.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            pkg.name,
+            pkg.current_version,
+            pkg.upgrade_version(&config.upgrade_style),
+            match pkg.upgrade_type {
+                UpgradeType::Safe => "safe",
+                UpgradeType::Major => "major",
+            }
+        ));
+    }
+
+    output
+}
+
+/// Packages with a newer major which are left for a manual bump, e.g. "4.x -> 5.x"
+pub fn majors_to_review(packages: &[Package], config: &Config) -> String {
+    let mut output = String::from("Major updates to review\n");
+
+    for pkg in packages.iter() {
+        let jump = version::major_jump(&pkg.current_version, &pkg.latest_version)
+            .map(|jump| format!(" ({})", jump))
+            .unwrap_or_default();
+
+        output.push_str(&format!(
+            "{} {} {} -> {}{}\n",
+            config.theme.icon(Icon::PointRight),
+            pkg.name,
+            pkg.current_version,
+            paint(
+                &pkg.latest_version,
+                color_codes::YELLOW,
+                config.is_color_enabled
+            ),
+            jump
+        ));
+    }
+
+    output
+}
+
+/// The packages npm reported which aren't being bumped, and why
+pub fn skipped(skipped: &[(Package, SkipReason)], config: &Config) -> String {
+    let mut output = String::from("Skipped\n");
+
+    for (pkg, reason) in skipped.iter() {
+        output.push_str(&format!(
+            "{} {} {} - {}\n",
+            config.theme.icon(Icon::PointRight),
+            pkg.name,
+            pkg.current_version,
+            reason
+        ));
+    }
+
+    output
+}
+
+/// Why nothing is being bumped, or `None` when up to date with --quiet-success
+pub fn nothing_to_bump(result: NothingToBump, config: &Config) -> Option<String> {
+    match result {
+        NothingToBump::UpToDate if config.is_quiet_success => None,
+        NothingToBump::UpToDate => Some(paint(
+            &config
+                .theme
+                .decorate("No outdated packages found", Icon::Rocket),
+            color_codes::GREEN,
+            config.is_color_enabled,
+        )),
+        NothingToBump::AllFiltered(count) => Some(paint(
+            &config.theme.decorate(
+                &format!(
+                    "No packages left to bump - the options passed (e.g. --include) excluded all {} updates, pass --dry-run --verbose to see why",
+                    count
+                ),
+                Icon::Warning,
+            ),
+            color_codes::YELLOW,
+            config.is_color_enabled,
+        )),
+    }
+}
+
+/// What changed since an earlier plan, e.g. "debug newly outdated, 4.3.4"
+pub fn plan_diff(plan_diff: &PlanDiff, config: &Config) -> String {
+    if plan_diff.is_empty() {
+        return String::from("No changes since the earlier plan\n");
+    }
+
+    let mut output = String::from("Changes since the earlier plan\n");
+    let icon = config.theme.icon(Icon::PointRight);
+
+    for pkg in plan_diff.added.iter() {
+        output.push_str(&format!(
+            "{} {} newly outdated, {}\n",
+            icon,
+            pkg.name,
+            pkg.upgrade_version(&config.upgrade_style)
+        ));
+    }
+
+    for pkg in plan_diff.resolved.iter() {
+        output.push_str(&format!("{} {} no longer outdated\n", icon, pkg.name));
+    }
+
+    for changed in plan_diff.changed.iter() {
+        output.push_str(&format!(
+            "{} {} target {} -> {}\n",
+            icon, changed.name, changed.before, changed.after
+        ));
+    }
+
+    output
+}
+
+/// Lists the dependencies which were added, removed or changed, under the given heading
+pub fn dependency_changes(heading: &str, changes: &[DependencyChange], config: &Config) -> String {
+    let mut output = format!("{}\n", heading);
+
+    for change in changes.iter() {
+        let description = match (&change.before, &change.after) {
+            (Some(before), Some(after)) => format!("{} -> {}", before, after),
+            (None, Some(after)) => format!("added {}", after),
+            (Some(before), None) => format!("removed {}", before),
+            (None, None) => continue,
+        };
+
+        output.push_str(&format!(
+            "{} {} {}\n",
+            config.theme.icon(Icon::PointRight),
+            change.name,
+            description
+        ));
+    }
+
+    output
+}
+
+/// Machine-readable version of the plan for other tooling to consume, with the skipped packages
+/// and why when --report-include-skipped is set
+pub fn plan(
+    packages: &[Package],
+    skipped: &[(Package, SkipReason)],
+    cmd_args: &[String],
+    config: &Config,
+) -> Value {
+    let packages: Vec<Value> = packages
+        .iter()
+        .map(|pkg| {
+            json!({
+                "name": pkg.name,
+                "current": pkg.current_version,
+                "wanted": pkg.wanted_version,
+                "latest": pkg.latest_version,
+                "target": pkg.upgrade_version(&config.upgrade_style),
+                "upgrade_type": match pkg.upgrade_type {
+                    UpgradeType::Safe => "safe",
+                    UpgradeType::Major => "major",
+                },
+            })
+        })
+        .collect();
+
+    let mut plan = json!({
+        "dry_run": config.is_dry_run,
+        "upgrade_style": match config.upgrade_style {
+            UpgradeStyle::Latest => "latest",
+            UpgradeStyle::Wanted => "wanted",
+        },
+        "packages": packages,
+        "install_args": cmd_args,
+    });
+
+    if config.is_report_include_skipped {
+        plan["skipped"] = skipped
+            .iter()
+            .map(|(pkg, reason)| {
+                json!({
+                    "name": pkg.name,
+                    "current": pkg.current_version,
+                    "target": pkg.upgrade_version(&config.upgrade_style),
+                    "reason": reason.to_string(),
+                })
+            })
+            .collect();
+    }
+
+    plan
+}
+
+/// Compact JSON on one line by default, pretty-printed with --json-pretty
+pub fn json(value: &Value, config: &Config) -> serde_json::Result<String> {
+    if config.is_json_pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+}
+
+/// `BUMPALL_`-prefixed KEY=value lines, quoted so they can be sourced or passed to eval
+pub fn env(outcome: &RunOutcome) -> String {
+    let lines = [
+        ("TOTAL_OUTDATED", outcome.total_outdated.to_string()),
+        ("SAFE_COUNT", outcome.upgrade_counts.safe.to_string()),
+        ("MAJOR_COUNT", outcome.upgrade_counts.major.to_string()),
+        ("BUMPED", outcome.bumped.join(",")),
+        (
+            "SKIPPED",
+            outcome
+                .skipped
+                .iter()
+                .map(|pkg| pkg.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(","),
+        ),
+    ];
+
+    lines
+        .iter()
+        .map(|(key, value)| format!("BUMPALL_{}={}\n", key, utility::quote_arg(value)))
+        .collect()
+}
+
+/// e.g. "3 safe, 1 major", with the counts colored to match the listed updates
+pub fn upgrade_counts(packages: &[Package], config: &Config) -> String {
+    let major = packages
+        .iter()
+        .filter(|pkg| pkg.upgrade_type == UpgradeType::Major)
+        .count();
+    let safe = packages.len() - major;
+
+    format!(
+        "{} safe, {} major",
+        paint(
+            &safe.to_string(),
+            color_codes::GREEN,
+            config.is_color_enabled
+        ),
+        paint(
+            &major.to_string(),
+            color_codes::YELLOW,
+            config.is_color_enabled
+        )
+    )
+}
+
+pub fn peer_conflicts(conflicts: &[PeerConflict], config: &Config) -> String {
+    let mut output = String::from("Peer dependency conflicts\n");
+
+    for conflict in conflicts.iter() {
+        output.push_str(&format!(
+            "{} {} {} -> {} (required by {})\n",
+            config.theme.icon(Icon::PointRight),
+            conflict.name,
+            conflict.installed_version.as_deref().unwrap_or("missing"),
+            paint(
+                &conflict.required_range,
+                color_codes::YELLOW,
+                config.is_color_enabled
+            ),
+            conflict.required_by
+        ));
+    }
+
+    output
+}
+
+/// The version jump for each major update, followed by whatever links npm knows about
+pub fn major_explanations(explained: &[(&Package, MajorHints)], config: &Config) -> String {
+    let mut output = String::from("Breaking change hints\n");
+
+    for (pkg, hints) in explained.iter() {
+        let target = pkg.upgrade_version(&config.upgrade_style);
+        // a MISSING current version falls back to what package.json asks for
+        let jump = version::major_jump(&pkg.current_version, target)
+            .or_else(|| version::major_jump(&pkg.wanted_version, target))
+            .unwrap_or_else(|| format!("{} -> {}", pkg.current_version, target));
+
+        output.push_str(&format!(
+            "{} {} {}\n",
+            config.theme.icon(Icon::PointRight),
+            pkg.name,
+            paint(&jump, color_codes::YELLOW, config.is_color_enabled)
+        ));
+
+        for (label, url) in [
+            ("changelog", &hints.changelog),
+            ("homepage", &hints.homepage),
+            ("issues", &hints.bugs),
+        ] {
+            if let Some(url) = url {
+                output.push_str(&format!("   {}: {}\n", label, url));
+            }
+        }
+    }
+
+    output
+}
+
+// Parses a single `"name": "range",` line from a dependency section
+fn parse_dependency_line(line: &str) -> Option<(String, String)> {
+    let entry = format!("{{{}}}", line.trim().trim_end_matches(','));
+    let entry: Map<String, Value> = serde_json::from_str(&entry).ok()?;
+    let (name, range) = entry.into_iter().next()?;
+
+    Some((name, range.as_str()?.to_string()))
+}
+
+/// Renders the changes `npm i` will make to the manifest as a unified diff
+pub fn diff(packages: &[Package], manifest: &str, config: &Config) -> String {
+    let targets: HashMap<&str, &str> = packages
+        .iter()
+        .map(|pkg| {
+            (
+                pkg.name.as_str(),
+                pkg.upgrade_version(&config.upgrade_style),
+            )
+        })
+        .collect();
+
+    let mut output = String::from("--- a/package.json\n+++ b/package.json\n");
+    let mut section = None;
+
+    for (idx, line) in manifest.lines().enumerate() {
+        let trimmed = line.trim();
+
+        if let Some(s) = DEPENDENCY_SECTIONS
+            .iter()
+            .find(|s| trimmed.starts_with(&format!("\"{}\"", s)))
+        {
+            section = Some(*s);
+            continue;
+        }
+
+        if trimmed.starts_with('}') {
+            section = None;
+            continue;
+        }
+
+        // peer ranges aren't touched by npm i
+        if section.is_none() || section == Some("peerDependencies") {
+            continue;
+        }
+
+        let (name, range) = match parse_dependency_line(trimmed) {
+            Some(entry) => entry,
+            None => continue,
+        };
+
+        let version = match targets.get(name.as_str()) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let new_range = bumped_range(&range, version, config);
+        if new_range == range {
+            continue;
+        }
+
+        let quoted_range = format!("\"{}\"", range);
+        let new_line = match line.rfind(&quoted_range) {
+            Some(pos) => format!(
+                "{}\"{}\"{}",
+                &line[..pos],
+                new_range,
+                &line[pos + quoted_range.len()..]
+            ),
+            None => continue,
+        };
+
+        output.push_str(&format!(
+            "@@ -{0} +{0} @@\n-{1}\n+{2}\n",
+            idx + 1,
+            line,
+            new_line
+        ));
+    }
+
+    output
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod diff_tests {
+    use super::*;
+    use crate::utility::{Args, SavePrefix};
+
+    const MANIFEST: &str = r#"{
+  "name": "test",
+  "version": "1.0.0",
+  "dependencies": {
+    "@jonshort/cenv": "^0.0.6",
+    "left-pad": "~1.2.0",
+    "polished": "^3.6.5"
+  },
+  "devDependencies": {
+    "debug": "4.3.2"
+  },
+  "peerDependencies": {
+    "polished": "^3.0.0"
+  }
+}"#;
+
+    fn packages(config: &Config) -> Vec<Package> {
+        vec![
+            String::from("location:left-pad@1.2.3:left-pad@1.2.0:left-pad@1.3.0:test"),
+            String::from("location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test"),
+            String::from("location:debug@4.3.2:debug@4.3.2:debug@4.3.4:test"),
+        ]
+        .into_iter()
+        .map(|s| Package::new(s, config).unwrap())
+        // as in a run, which never installs those already at their target
+        .filter(|pkg| pkg.current_version != pkg.upgrade_version(&config.upgrade_style))
+        .collect()
+    }
+
+    #[test]
+    fn renders_hunks_for_wanted() {
+        let config = Config::new_from_args(Args {
+            format: OutputFormat::Diff,
+            ..Args::default()
+        });
+        let expected = r#"--- a/package.json
++++ b/package.json
+@@ -6 +6 @@
+-    "left-pad": "~1.2.0",
++    "left-pad": "^1.2.3",
+@@ -7 +7 @@
+-    "polished": "^3.6.5"
++    "polished": "^3.7.2"
+"#;
+
+        assert_eq!(diff(&packages(&config), MANIFEST, &config), expected);
+    }
+
+    #[test]
+    fn renders_hunks_for_latest() {
+        let config = Config::new_from_args(Args {
+            format: OutputFormat::Diff,
+            latest: true,
+            ..Args::default()
+        });
+        let expected = r#"--- a/package.json
++++ b/package.json
+@@ -6 +6 @@
+-    "left-pad": "~1.2.0",
++    "left-pad": "^1.3.0",
+@@ -7 +7 @@
+-    "polished": "^3.6.5"
++    "polished": "^4.2.2"
+@@ -10 +10 @@
+-    "debug": "4.3.2"
++    "debug": "^4.3.4"
+"#;
+
+        assert_eq!(diff(&packages(&config), MANIFEST, &config), expected);
+    }
+
+    #[test]
+    fn renders_hunks_with_save_prefix() {
+        let config = Config::new_from_args(Args {
+            format: OutputFormat::Diff,
+            latest: true,
+            save_prefix: Some(SavePrefix::Tilde),
+            ..Args::default()
+        });
+        let expected = r#"--- a/package.json
++++ b/package.json
+@@ -6 +6 @@
+-    "left-pad": "~1.2.0",
++    "left-pad": "~1.3.0",
+@@ -7 +7 @@
+-    "polished": "^3.6.5"
++    "polished": "~4.2.2"
+@@ -10 +10 @@
+-    "debug": "4.3.2"
++    "debug": "~4.3.4"
+"#;
+
+        assert_eq!(diff(&packages(&config), MANIFEST, &config), expected);
+    }
+
+    #[test]
+    fn renders_hunks_keeping_range_width() {
+        let config = Config::new_from_args(Args {
+            format: OutputFormat::Diff,
+            latest: true,
+            keep_range_width: true,
+            ..Args::default()
+        });
+        let expected = r#"--- a/package.json
++++ b/package.json
+@@ -6 +6 @@
+-    "left-pad": "~1.2.0",
++    "left-pad": "~1.3.0",
+@@ -7 +7 @@
+-    "polished": "^3.6.5"
++    "polished": "^4.2.2"
+@@ -10 +10 @@
+-    "debug": "4.3.2"
++    "debug": "4.3.4"
+"#;
+
+        assert_eq!(diff(&packages(&config), MANIFEST, &config), expected);
+    }
+
+    #[test]
+    fn parses_dependency_lines() {
+        assert_eq!(
+            parse_dependency_line(r#"    "@jonshort/cenv": "^0.0.6","#),
+            Some((String::from("@jonshort/cenv"), String::from("^0.0.6")))
+        );
+        assert_eq!(parse_dependency_line(r#"  "dependencies": {"#), None);
+        assert_eq!(parse_dependency_line("}"), None);
+    }
+}
+
+#[cfg(test)]
+mod plan_tests {
+    use super::*;
+    use crate::utility::Args;
+
+    #[test]
+    fn lists_packages_and_install_args() {
+        let config = Config::new_from_args(Args {
+            latest: true,
+            ..Args::default()
+        });
+        let packages = vec![Package::new(
+            String::from("location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test"),
+            &config,
+        )
+        .unwrap()];
+        let cmd_args = vec![String::from("polished@4.2.2")];
+
+        let expected = json!({
+            "dry_run": true,
+            "upgrade_style": "latest",
+            "packages": [{
+                "name": "polished",
+                "current": "3.6.5",
+                "wanted": "3.7.2",
+                "latest": "4.2.2",
+                "target": "4.2.2",
+                "upgrade_type": "major",
+            }],
+            "install_args": ["polished@4.2.2"],
+        });
+
+        assert_eq!(plan(&packages, &[], &cmd_args, &config), expected);
+    }
+
+    #[test]
+    fn lists_skipped_with_reasons_when_included() {
+        let mut config = Config::new_from_args(Args {
+            report_include_skipped: true,
+            ..Args::default()
+        });
+        config.current_dir_name = Some(String::from("test"));
+        let skipped: Vec<(Package, SkipReason)> = [
+            "location:polished@3.7.2:polished@3.7.2:polished@4.2.2:test",
+            "location:ms@2.1.3:ms@2.1.2:ms@2.1.3:other",
+        ]
+        .iter()
+        .map(|src| {
+            let pkg = Package::new(String::from(*src), &config).unwrap();
+            let reason = pkg.skip.unwrap();
+            (pkg, reason)
+        })
+        .collect();
+
+        let result = plan(&[], &skipped, &[], &config);
+
+        assert_eq!(
+            result["skipped"],
+            json!([
+                {
+                    "name": "polished",
+                    "current": "3.7.2",
+                    "target": "3.7.2",
+                    "reason": "newer versions are outside the declared range (see --latest)",
+                },
+                {
+                    "name": "ms",
+                    "current": "2.1.2",
+                    "target": "2.1.3",
+                    "reason": "dependency of another workspace",
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn leaves_out_skipped_by_default() {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = Some(String::from("test"));
+        let pkg = Package::new(
+            String::from("location:ms@2.1.3:ms@2.1.2:ms@2.1.3:other"),
+            &config,
+        )
+        .unwrap();
+        let skipped = vec![(pkg, SkipReason::WorkspaceDependency)];
+
+        assert_eq!(plan(&[], &skipped, &[], &config).get("skipped"), None);
+    }
+}
+
+#[cfg(test)]
+mod templated_tests {
+    use super::*;
+    use crate::utility::Args;
+
+    fn packages(config: &Config) -> Vec<Package> {
+        vec![
+            Package::new(
+                String::from("location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test"),
+                config,
+            )
+            .unwrap(),
+            Package::new(
+                String::from(
+                    "location:@jonshort/cenv@1.0.3:@jonshort/cenv@1.0.2:@jonshort/cenv@1.0.3:test",
+                ),
+                config,
+            )
+            .unwrap(),
+        ]
+    }
+
+    #[test]
+    fn renders_each_package() {
+        let config = Config::new_from_args(Args {
+            latest: true,
+            ..Args::default()
+        });
+
+        assert_eq!(
+            templated(
+                &packages(&config),
+                "{{name}}: {{current}} -> {{latest}} (wanted {{wanted}}, {{type}})",
+                &config
+            )
+            .unwrap(),
+            "polished: 3.6.5 -> 4.2.2 (wanted 3.7.2, major)\n@jonshort/cenv: 1.0.2 -> 1.0.3 (wanted 1.0.3, safe)\n"
+        );
+    }
+
+    #[test]
+    fn supports_helpers_without_escaping() {
+        let config = Config::new_from_args(Args {
+            latest: true,
+            ..Args::default()
+        });
+
+        assert_eq!(
+            templated(
+                &packages(&config),
+                "{{#if (eq type \"major\")}}<{{name}}@{{target}}>{{else}}{{name}}{{/if}}",
+                &config
+            )
+            .unwrap(),
+            "<polished@4.2.2>\n@jonshort/cenv\n"
+        );
+    }
+
+    #[test]
+    fn errors_on_unknown_fields() {
+        let config = Config::default();
+
+        assert!(templated(&packages(&config), "{{nmae}}", &config).is_err());
+        assert_eq!(templated(&[], "{{nmae}}", &config).unwrap(), "");
+    }
+}
+
+#[cfg(test)]
+mod commit_message_tests {
+    use super::*;
+    use crate::utility::{Args, DEFAULT_COMMIT_MESSAGE};
+    use clap::Parser;
+
+    fn config() -> Config {
+        let mut config = Config::new_from_args(Args::parse_from(["npm-bumpall", "--latest"]));
+        config.current_dir_name = None;
+        config
+    }
+
+    fn pkg(src: &str) -> Package {
+        Package::new(String::from(src), &config()).unwrap()
+    }
+
+    #[test]
+    fn renders_the_default() {
+        let debug = pkg("location:debug@3.2.7:debug@3.1.0:debug@4.3.4:test");
+
+        assert_eq!(
+            commit_message(&debug, DEFAULT_COMMIT_MESSAGE, &config()).unwrap(),
+            "Bump debug from 3.1.0 to 4.3.4"
+        );
+    }
+
+    #[test]
+    fn renders_each_field() {
+        let debug = pkg("location:debug@3.2.7:debug@3.1.0:debug@4.3.4:test");
+        let template = "chore(deps): {{type}} {{name}} {{current}} -> {{target}} (wanted {{wanted}}, latest {{latest}})";
+
+        assert_eq!(
+            commit_message(&debug, template, &config()).unwrap(),
+            "chore(deps): major debug 3.1.0 -> 4.3.4 (wanted 3.2.7, latest 4.3.4)"
+        );
+    }
+
+    #[test]
+    fn leaves_scoped_names_unescaped() {
+        let cenv =
+            pkg("location:@jonshort/cenv@1.0.3:@jonshort/cenv@1.0.2:@jonshort/cenv@1.0.3:test");
+
+        assert_eq!(
+            commit_message(&cenv, "{{name}}@{{target}}", &config()).unwrap(),
+            "@jonshort/cenv@1.0.3"
+        );
+    }
+
+    #[test]
+    fn errors_on_unknown_fields() {
+        let debug = pkg("location:debug@3.2.7:debug@3.1.0:debug@4.3.4:test");
+
+        assert!(commit_message(&debug, "Bump {{package}}", &config()).is_err());
+    }
+}
+
+#[cfg(test)]
+mod report_packages_tests {
+    use super::*;
+    use crate::utility::Args;
+    use clap::Parser;
+
+    fn packages(config: &Config) -> Vec<Package> {
+        [
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:@jonshort/cenv@1.0.3:@jonshort/cenv@1.0.2:@jonshort/cenv@1.0.3:test",
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test",
+        ]
+        .iter()
+        .map(|s| Package::new(String::from(*s), config).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn lists_everything_by_default() {
+        let config = Config::new_from_args(Args::parse_from(["npm-bumpall", "--latest"]));
+
+        assert_eq!(report_packages(&packages(&config), &config).len(), 3);
+    }
+
+    #[test]
+    fn only_majors_when_set() {
+        let config = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--latest",
+            "--report-only-major",
+        ]));
+        let report = report_packages(&packages(&config), &config);
+
+        let expected = "\
+| Package | Current | Target | Type |
+| --- | --- | --- | --- |
+| polished | 3.6.5 | 4.2.2 | major |
+| debug | 3.2.6 | 4.3.4 | major |
+";
+
+        assert_eq!(markdown(&report, &config), expected);
+    }
+}
+
+#[cfg(test)]
+mod markdown_tests {
+    use super::*;
+    use crate::utility::Args;
+
+    #[test]
+    fn lists_updates_as_a_table() {
+        let config = Config::new_from_args(Args {
+            latest: true,
+            ..Args::default()
+        });
+        let packages: Vec<Package> = [
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:@jonshort/cenv@1.0.3:@jonshort/cenv@1.0.2:@jonshort/cenv@1.0.3:test",
+        ]
+        .iter()
+        .map(|s| Package::new(String::from(*s), &config).unwrap())
+        .collect();
+
+        let expected = "\
+| Package | Current | Target | Type |
+| --- | --- | --- | --- |
+| polished | 3.6.5 | 4.2.2 | major |
+| @jonshort/cenv | 1.0.2 | 1.0.3 | safe |
+";
+
+        assert_eq!(markdown(&packages, &config), expected);
+    }
+
+    #[test]
+    fn header_only_without_updates() {
+        let config = Config::default();
+
+        assert_eq!(
+            markdown(&[], &config),
+            "| Package | Current | Target | Type |\n| --- | --- | --- | --- |\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+    use super::*;
+    use crate::utility::Args;
+
+    #[test]
+    fn compact_by_default() {
+        let config = Config::default();
+        let value = json!({ "packages": [{ "name": "polished" }] });
+
+        let output = json(&value, &config).unwrap();
+
+        assert_eq!(output, r#"{"packages":[{"name":"polished"}]}"#);
+        assert!(!output.contains('\n'));
+    }
+
+    #[test]
+    fn pretty_is_indented() {
+        let config = Config::new_from_args(Args {
+            json_pretty: true,
+            ..Args::default()
+        });
+        let value = json!({ "packages": [{ "name": "polished" }] });
+
+        let output = json(&value, &config).unwrap();
+
+        assert!(output.contains("\n  \"packages\": [\n    {"));
+        assert_eq!(serde_json::from_str::<Value>(&output).unwrap(), value);
+    }
+}
+
+#[cfg(test)]
+mod env_tests {
+    use super::*;
+    use crate::outcome::{SkippedPackage, UpgradeCounts};
+
+    #[test]
+    fn lists_counts_and_packages() {
+        let outcome = RunOutcome {
+            total_outdated: 4,
+            upgrade_counts: UpgradeCounts { safe: 2, major: 1 },
+            bumped: vec![String::from("react"), String::from("@types/node")],
+            skipped: vec![SkippedPackage {
+                name: String::from("lodash"),
+                reason: String::from("already at the target version"),
+            }],
+            install_status: None,
+        };
+
+        assert_eq!(
+            env(&outcome),
+            "BUMPALL_TOTAL_OUTDATED=4\n\
+             BUMPALL_SAFE_COUNT=2\n\
+             BUMPALL_MAJOR_COUNT=1\n\
+             BUMPALL_BUMPED=react,@types/node\n\
+             BUMPALL_SKIPPED=lodash\n"
+        );
+    }
+
+    #[test]
+    fn quotes_special_characters() {
+        let outcome = RunOutcome {
+            total_outdated: 2,
+            upgrade_counts: UpgradeCounts { safe: 2, major: 0 },
+            bumped: vec![String::from("it's"), String::from("$(rm -rf)")],
+            skipped: vec![],
+            install_status: None,
+        };
+
+        assert_eq!(
+            env(&outcome),
+            "BUMPALL_TOTAL_OUTDATED=2\n\
+             BUMPALL_SAFE_COUNT=2\n\
+             BUMPALL_MAJOR_COUNT=0\n\
+             BUMPALL_BUMPED='it'\\''s,$(rm -rf)'\n\
+             BUMPALL_SKIPPED=''\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod upgrade_counts_tests {
+    use super::*;
+    use crate::utility::Args;
+
+    fn packages(config: &Config) -> Vec<Package> {
+        vec![
+            String::from("location:left-pad@1.3.0:left-pad@1.2.0:left-pad@1.3.0:test"),
+            String::from("location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test"),
+            String::from("location:debug@4.3.4:debug@4.3.2:debug@4.3.4:test"),
+        ]
+        .into_iter()
+        .map(|s| Package::new(s, config).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn colors_counts() {
+        let config = Config::new_from_args(Args {
+            latest: true,
+            ..Args::default()
+        });
+
+        assert_eq!(
+            upgrade_counts(&packages(&config), &config),
+            String::from("\x1b[32m2\x1b[0m safe, \x1b[33m1\x1b[0m major")
+        );
+    }
+
+    #[test]
+    fn plain_counts_without_color() {
+        let config = Config::new_from_args(Args {
+            latest: true,
+            no_color: true,
+            ..Args::default()
+        });
+        let counts = upgrade_counts(&packages(&config), &config);
+
+        assert_eq!(counts, String::from("2 safe, 1 major"));
+        assert!(!counts.contains('\x1b'));
+    }
+
+    #[test]
+    fn text_respects_color_setting() {
+        let config = Config::new_from_args(Args {
+            no_color: true,
+            ..Args::default()
+        });
+
+        assert!(!text(&packages(&config), None, &config).contains('\x1b'));
+    }
+}
+
+#[cfg(test)]
+mod text_tests {
+    use super::*;
+    use crate::emojis::POINT_RIGHT;
+    use crate::utility::Args;
+
+    #[test]
+    fn shows_the_declared_range_fit() {
+        let mut config = Config::new_from_args(Args {
+            latest: true,
+            no_color: true,
+            ..Args::default()
+        });
+        config.current_dir_name = None;
+        let packages: Vec<Package> = [
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:debug@4.3.4:debug@4.3.2:debug@4.3.4:test",
+            "location:cenv@1.0.3:cenv@1.0.2:cenv@1.0.3:test",
+            "location:ms@2.1.3:ms@2.1.2:ms@2.1.3:test",
+        ]
+        .iter()
+        .map(|src| Package::new(String::from(*src), &config).unwrap())
+        .collect();
+        let declared: BTreeMap<String, String> = [
+            ("polished", "^3.6.0"),
+            ("debug", "^4.3.2"),
+            ("cenv", "github:JonShort/cenv"),
+        ]
+        .iter()
+        .map(|(name, range)| (String::from(*name), String::from(*range)))
+        .collect();
+
+        let expected = format!(
+            "Updates required
+{0} polished 3.6.5 -> 4.2.2 (^3.6.0, out of range)
+{0} debug 4.3.2 -> 4.3.4 (^4.3.2, in range)
+{0} cenv 1.0.2 -> 1.0.3 (github:JonShort/cenv)
+{0} ms 2.1.2 -> 2.1.3
+",
+            &POINT_RIGHT
+        );
+
+        assert_eq!(text(&packages, Some(&declared), &config), expected);
+    }
+}
+
+#[cfg(test)]
+mod major_explanations_tests {
+    use super::*;
+    use crate::emojis::POINT_RIGHT;
+    use crate::utility::Args;
+
+    #[test]
+    fn lists_jump_and_hints() {
+        let config = Config::new_from_args(Args {
+            latest: true,
+            no_color: true,
+            ..Args::default()
+        });
+        let polished = Package::new(
+            String::from("location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test"),
+            &config,
+        )
+        .unwrap();
+        let debug = Package::new(
+            String::from("location:debug@3.2.7:MISSING:debug@4.3.4:test"),
+            &config,
+        )
+        .unwrap();
+        let hints = MajorHints {
+            changelog: Some(String::from(
+                "https://github.com/styled-components/polished/releases",
+            )),
+            homepage: Some(String::from("https://polished.js.org")),
+            ..MajorHints::default()
+        };
+        let expected = format!(
+            "Breaking change hints
+{0} polished 3.x -> 4.x
+   changelog: https://github.com/styled-components/polished/releases
+   homepage: https://polished.js.org
+{0} debug 3.x -> 4.x
+",
+            &POINT_RIGHT
+        );
+
+        assert_eq!(
+            major_explanations(
+                &[(&polished, hints), (&debug, MajorHints::default())],
+                &config
+            ),
+            expected
+        );
+    }
+}
+
+#[cfg(test)]
+mod skipped_tests {
+    use super::*;
+    use crate::emojis::POINT_RIGHT;
+    use crate::utility::Args;
+
+    #[test]
+    fn lists_each_reason() {
+        let mut config = Config::new_from_args(Args {
+            include: Some(String::from("polished")),
+            ..Args::default()
+        });
+        config.current_dir_name = Some(String::from("test"));
+        let packages: Vec<(Package, SkipReason)> = [
+            "location:polished@3.7.2:polished@3.7.2:polished@4.2.2:test",
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test",
+            "location:ms@2.1.3:ms@2.1.2:ms@2.1.3:other",
+        ]
+        .iter()
+        .map(|src| {
+            let pkg = Package::new(String::from(*src), &config).unwrap();
+            let reason = pkg.skip.unwrap();
+            (pkg, reason)
+        })
+        .collect();
+
+        let expected = format!(
+            "Skipped
+{0} polished 3.7.2 - newer versions are outside the declared range (see --latest)
+{0} debug 3.2.6 - excluded by --include
+{0} ms 2.1.2 - dependency of another workspace
+",
+            &POINT_RIGHT
+        );
+
+        assert_eq!(skipped(&packages, &config), expected);
+    }
+}
+
+#[cfg(test)]
+mod nothing_to_bump_tests {
+    use super::*;
+    use crate::emojis::{ROCKET, WARNING};
+    use crate::utility::Args;
+    use clap::Parser;
+
+    #[test]
+    fn silent_when_up_to_date_with_quiet_success() {
+        let config = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--quiet-success",
+            "--no-color",
+        ]));
+
+        assert_eq!(nothing_to_bump(NothingToBump::UpToDate, &config), None);
+    }
+
+    #[test]
+    fn still_warns_when_all_filtered_with_quiet_success() {
+        let config = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--quiet-success",
+            "--no-color",
+        ]));
+
+        assert_eq!(
+            nothing_to_bump(NothingToBump::AllFiltered(2), &config),
+            Some(format!(
+                "{0} No packages left to bump - the options passed (e.g. --include) excluded all 2 updates, pass --dry-run --verbose to see why {0}",
+                WARNING
+            ))
+        );
+    }
+
+    #[test]
+    fn up_to_date() {
+        let config = Config::new_from_args(Args::parse_from(["npm-bumpall", "--no-color"]));
+
+        assert_eq!(
+            nothing_to_bump(NothingToBump::UpToDate, &config),
+            Some(format!("{0} No outdated packages found {0}", ROCKET))
+        );
+    }
+}
+
+#[cfg(test)]
+mod plan_diff_tests {
+    use super::*;
+    use crate::emojis::POINT_RIGHT;
+    use crate::plan_diff::ChangedTarget;
+    use crate::utility::Args;
+
+    #[test]
+    fn describes_each_change() {
+        let config = Config::new_from_args(Args::default());
+        let pkg = |s: &str| Package::new(String::from(s), &config).unwrap();
+        let changes = PlanDiff {
+            added: vec![pkg("location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test")],
+            resolved: vec![pkg("location:ms@2.1.3:ms@2.1.2:ms@2.1.3:test")],
+            changed: vec![ChangedTarget {
+                name: String::from("polished"),
+                before: String::from("3.7.1"),
+                after: String::from("3.7.2"),
+            }],
+        };
+        let expected = format!(
+            "Changes since the earlier plan
+{0} debug newly outdated, 3.2.7
+{0} ms no longer outdated
+{0} polished target 3.7.1 -> 3.7.2
+",
+            &POINT_RIGHT
+        );
+
+        assert_eq!(plan_diff(&changes, &config), expected);
+    }
+
+    #[test]
+    fn says_when_nothing_changed() {
+        assert_eq!(
+            plan_diff(&PlanDiff::default(), &Config::default()),
+            "No changes since the earlier plan\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod dependency_changes_tests {
+    use super::*;
+    use crate::emojis::POINT_RIGHT;
+    use crate::utility::Args;
+
+    #[test]
+    fn describes_each_change() {
+        let config = Config::new_from_args(Args::default());
+        let changes = vec![
+            DependencyChange {
+                name: String::from("@jonshort/cenv"),
+                before: None,
+                after: Some(String::from("^0.0.6")),
+            },
+            DependencyChange {
+                name: String::from("debug"),
+                before: Some(String::from("^4.3.2")),
+                after: Some(String::from("^4.3.4")),
+            },
+            DependencyChange {
+                name: String::from("left-pad"),
+                before: Some(String::from("^1.2.0")),
+                after: None,
+            },
+        ];
+        let expected = format!(
+            "Declared ranges changed since main
+{0} @jonshort/cenv added ^0.0.6
+{0} debug ^4.3.2 -> ^4.3.4
+{0} left-pad removed ^1.2.0
+",
+            &POINT_RIGHT
+        );
+
+        assert_eq!(
+            dependency_changes("Declared ranges changed since main", &changes, &config),
+            expected
+        );
+    }
+}
+
+#[cfg(test)]
+mod majors_to_review_tests {
+    use super::*;
+    use crate::emojis::POINT_RIGHT;
+    use crate::utility::Args;
+
+    #[test]
+    fn lists_latest_with_jump() {
+        let config = Config::new_from_args(Args {
+            no_color: true,
+            ..Args::default()
+        });
+        let packages: Vec<Package> = [
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:debug@3.2.7:MISSING:debug@4.3.4:test",
+        ]
+        .iter()
+        .map(|s| Package::new(String::from(*s), &config).unwrap())
+        .collect();
+        let expected = format!(
+            "Major updates to review
+{0} polished 3.6.5 -> 4.2.2 (3.x -> 4.x)
+{0} debug MISSING -> 4.3.4
+",
+            &POINT_RIGHT
+        );
+
+        assert_eq!(majors_to_review(&packages, &config), expected);
+    }
+}
@@ -0,0 +1,59 @@
+use std::{error::Error, process};
+
+/// The contents of `path` (relative to the current directory) at the given revision
+pub fn show_file(rev: &str, path: &str) -> Result<String, Box<dyn Error>> {
+    let output = process::Command::new("git")
+        .arg("show")
+        .arg(format!("{}:./{}", rev, path))
+        .output()?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Unable to read {} at {} - {}",
+            path,
+            rev,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Commits `paths` (relative to the current directory) with the given message, staging them
+/// first. Anything else already staged is left out of the commit
+pub fn commit(paths: &[&str], message: &str) -> Result<(), Box<dyn Error>> {
+    let add = process::Command::new("git")
+        .arg("add")
+        .arg("--")
+        .args(paths)
+        .output()?;
+
+    if !add.status.success() {
+        return Err(format!(
+            "Unable to stage {} - {}",
+            paths.join(", "),
+            String::from_utf8_lossy(&add.stderr).trim()
+        )
+        .into());
+    }
+
+    let commit = process::Command::new("git")
+        .arg("commit")
+        .arg("--message")
+        .arg(message)
+        .arg("--")
+        .args(paths)
+        .output()?;
+
+    if !commit.status.success() {
+        return Err(format!(
+            "Unable to commit \"{}\" - {}",
+            message,
+            String::from_utf8_lossy(&commit.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(())
+}
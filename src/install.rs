@@ -0,0 +1,974 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    process::ExitStatus,
+    time::{Duration, Instant},
+};
+
+use crate::deadline::{self, Deadline};
+use crate::emojis::Icon;
+use crate::git;
+use crate::manifest;
+use crate::npm_cmd::{self, InstallOutput};
+use crate::package::{InstallSpec, Package, SkipReason, UpgradeType};
+use crate::retry;
+use crate::utility::{label_lines, print_labelled, print_message, Config, UpgradeStyle};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstallResult {
+    pub spec: String,
+    pub success: bool,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct InstallSummary {
+    pub failed: Vec<String>,
+    pub installed: Vec<String>,
+}
+
+impl InstallSummary {
+    /// The final message for the install, e.g. "Bumped 8 of 10 packages; 2 failed (...)"
+    pub fn message(&self) -> (String, Icon) {
+        let total = self.installed.len() + self.failed.len();
+
+        if self.failed.is_empty() {
+            (String::from("All packages bumped"), Icon::Trophy)
+        } else if self.installed.is_empty() {
+            (
+                format!(
+                    "Bumped 0 of {} packages; all failed - try running manually",
+                    total
+                ),
+                Icon::Cross,
+            )
+        } else {
+            (
+                format!(
+                    "Bumped {} of {} packages; {} failed ({}) - try running these manually",
+                    self.installed.len(),
+                    total,
+                    self.failed.len(),
+                    self.failed.join(", ")
+                ),
+                Icon::Warning,
+            )
+        }
+    }
+}
+
+/// A package which would be installed at more than one version
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpecConflict {
+    pub name: String,
+    pub specs: Vec<String>,
+}
+
+impl fmt::Display for SpecConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.specs.join(", "))
+    }
+}
+
+/// A package still outdated after being bumped, e.g. because a peer dependency held it back
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unapplied {
+    pub name: String,
+    pub expected: String,
+    pub found: String,
+}
+
+impl fmt::Display for Unapplied {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (expected {}, found {})",
+            self.name, self.expected, self.found
+        )
+    }
+}
+
+// The package name from a `name@version` spec, keeping the leading @ of scoped packages
+fn spec_name(spec: &str) -> &str {
+    match spec.get(1..).and_then(|rest| rest.find('@')) {
+        Some(idx) => &spec[..idx + 1],
+        None => spec,
+    }
+}
+
+/// Finds packages given more than once with different versions, which npm would otherwise
+/// resolve by picking one of them
+pub fn find_conflicts(specs: &[String]) -> Vec<SpecConflict> {
+    let mut by_name: Vec<SpecConflict> = vec![];
+
+    for spec in specs.iter() {
+        let name = spec_name(spec);
+
+        match by_name.iter_mut().find(|c| c.name == name) {
+            Some(entry) if !entry.specs.contains(spec) => entry.specs.push(spec.into()),
+            Some(_) => {}
+            None => by_name.push(SpecConflict {
+                name: name.into(),
+                specs: vec![spec.into()],
+            }),
+        }
+    }
+
+    by_name.retain(|c| c.specs.len() > 1);
+    by_name
+}
+
+fn install_with_retries(
+    specs: &[String],
+    config: &Config,
+    deadline: &Deadline,
+) -> Result<InstallOutput, Box<dyn Error>> {
+    let mut attempt = 0;
+
+    retry::retry(
+        config.retries,
+        config.backoff_strategy,
+        config.backoff_base,
+        || {
+            if attempt > 0 {
+                print_message(
+                    &format!("Retrying install ({}/{})", attempt, config.retries),
+                    Icon::Dizzy,
+                    config,
+                );
+            }
+            attempt += 1;
+
+            npm_cmd::install(specs, config, deadline)
+        },
+        |result| match result {
+            Ok(output) => output.status.success(),
+            // there's no time left for another attempt
+            Err(err) => deadline::is_timeout(err.as_ref()),
+        },
+    )
+}
+
+/// The peers from an ERESOLVE failure to add to the install, leaving out any already being
+/// installed at another version as including them would undo that bump
+pub fn peer_specs(stderr: &str, specs: &[String]) -> Vec<String> {
+    npm_cmd::parse_eresolve(stderr)
+        .iter()
+        .filter(|conflict| !specs.iter().any(|spec| spec_name(spec) == conflict.name))
+        .map(|conflict| conflict.install_cmd())
+        .collect()
+}
+
+/// Installs the specs, and with --resolve-peer-conflicts tries once more including the peers
+/// npm couldn't resolve. Returns every spec which ended up installed
+fn install_resolving_peers(
+    specs: &[String],
+    config: &Config,
+    deadline: &Deadline,
+) -> Result<(Vec<String>, ExitStatus), Box<dyn Error>> {
+    let output = install_with_retries(specs, config, deadline)?;
+
+    if output.status.success() || !config.is_resolve_peer_conflicts {
+        return Ok((specs.to_vec(), output.status));
+    }
+
+    let peers = peer_specs(&output.stderr, specs);
+
+    if peers.is_empty() {
+        return Ok((specs.to_vec(), output.status));
+    }
+
+    print_message(
+        &format!(
+            "Retrying with the conflicting peer dependencies ({})",
+            peers.join(", ")
+        ),
+        Icon::Dizzy,
+        config,
+    );
+
+    let all_specs: Vec<String> = specs.iter().chain(&peers).cloned().collect();
+    let output = install_with_retries(&all_specs, config, deadline)?;
+
+    Ok((all_specs, output.status))
+}
+
+/// Installs every package with a single npm install, so they share the outcome
+pub fn batched(
+    specs: &[String],
+    config: &Config,
+    deadline: &Deadline,
+) -> Result<Vec<InstallResult>, Box<dyn Error>> {
+    let (specs, status) = install_resolving_peers(specs, config, deadline)?;
+
+    Ok(shared_outcome(&specs, status))
+}
+
+/// Installs from the widened package.json, plus any `extra_specs` it doesn't declare
+pub fn widened(
+    specs: &[String],
+    extra_specs: &[String],
+    config: &Config,
+    deadline: &Deadline,
+) -> Result<Vec<InstallResult>, Box<dyn Error>> {
+    let (extra_specs, status) = install_resolving_peers(extra_specs, config, deadline)?;
+    let all_specs: Vec<String> = specs.iter().chain(&extra_specs).cloned().collect();
+
+    Ok(shared_outcome(&all_specs, status))
+}
+
+fn shared_outcome(specs: &[String], status: ExitStatus) -> Vec<InstallResult> {
+    specs
+        .iter()
+        .map(|spec| InstallResult {
+            spec: spec.into(),
+            success: status.success(),
+        })
+        .collect()
+}
+
+/// The spec to retry a failed major upgrade at, staying within the declared range, or None
+/// when it isn't a major or is already at its wanted version
+pub fn wanted_fallback(pkg: &Package, config: &Config) -> Option<String> {
+    if pkg.upgrade_type != UpgradeType::Major || !pkg.has_in_range_update() {
+        return None;
+    }
+
+    let spec = InstallSpec {
+        version: pkg.wanted_version.clone(),
+        ..pkg.install_spec(config)
+    };
+
+    Some(spec.to_string())
+}
+
+/// The failed results which can fall back to the wanted version, by index, with the spec to
+/// retry at
+pub fn fallback_candidates(
+    packages: &[Package],
+    results: &[InstallResult],
+    config: &Config,
+) -> Vec<(usize, String)> {
+    results
+        .iter()
+        .enumerate()
+        .filter(|(_, result)| !result.success)
+        .filter_map(|(idx, result)| {
+            let pkg = packages
+                .iter()
+                .find(|pkg| pkg.install_spec(config).to_string() == result.spec)?;
+
+            wanted_fallback(pkg, config).map(|spec| (idx, spec))
+        })
+        .collect()
+}
+
+/// Retries each failed major upgrade at its wanted version, one at a time, replacing the
+/// results of those which then install. Returns the specs which were fallen back to
+pub fn fall_back_to_wanted(
+    packages: &[Package],
+    results: &mut [InstallResult],
+    config: &Config,
+    deadline: &Deadline,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut fell_back = vec![];
+
+    for (idx, spec) in fallback_candidates(packages, results, config) {
+        print_message(
+            &format!(
+                "Installing {} failed, falling back to {}",
+                results[idx].spec, spec
+            ),
+            Icon::Dizzy,
+            config,
+        );
+
+        let (_, status) = install_resolving_peers(std::slice::from_ref(&spec), config, deadline)?;
+
+        if status.success() {
+            results[idx] = InstallResult {
+                spec: spec.clone(),
+                success: true,
+            };
+            fell_back.push(spec);
+        }
+    }
+
+    Ok(fell_back)
+}
+
+/// How long each spec took to install
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Timings {
+    entries: Vec<(String, Duration)>,
+}
+
+impl Timings {
+    pub fn record(&mut self, spec: &str, duration: Duration) {
+        self.entries.push((spec.into(), duration));
+    }
+
+    pub fn total(&self) -> Duration {
+        self.entries.iter().map(|(_, duration)| *duration).sum()
+    }
+
+    /// One line per spec, slowest first, e.g. "react@18.2.0 - 12.3s"
+    pub fn report(&self) -> String {
+        let mut entries: Vec<&(String, Duration)> = self.entries.iter().collect();
+        // stable, so specs which took as long keep the order they were installed in
+        entries.sort_by_key(|(_, duration)| std::cmp::Reverse(*duration));
+
+        entries
+            .iter()
+            .map(|(spec, duration)| format!("{} - {:.1}s\n", spec, duration.as_secs_f64()))
+            .collect()
+    }
+}
+
+/// Installs each package on its own, so one failure doesn't prevent the others. With
+/// --commit-per-package, `commit_messages` has the message each spec is committed with once
+/// it installs
+pub fn isolated(
+    specs: &[String],
+    commit_messages: &HashMap<String, String>,
+    config: &Config,
+    deadline: &Deadline,
+) -> Result<Vec<InstallResult>, Box<dyn Error>> {
+    let mut results = vec![];
+    let mut timings = Timings::default();
+
+    for spec in specs.iter() {
+        let line = format!(
+            "{} Installing {}",
+            config.theme.icon(Icon::PointRight),
+            spec
+        );
+        print_labelled(&format!("{}\n", line), config);
+
+        let started = Instant::now();
+        let success = match install_resolving_peers(&[spec.into()], config, deadline) {
+            Ok((_, status)) => status.success(),
+            Err(err) if deadline::is_timeout(err.as_ref()) => return Err(err),
+            Err(err) => {
+                eprintln!("{}", label_lines(&err.to_string(), config));
+                false
+            }
+        };
+        timings.record(spec, started.elapsed());
+
+        if success {
+            if let Some(message) = commit_messages.get(spec) {
+                git::commit(&manifest::committed_files(), message)?;
+            }
+        }
+
+        results.push(InstallResult {
+            spec: spec.into(),
+            success,
+        });
+    }
+
+    // only worth the noise when looking for the slow packages
+    if config.is_verbose && !specs.is_empty() {
+        print_message(
+            &format!(
+                "Installed in {:.1}s, slowest first:\n{}",
+                timings.total().as_secs_f64(),
+                timings.report().trim_end()
+            ),
+            Icon::PointRight,
+            config,
+        );
+    }
+
+    Ok(results)
+}
+
+/// The order packages are passed to npm install in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum InstallOrder {
+    /// The order they're declared in package.json
+    Declared,
+    /// By name
+    Alphabetical,
+    /// The safe updates, then the major ones
+    SafeFirst,
+}
+
+/// Puts the packages in the given install order. `declared` is the dependency names in the
+/// order package.json declares them
+pub fn order_for_install(packages: &mut [&Package], order: InstallOrder, declared: &[String]) {
+    match order {
+        InstallOrder::Declared => order_declared(packages, declared),
+        InstallOrder::Alphabetical => order_alphabetical(packages),
+        InstallOrder::SafeFirst => order_safe_first(packages),
+    }
+}
+
+/// Packages which aren't declared (e.g. a workspace's) go last, in their existing order
+pub fn order_declared(packages: &mut [&Package], declared: &[String]) {
+    packages.sort_by_key(|pkg| {
+        declared
+            .iter()
+            .position(|name| name == &pkg.name)
+            .unwrap_or(usize::MAX)
+    });
+}
+
+pub fn order_alphabetical(packages: &mut [&Package]) {
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+}
+
+/// Keeps the existing order within the safe and the major updates
+pub fn order_safe_first(packages: &mut [&Package]) {
+    packages.sort_by_key(|pkg| pkg.upgrade_type == UpgradeType::Major);
+}
+
+/// Shuffles the install args, the same way each time for a given seed
+pub fn shuffle(cmd_args: &mut [String], seed: u64) {
+    fastrand::Rng::with_seed(seed).shuffle(cmd_args);
+}
+
+/// Whether there are too few bumps to be worth installing, going by --min-bumps
+pub fn is_below_min_bumps(bumps: usize, min_bumps: Option<usize>) -> bool {
+    min_bumps.is_some_and(|min| bumps < min)
+}
+
+pub fn summarize(results: &[InstallResult]) -> InstallSummary {
+    let mut summary = InstallSummary::default();
+
+    for result in results.iter() {
+        if result.success {
+            summary.installed.push(result.spec.clone());
+        } else {
+            summary.failed.push(result.spec.clone());
+        }
+    }
+
+    summary
+}
+
+/// Compares the packages which were bumped against a fresh outdated check, returning any which
+/// didn't reach their target version
+pub fn find_unapplied(
+    targeted: &[Package],
+    after: &[Package],
+    upgrade_style: &UpgradeStyle,
+) -> Vec<Unapplied> {
+    targeted
+        .iter()
+        .filter_map(|pkg| {
+            let expected = pkg.upgrade_version(upgrade_style);
+            // anything missing from the second check is no longer outdated
+            let remaining = after
+                .iter()
+                .find(|a| a.name == pkg.name && a.skip != Some(SkipReason::WorkspaceDependency))?;
+
+            if remaining.current_version == expected {
+                return None;
+            }
+
+            Some(Unapplied {
+                name: pkg.name.clone(),
+                expected: expected.into(),
+                found: remaining.current_version.clone(),
+            })
+        })
+        .collect()
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod find_conflicts_tests {
+    use super::*;
+
+    fn specs(specs: &[&str]) -> Vec<String> {
+        specs.iter().map(|s| String::from(*s)).collect()
+    }
+
+    #[test]
+    fn finds_package_at_two_versions() {
+        let result = find_conflicts(&specs(&[
+            "left-pad@1.3.0",
+            "@jonshort/cenv@0.1.0",
+            "polished@4.2.2",
+            "@jonshort/cenv@0.2.0",
+        ]));
+
+        assert_eq!(
+            result,
+            vec![SpecConflict {
+                name: String::from("@jonshort/cenv"),
+                specs: specs(&["@jonshort/cenv@0.1.0", "@jonshort/cenv@0.2.0"]),
+            }]
+        );
+        assert_eq!(
+            result[0].to_string(),
+            String::from("@jonshort/cenv (@jonshort/cenv@0.1.0, @jonshort/cenv@0.2.0)")
+        );
+    }
+
+    #[test]
+    fn ignores_repeated_identical_specs() {
+        assert_eq!(
+            find_conflicts(&specs(&["debug@4.3.4", "debug@4.3.4", "ms@2.1.3"])),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn distinguishes_scoped_packages() {
+        assert_eq!(
+            find_conflicts(&specs(&["@a/debug@1.0.0", "debug@2.0.0", "@b/debug@3.0.0"])),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn splits_spec_names() {
+        assert_eq!(spec_name("left-pad@1.3.0"), "left-pad");
+        assert_eq!(spec_name("@jonshort/cenv@0.1.0"), "@jonshort/cenv");
+        assert_eq!(spec_name("@jonshort/cenv"), "@jonshort/cenv");
+        assert_eq!(spec_name("react@>=16 <18"), "react");
+        assert_eq!(spec_name(""), "");
+    }
+}
+
+#[cfg(test)]
+mod wanted_fallback_tests {
+    use super::*;
+    use crate::utility::Args;
+    use clap::Parser;
+
+    fn config(argv: &[&str]) -> Config {
+        let mut config = Config::new_from_args(Args::parse_from(argv));
+        config.current_dir_name = None;
+        config
+    }
+
+    fn packages(config: &Config) -> Vec<Package> {
+        // location:name@wanted_version:name@current_version:name@latest_version:project
+        [
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test",
+            "location:polished@3.7.2:polished@3.7.2:polished@4.2.2:test",
+            "location:ms@2.1.3:ms@2.1.2:ms@2.1.3:test",
+        ]
+        .iter()
+        .map(|s| Package::new(String::from(*s), config).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn falls_back_to_wanted_for_majors() {
+        let config = config(&["npm-bumpall", "--latest"]);
+        let packages = packages(&config);
+
+        assert_eq!(
+            wanted_fallback(&packages[0], &config),
+            Some(String::from("debug@3.2.7"))
+        );
+        // already at its wanted version, so there's nothing to fall back to
+        assert_eq!(wanted_fallback(&packages[1], &config), None);
+        // not a major
+        assert_eq!(wanted_fallback(&packages[2], &config), None);
+    }
+
+    #[test]
+    fn never_falls_back_without_latest() {
+        let config = config(&["npm-bumpall"]);
+
+        assert!(packages(&config)
+            .iter()
+            .all(|pkg| wanted_fallback(pkg, &config).is_none()));
+    }
+
+    #[test]
+    fn only_failed_results_are_candidates() {
+        let config = config(&["npm-bumpall", "--latest", "--save-prefix", "~"]);
+        let packages = packages(&config);
+        let results = vec![
+            InstallResult {
+                spec: String::from("ms@2.1.3"),
+                success: false,
+            },
+            InstallResult {
+                spec: String::from("debug@4.3.4"),
+                success: false,
+            },
+            InstallResult {
+                spec: String::from("polished@4.2.2"),
+                success: true,
+            },
+        ];
+
+        assert_eq!(
+            fallback_candidates(&packages, &results, &config),
+            vec![(1, String::from("debug@3.2.7"))]
+        );
+    }
+}
+
+#[cfg(test)]
+mod timings_tests {
+    use super::*;
+
+    #[test]
+    fn lists_slowest_first() {
+        let mut timings = Timings::default();
+        timings.record("debug@4.3.4", Duration::from_millis(1200));
+        timings.record("react@18.2.0", Duration::from_millis(12300));
+        timings.record("ms@2.1.3", Duration::from_millis(1200));
+
+        assert_eq!(timings.total(), Duration::from_millis(14700));
+        assert_eq!(
+            timings.report(),
+            "react@18.2.0 - 12.3s\ndebug@4.3.4 - 1.2s\nms@2.1.3 - 1.2s\n"
+        );
+    }
+
+    #[test]
+    fn nothing_recorded() {
+        let timings = Timings::default();
+
+        assert_eq!(timings.total(), Duration::ZERO);
+        assert_eq!(timings.report(), "");
+    }
+}
+
+#[cfg(test)]
+mod order_for_install_tests {
+    use super::*;
+    use crate::utility::Args;
+
+    fn packages() -> Vec<Package> {
+        // the upgrade type is only major when bumping to latest
+        let mut config = Config::new_from_args(Args {
+            latest: true,
+            ..Args::default()
+        });
+        config.current_dir_name = None;
+
+        [
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:ms@2.1.3:ms@2.1.2:ms@2.1.3:test",
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test",
+            "location:chalk@4.1.2:chalk@4.1.0:chalk@4.1.2:test",
+        ]
+        .iter()
+        .map(|src| Package::new(String::from(*src), &config).unwrap())
+        .collect()
+    }
+
+    fn ordered(order: InstallOrder, declared: &[String]) -> Vec<String> {
+        let packages = packages();
+        let mut ordered: Vec<&Package> = packages.iter().collect();
+
+        order_for_install(&mut ordered, order, declared);
+
+        ordered.iter().map(|pkg| pkg.name.clone()).collect()
+    }
+
+    #[test]
+    fn declared() {
+        let declared: Vec<String> = ["debug", "chalk", "polished"]
+            .iter()
+            .map(|name| String::from(*name))
+            .collect();
+
+        // ms isn't declared, so goes last
+        assert_eq!(
+            ordered(InstallOrder::Declared, &declared),
+            vec!["debug", "chalk", "polished", "ms"]
+        );
+    }
+
+    #[test]
+    fn alphabetical() {
+        assert_eq!(
+            ordered(InstallOrder::Alphabetical, &[]),
+            vec!["chalk", "debug", "ms", "polished"]
+        );
+    }
+
+    #[test]
+    fn safe_first() {
+        assert_eq!(
+            ordered(InstallOrder::SafeFirst, &[]),
+            vec!["ms", "chalk", "polished", "debug"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod shuffle_tests {
+    use super::*;
+
+    fn args() -> Vec<String> {
+        ["a@1", "b@2", "c@3", "d@4", "e@5", "f@6", "g@7", "h@8"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn same_order_for_a_seed() {
+        let mut first = args();
+        let mut second = args();
+        shuffle(&mut first, 42);
+        shuffle(&mut second, 42);
+
+        assert_eq!(first, second);
+        assert_ne!(first, args());
+    }
+
+    #[test]
+    fn keeps_every_arg() {
+        let mut shuffled = args();
+        shuffle(&mut shuffled, 7);
+        shuffled.sort();
+
+        assert_eq!(shuffled, args());
+    }
+}
+
+#[cfg(test)]
+mod is_below_min_bumps_tests {
+    use super::*;
+
+    #[test]
+    fn no_threshold_by_default() {
+        assert!(!is_below_min_bumps(0, None));
+        assert!(!is_below_min_bumps(1, None));
+    }
+
+    #[test]
+    fn compares_with_threshold() {
+        assert!(is_below_min_bumps(2, Some(3)));
+        assert!(!is_below_min_bumps(3, Some(3)));
+        assert!(!is_below_min_bumps(4, Some(3)));
+    }
+}
+
+#[cfg(test)]
+mod summarize_tests {
+    use super::*;
+
+    fn result(spec: &str, success: bool) -> InstallResult {
+        InstallResult {
+            spec: String::from(spec),
+            success,
+        }
+    }
+
+    #[test]
+    fn splits_installed_and_failed() {
+        let results = vec![
+            result("left-pad@1.3.0", true),
+            result("@jonshort/cenv@0.1.0", false),
+            result("polished@3.7.2", true),
+        ];
+        let expected = InstallSummary {
+            failed: vec![String::from("@jonshort/cenv@0.1.0")],
+            installed: vec![
+                String::from("left-pad@1.3.0"),
+                String::from("polished@3.7.2"),
+            ],
+        };
+
+        assert_eq!(summarize(&results), expected);
+    }
+
+    #[test]
+    fn handles_all_failed() {
+        let results = vec![
+            result("left-pad@1.3.0", false),
+            result("debug@4.3.4", false),
+        ];
+        let expected = InstallSummary {
+            failed: vec![String::from("left-pad@1.3.0"), String::from("debug@4.3.4")],
+            installed: vec![],
+        };
+
+        assert_eq!(summarize(&results), expected);
+    }
+
+    #[test]
+    fn handles_no_results() {
+        assert_eq!(summarize(&[]), InstallSummary::default());
+    }
+}
+
+#[cfg(test)]
+mod install_summary_message_tests {
+    use super::*;
+
+    fn specs(specs: &[&str]) -> Vec<String> {
+        specs.iter().map(|spec| String::from(*spec)).collect()
+    }
+
+    #[test]
+    fn full_success() {
+        let summary = InstallSummary {
+            failed: vec![],
+            installed: specs(&["left-pad@1.3.0", "polished@3.7.2"]),
+        };
+
+        assert_eq!(
+            summary.message(),
+            (String::from("All packages bumped"), Icon::Trophy)
+        );
+    }
+
+    #[test]
+    fn partial_success() {
+        let summary = InstallSummary {
+            failed: specs(&["@jonshort/cenv@0.1.0", "debug@4.3.4"]),
+            installed: specs(&["left-pad@1.3.0", "polished@3.7.2", "ms@2.1.3"]),
+        };
+
+        assert_eq!(
+            summary.message(),
+            (
+                String::from(
+                    "Bumped 3 of 5 packages; 2 failed (@jonshort/cenv@0.1.0, debug@4.3.4) - try running these manually"
+                ),
+                Icon::Warning
+            )
+        );
+    }
+
+    #[test]
+    fn zero_success() {
+        let summary = InstallSummary {
+            failed: specs(&["left-pad@1.3.0", "polished@3.7.2"]),
+            installed: vec![],
+        };
+
+        assert_eq!(
+            summary.message(),
+            (
+                String::from("Bumped 0 of 2 packages; all failed - try running manually"),
+                Icon::Cross
+            )
+        );
+    }
+}
+
+#[cfg(test)]
+mod peer_specs_tests {
+    use super::*;
+
+    #[test]
+    fn adds_unresolved_peers() {
+        let stderr = include_str!("test_files/npm_eresolve.txt");
+        let specs = vec![String::from("react-dom@18.2.0")];
+
+        assert_eq!(peer_specs(stderr, &specs), vec!["react@^18.2.0"]);
+    }
+
+    #[test]
+    fn leaves_out_peers_already_being_bumped() {
+        let stderr = include_str!("test_files/npm_eresolve.txt");
+        let specs = vec![
+            String::from("react-dom@18.2.0"),
+            String::from("react@17.0.2"),
+        ];
+
+        assert!(peer_specs(stderr, &specs).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod find_unapplied_tests {
+    use super::*;
+    use crate::utility::Args;
+
+    fn packages(src: &[&str], config: &Config) -> Vec<Package> {
+        src.iter()
+            .map(|s| Package::new(String::from(*s), config).unwrap())
+            .collect()
+    }
+
+    fn config() -> Config {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = Some(String::from("test"));
+        config
+    }
+
+    #[test]
+    fn nothing_unapplied_when_no_longer_outdated() {
+        let config = config();
+        let before = packages(
+            &["location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test"],
+            &config,
+        );
+
+        assert!(find_unapplied(&before, &[], &config.upgrade_style).is_empty());
+    }
+
+    #[test]
+    fn at_target_but_still_listed_is_applied() {
+        let config = config();
+        let before = packages(
+            &["location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test"],
+            &config,
+        );
+        // only the out of range major remains
+        let after = packages(
+            &["location:polished@3.7.2:polished@3.7.2:polished@4.2.2:test"],
+            &config,
+        );
+
+        assert!(find_unapplied(&before, &after, &config.upgrade_style).is_empty());
+    }
+
+    #[test]
+    fn reports_packages_left_behind() {
+        let config = config();
+        let before = packages(
+            &[
+                "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+                "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test",
+            ],
+            &config,
+        );
+        let after = packages(
+            &["location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test"],
+            &config,
+        );
+
+        let unapplied = find_unapplied(&before, &after, &config.upgrade_style);
+
+        assert_eq!(
+            unapplied,
+            vec![Unapplied {
+                name: String::from("debug"),
+                expected: String::from("3.2.7"),
+                found: String::from("3.2.6"),
+            }]
+        );
+        assert_eq!(
+            unapplied[0].to_string(),
+            "debug (expected 3.2.7, found 3.2.6)"
+        );
+    }
+
+    #[test]
+    fn ignores_other_workspaces() {
+        let config = config();
+        let before = packages(
+            &["location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test"],
+            &config,
+        );
+        let after = packages(
+            &["location:debug@3.2.7:debug@3.2.6:debug@4.3.4:other"],
+            &config,
+        );
+
+        assert!(find_unapplied(&before, &after, &config.upgrade_style).is_empty());
+    }
+}
@@ -1,116 +1,1130 @@
-use std::process;
+use clap::Parser;
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    io::{self, BufRead, IsTerminal},
+    path::{Path, PathBuf},
+    process,
+};
 
+mod allowlist;
+mod audit;
 mod color_codes;
+mod deadline;
 mod emojis;
+mod fetch;
+mod format;
+mod git;
+mod install;
+mod manifest;
+mod node_version;
+mod notification;
 mod npm_cmd;
+mod outcome;
 mod package;
+mod plan_diff;
+mod profile;
+mod retry;
+mod self_check;
+mod smoke;
+mod steps;
+mod tui;
 mod utility;
+mod version;
+mod watch;
+mod workspace;
 
-use emojis::{CROSS, DIZZY, MAGNIFYING_GLASS, POINT_RIGHT, ROCKET, TROPHY};
-use package::{Package, UpgradeType};
-use utility::{print_message, Config, UpgradeStyle};
+use deadline::{Deadline, TIMED_OUT_EXIT_CODE};
+use emojis::Icon;
+use format::OutputFormat;
+use notification::RunResult;
+use outcome::RunOutcome;
+use package::{Package, SkipReason, UpgradeType};
+use utility::{print_labelled, print_message, Args, Command, Config, ConfigView};
 
-#[cfg(windows)]
-pub const NPM: &str = "npm.cmd";
+fn exit_on_error(err: Box<dyn Error>) -> ! {
+    eprintln!("{}", err);
 
-#[cfg(not(windows))]
-pub const NPM: &str = "npm";
+    if deadline::is_timeout(err.as_ref()) {
+        process::exit(TIMED_OUT_EXIT_CODE)
+    }
+
+    process::exit(70)
+}
 
 fn main() {
-    let config = Config::create_config();
+    let mut args = Args::parse();
+
+    if let Some(name) = &args.profile {
+        let profile_args = profile::load(name).unwrap_or_else(|err| exit_on_error(err));
+        args = Args::parse_from(profile::with_profile_args(env::args_os(), profile_args));
+    }
+
+    if let Some(shell) = args.shell_completion {
+        utility::write_completion(shell, &mut io::stdout());
+        process::exit(0);
+    }
+
+    let is_print_config = args.print_config;
+    let command = args.command.take();
+    let mut config = Config::new_from_args(args);
+    // set by GitHub Actions for each step
+    config.github_output = env::var_os("GITHUB_OUTPUT").map(PathBuf::from);
+
+    if is_print_config {
+        match serde_json::to_string_pretty(&ConfigView::from(&config)) {
+            Ok(view) => println!("{}", view),
+            Err(err) => exit_on_error(err.into()),
+        }
+        process::exit(0);
+    }
+
+    if let Some(rev) = &config.compare_with {
+        compare_with(rev, &config).unwrap_or_else(|err| exit_on_error(err));
+        process::exit(0);
+    }
+
+    let deadline = Deadline::new(config.max_runtime);
+
+    // npm only reads the manifest in the directory it's run from
+    if let Some(dir) = config.manifest.as_deref().and_then(|path| path.parent()) {
+        env::set_current_dir(dir).unwrap_or_else(|err| exit_on_error(err.into()));
+    }
+
+    if let Some(Command::Dedupe) = command {
+        dedupe(&config, &deadline).unwrap_or_else(|err| exit_on_error(err));
+        process::exit(0);
+    }
+
+    if config.current_dir_name.is_none() {
+        print_message(
+            "Unable to read the current directory name, workspace detection is disabled",
+            Icon::Warning,
+            &config,
+        );
+    }
+
+    if config.is_watch {
+        watch(&config).unwrap_or_else(|err| exit_on_error(err));
+        process::exit(0);
+    }
+
+    let newer_release = config.max_age_warn_days.map(self_check::spawn);
+
+    let projects = nearby_projects(&config).unwrap_or_else(|err| exit_on_error(err));
+
+    let result = match projects {
+        Some(dirs) => run_in_dirs(&dirs, None, &config, &deadline),
+        None if config.is_recursive => run_recursive(&config, &deadline),
+        None => run(&config, None, &deadline),
+    }
+    .unwrap_or_else(|err| exit_on_error(err));
+
+    notification::send(&result, &config);
+
+    if let Some(release) =
+        newer_release.and_then(|receiver| receiver.recv_timeout(self_check::REPORT_GRACE).ok())
+    {
+        print_message(
+            &format!(
+                "{} {} has been available for {} days (running {}), consider updating",
+                self_check::PACKAGE_NAME,
+                release.latest,
+                release.days_available,
+                self_check::VERSION
+            ),
+            Icon::Warning,
+            &config,
+        );
+    }
+
+    if let RunResult::Stale(_) = result {
+        process::exit(1);
+    }
+}
+
+/// Checks for updates now, then again each time package.json changes
+fn watch(config: &Config) -> Result<(), Box<dyn Error>> {
+    let check = || {
+        // each check gets the full --max-runtime, and a failed one doesn't end the watch
+        if let Err(err) = run(config, None, &Deadline::new(config.max_runtime)) {
+            eprintln!("{}", err);
+        }
+
+        print_message(
+            "Watching package.json for changes...",
+            Icon::MagnifyingGlass,
+            config,
+        );
+    };
+
+    check();
+    watch::run(check)
+}
+
+/// Flattens the dependency tree without checking for updates
+fn dedupe(config: &Config, deadline: &Deadline) -> Result<(), Box<dyn Error>> {
+    print_message("Running npm dedupe", Icon::Dizzy, config);
+
+    if !npm_cmd::dedupe(config, deadline)?.success() {
+        return Err("npm dedupe failed - try running manually".into());
+    }
 
-    print_message("Checking for outdated packages...", &MAGNIFYING_GLASS);
+    print_message("Dependency tree deduped", Icon::Trophy, config);
+    Ok(())
+}
 
-    let output = npm_cmd::run(&config).unwrap_or_else(|err| {
-        eprintln!("{}", err);
-        process::exit(70)
-    });
+/// Lists the dependencies whose declared range or installed version changed since `rev`
+fn compare_with(rev: &str, config: &Config) -> Result<(), Box<dyn Error>> {
+    let before = manifest::dependency_map(&git::show_file(rev, manifest::MANIFEST)?)?;
+    let after = manifest::dependency_map(&manifest::read_manifest()?)?;
+    let mut sections = vec![(
+        "Declared ranges",
+        manifest::dependency_changes(&before, &after),
+    )];
 
-    let split_by_eol: Vec<&str> = output.split_terminator('\n').collect();
-    let packages: Vec<Package> = split_by_eol
+    // not every project commits a lockfile
+    if let (Ok(before), Ok(after)) = (
+        git::show_file(rev, manifest::LOCKFILE),
+        manifest::read_lockfile(),
+    ) {
+        let before = manifest::lockfile_versions(&before)?;
+        let after = manifest::lockfile_versions(&after)?;
+        sections.push((
+            "Installed versions",
+            manifest::dependency_changes(&before, &after),
+        ));
+    }
+
+    if sections.iter().all(|(_, changes)| changes.is_empty()) {
+        print_message(
+            &format!("No dependency changes since {}", rev),
+            Icon::Rocket,
+            config,
+        );
+        return Ok(());
+    }
+
+    for (heading, changes) in sections.iter().filter(|(_, c)| !c.is_empty()) {
+        let heading = format!("{} changed since {}", heading, rev);
+        print_labelled(
+            &format!(
+                "{}\n",
+                format::dependency_changes(&heading, changes, config)
+            ),
+            config,
+        );
+    }
+
+    Ok(())
+}
+
+/// When the current directory has no package.json, the projects below it to run in instead,
+/// picked with --all-projects or at a prompt
+fn nearby_projects(config: &Config) -> Result<Option<Vec<PathBuf>>, Box<dyn Error>> {
+    let root = env::current_dir()?;
+
+    if root.join(manifest::MANIFEST).is_file() {
+        return Ok(None);
+    }
+
+    let projects = workspace::find_projects(&root, workspace::PROJECT_SEARCH_DEPTH);
+
+    if projects.is_empty() {
+        return Ok(None);
+    }
+
+    let label = |dir: &PathBuf| dir.strip_prefix(&root).unwrap_or(dir).display().to_string();
+
+    let chosen = if config.is_all_projects {
+        projects
+    } else if io::stdin().is_terminal() {
+        let mut listing = String::from("No package.json here, but found these projects\n");
+        for (i, dir) in projects.iter().enumerate() {
+            listing.push_str(&format!("  {}) {}\n", i + 1, label(dir)));
+        }
+        listing.push_str("Bump which? (e.g. 1,3 or all)");
+        print_message(&listing, Icon::MagnifyingGlass, config);
+
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+
+        match workspace::parse_project_choice(&answer, projects.len()) {
+            Some(indices) => indices.iter().map(|&i| projects[i].clone()).collect(),
+            None => return Err("No projects chosen".into()),
+        }
+    } else {
+        let labels: Vec<String> = projects.iter().map(label).collect();
+        return Err(format!(
+            "No package.json in the current directory, pass --all-projects to bump the projects below it ({})",
+            labels.join(", ")
+        )
+        .into());
+    };
+
+    let mut dirs = vec![];
+
+    for project in chosen {
+        if config.is_recursive {
+            let workspaces = workspace::discover(&project)?;
+            dirs.push(project);
+            dirs.extend(workspaces);
+        } else {
+            dirs.push(project);
+        }
+    }
+
+    Ok(Some(dirs))
+}
+
+/// Runs in the current directory, then each of its workspaces
+fn run_recursive(config: &Config, deadline: &Deadline) -> Result<RunResult, Box<dyn Error>> {
+    let root = env::current_dir()?;
+    let mut workspaces = workspace::discover(&root)?;
+
+    // reused for the root's own check, rather than asking npm twice
+    let mut root_outdated = None;
+
+    if config.is_skip_unchanged_workspaces {
+        let outdated = npm_cmd::run(&dir_config(&root, &root, config), deadline)?;
+        let discovered = workspaces.len();
+        workspaces = workspace::with_outdated(workspaces, &outdated);
+        root_outdated = Some(outdated);
+
+        if workspaces.len() < discovered {
+            print_message(
+                &format!(
+                    "Skipping {} workspaces with no outdated packages",
+                    discovered - workspaces.len()
+                ),
+                Icon::PointRight,
+                config,
+            );
+        }
+    }
+
+    let mut dirs = vec![root];
+    dirs.extend(workspaces);
+
+    run_in_dirs(&dirs, root_outdated, config, deadline)
+}
+
+/// The config for running in `dir`, as workspace detection compares against the directory npm
+/// runs in. Artifacts are kept apart by the path from `root`, as workspaces can share a name
+fn dir_config(dir: &Path, root: &Path, config: &Config) -> Config {
+    let workspace_dir = match dir.strip_prefix(root) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => dir.file_name().map(PathBuf::from).unwrap_or_default(),
+    };
+
+    Config {
+        current_dir_name: dir.file_name().and_then(|d| d.to_str()).map(String::from),
+        workspace_dir: Some(workspace_dir),
+        ..config.clone()
+    }
+}
+
+/// Runs in each of the directories in turn, with a combined summary at the end. The root's
+/// outdated packages are only checked again if they aren't passed in
+fn run_in_dirs(
+    dirs: &[PathBuf],
+    mut root_outdated: Option<Vec<Package>>,
+    config: &Config,
+    deadline: &Deadline,
+) -> Result<RunResult, Box<dyn Error>> {
+    let root = env::current_dir()?;
+    let mut result = RunResult::UpToDate;
+
+    for dir in dirs.iter() {
+        let label = match dir.strip_prefix(&root) {
+            Ok(relative) if relative.as_os_str().is_empty() => String::from("."),
+            Ok(relative) => relative.display().to_string(),
+            Err(_) => dir.display().to_string(),
+        };
+        print_message(&format!("Workspace {}", label), Icon::PointRight, config);
+
+        env::set_current_dir(dir)?;
+
+        let checked = if *dir == root {
+            root_outdated.take()
+        } else {
+            None
+        };
+        result = result.merge(run(&dir_config(dir, &root, config), checked, deadline)?);
+    }
+
+    env::set_current_dir(&root)?;
+
+    print_message(
+        &format!(
+            "{} across {} directories",
+            notification::message(&result),
+            dirs.len()
+        ),
+        Icon::Trophy,
+        config,
+    );
+
+    Ok(result)
+}
+
+/// The --commit-per-package message for each package, by its install spec
+fn commit_messages(
+    packages: &[Package],
+    config: &Config,
+) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    let template = match &config.commit_message {
+        Some(template) => template,
+        None => return Ok(HashMap::new()),
+    };
+
+    packages
         .iter()
-        .filter_map(|&s| match Package::new(s.into(), &config) {
-            Ok(pkg) => {
-                if pkg.skip {
-                    return None;
-                }
+        .map(|pkg| {
+            let message = format::commit_message(pkg, template, config)?;
+            Ok((pkg.install_spec(config).to_string(), message))
+        })
+        .collect()
+}
 
-                if config
-                    .include_glob
-                    .as_ref()
-                    .is_some_and(|glob| !glob.matches(&pkg.name))
-                {
-                    return None;
-                }
+/// Last week's npm downloads of each package, leaving out those which couldn't be fetched
+fn popularity(
+    packages: &[&Package],
+    limits: fetch::Limits,
+    config: &Config,
+) -> HashMap<String, u64> {
+    fetch::attach(
+        &fetch::DownloadsClient::new(config),
+        packages,
+        |pkg| pkg.upgrade_version(&config.upgrade_style),
+        limits,
+    )
+    .into_iter()
+    .filter_map(|(pkg, downloads)| Some((pkg.name.clone(), downloads?)))
+    .collect()
+}
 
-                Some(pkg)
+/// The outdated check and bump, for the current directory, starting from `checked` when npm
+/// has already been asked what's outdated
+fn run(
+    config: &Config,
+    checked: Option<Vec<Package>>,
+    deadline: &Deadline,
+) -> Result<RunResult, Box<dyn Error>> {
+    // a conflicted lockfile would be rewritten by npm, losing track of what was conflicting
+    if config.is_abort_on_lockfile_conflict {
+        if let Ok(lockfile) = manifest::read_lockfile() {
+            if manifest::has_conflict_markers(&lockfile) {
+                return Err(format!(
+                    "{} has unresolved merge conflicts, resolve them before bumping",
+                    manifest::LOCKFILE
+                )
+                .into());
             }
-            Err(_) => None,
-        })
-        .collect();
+        }
+    }
+
+    // with --quiet-success, an up to date project prints nothing at all
+    if !config.is_quiet_success {
+        print_message(
+            "Checking for outdated packages...",
+            Icon::MagnifyingGlass,
+            config,
+        );
+    }
 
-    if packages.is_empty() {
-        println!("{} No outdated packages found {}", &ROCKET, &ROCKET);
-        process::exit(0)
+    // only worth the noise when checking which Node the project expects
+    if config.is_verbose {
+        if let Some(node) = node_version::detect(Path::new(".")) {
+            print_message(
+                &format!(
+                    "Project targets Node {} (from {})",
+                    node.version, node.source
+                ),
+                Icon::PointRight,
+                config,
+            );
+        }
     }
 
-    println!("Updates required");
-    for pkg in packages.iter() {
-        let upgrade_version = match &config.upgrade_style {
-            UpgradeStyle::Latest => &pkg.latest_version,
-            UpgradeStyle::Wanted => &pkg.wanted_version,
-        };
+    let mut packages: Vec<Package> = vec![];
+    let mut skipped: Vec<(Package, SkipReason)> = vec![];
+
+    // a missing or unreadable manifest just means sources and types can't be checked
+    let manifest_src = manifest::read_manifest().unwrap_or_default();
+    let declared = manifest::dependency_map(&manifest_src).unwrap_or_default();
+    let types = manifest::dependency_types(&manifest_src).unwrap_or_default();
+
+    let mut outdated = match checked {
+        Some(outdated) => outdated,
+        None => npm_cmd::run(config, deadline)?,
+    };
+    // first, so the ordering options below and every report start from the same order
+    package::apply_listing_order(&mut outdated, config);
+
+    // across every dependent npm reported, including the ones skipped below
+    let dependent_counts = if config.is_order_by_dependents {
+        package::dependent_counts(&outdated)
+    } else {
+        HashMap::new()
+    };
+
+    let allowlist = config
+        .allowlist_file
+        .as_deref()
+        .map(allowlist::Allowlist::load)
+        .transpose()?;
+
+    for mut pkg in outdated {
+        pkg.check_source(declared.get(&pkg.name).map(|range| range.as_str()));
+
+        if let Some(pkg_types) = types.get(&pkg.name) {
+            pkg.check_type(pkg_types, &config.excluded_types);
+        }
+
+        if pkg.skip.is_none()
+            && allowlist
+                .as_ref()
+                .is_some_and(|allowlist| !allowlist.allows(&pkg.name, &pkg.install_version))
+        {
+            pkg.skip = Some(SkipReason::NotAllowlisted);
+        }
+
+        if let Some(reason) = pkg.skip {
+            skipped.push((pkg, reason));
+            continue;
+        }
+
+        if config.is_strict_semver {
+            let invalid = pkg.invalid_versions();
+
+            if !invalid.is_empty() {
+                print_message(
+                    &format!(
+                        "Skipping {} - invalid version(s) from npm: {}",
+                        pkg.name,
+                        invalid.join(", ")
+                    ),
+                    Icon::Warning,
+                    config,
+                );
+                skipped.push((pkg, SkipReason::InvalidVersion));
+                continue;
+            }
+        }
+
+        packages.push(pkg);
+    }
+
+    // dropped by the filtering options below, on top of those skipped above
+    let mut filtered_out = 0;
+
+    if let Some(tag) = &config.since_tag {
+        let before = manifest::dependency_map(&git::show_file(tag, manifest::MANIFEST)?)?;
+        let after = manifest::dependency_map(&git::show_file("HEAD", manifest::MANIFEST)?)?;
+        let changed = manifest::changed_dependencies(&before, &after);
 
-        let color = match pkg.upgrade_type {
-            UpgradeType::Safe => color_codes::CYAN,
-            UpgradeType::Major => color_codes::YELLOW,
+        let before_filter = packages.len();
+        packages.retain(|pkg| changed.contains(&pkg.name));
+        filtered_out += before_filter - packages.len();
+    }
+
+    if config.is_only_security {
+        let fixes = npm_cmd::list_security_fixes()?;
+        let before_filter = packages.len();
+        let before_skipped = skipped.len();
+        packages = audit::restrict_to_fixes(packages, &mut skipped, &fixes, &config.upgrade_style);
+        let restored = before_skipped - skipped.len();
+        filtered_out += before_filter + restored - packages.len();
+
+        if restored > 0 {
+            package::apply_listing_order(&mut packages, config);
+        }
+    }
+
+    // only worth the noise when checking why something isn't listed, or asked for in the report;
+    // the json plan lists them under "skipped" instead
+    let is_showing_skipped = (config.is_report_include_skipped
+        || (config.is_dry_run && config.is_verbose))
+        && config.output_format != OutputFormat::Json
+        && !skipped.is_empty();
+
+    let limits = fetch::Limits {
+        parallelism: config.parallelism,
+        per_host: config.max_concurrency_per_registry,
+    };
+
+    // before anything returns early, as a project with nothing outdated can still be neglected
+    if let Some(max_age_days) = config.max_outdated_age_fail_days {
+        let aged = match manifest::read_lockfile() {
+            Ok(lockfile) => fetch::aged_installed(
+                &fetch::PublishDayClient::new(config),
+                &lockfile,
+                &declared,
+                self_check::today()?,
+                max_age_days,
+                limits,
+            )?,
+            Err(_) => {
+                print_message(
+                    "No package-lock.json to read the installed versions from, skipping --max-outdated-age-fail",
+                    Icon::Warning,
+                    config,
+                );
+                vec![]
+            }
         };
 
-        println!(
-            "{} {} {} -> \x1b[{}m{}\x1b[0m",
-            &POINT_RIGHT, pkg.name, pkg.current_version, color, upgrade_version
+        if !aged.is_empty() {
+            let aged: Vec<String> = aged
+                .iter()
+                .map(|dep| format!("{} ({} days)", dep.spec, dep.age_days))
+                .collect();
+            return Err(format!(
+                "Installed versions are older than {} days: {}",
+                max_age_days,
+                aged.join("; ")
+            )
+            .into());
+        }
+    }
+
+    // majors are only reported, alongside any which were skipped as being at their wanted version
+    let mut majors_to_review: Vec<Package> = if config.is_wanted_then_report_latest {
+        let (safe, majors) = package::partition_by_major(packages);
+        packages = safe;
+
+        majors
+            .into_iter()
+            .chain(
+                skipped
+                    .iter()
+                    .filter(|(_, reason)| *reason == SkipReason::OutOfRange)
+                    .map(|(pkg, _)| pkg.clone()),
+            )
+            .collect()
+    } else {
+        vec![]
+    };
+
+    if config.is_frozen {
+        let stale = package::in_range_updates(&packages);
+
+        if stale.is_empty() {
+            print_message(
+                "Installed versions are up to date with package.json",
+                Icon::Rocket,
+                config,
+            );
+            return Ok(RunResult::UpToDate);
+        }
+
+        for pkg in stale.iter() {
+            let line = format!(
+                "{} {} {} -> {}",
+                config.theme.icon(Icon::PointRight),
+                pkg.name,
+                pkg.current_version,
+                pkg.wanted_version
+            );
+            print_labelled(&format!("{}\n", line), config);
+        }
+        print_labelled("\n", config);
+
+        let result = RunResult::Stale(stale.len());
+        print_message(&notification::message(&result), Icon::Cross, config);
+        return Ok(result);
+    }
+
+    let peer_conflicts = if config.include_peer_outdated {
+        npm_cmd::list_peer_conflicts()?
+    } else {
+        vec![]
+    };
+
+    if config.is_sort_majors_by_popularity && !majors_to_review.is_empty() {
+        let majors: Vec<&Package> = majors_to_review.iter().collect();
+        let downloads = popularity(&majors, limits, config);
+        package::sort_by_popularity(&mut majors_to_review, &downloads);
+    }
+
+    if !majors_to_review.is_empty() {
+        let majors = format::majors_to_review(&majors_to_review, config);
+        print_labelled(&format!("{}\n", majors), config);
+    }
+
+    if packages.is_empty() && peer_conflicts.is_empty() {
+        if is_showing_skipped {
+            print_labelled(&format!("{}\n", format::skipped(&skipped, config)), config);
+        }
+
+        if let Some(message) =
+            format::nothing_to_bump(package::nothing_to_bump(&skipped, filtered_out), config)
+        {
+            print_labelled(&format!("{}\n", message), config);
+        }
+        return Ok(RunResult::UpToDate);
+    }
+
+    // bumping from the dashboard overrides --dry-run for this run
+    let tui_config;
+    let config = if config.is_tui && !packages.is_empty() {
+        match tui::run(&packages, config)? {
+            Some(tui::Outcome::Bump(selected)) if !selected.is_empty() => {
+                packages.retain(|pkg| selected.contains(&pkg.name));
+                tui_config = Config {
+                    is_dry_run: false,
+                    ..config.clone()
+                };
+                &tui_config
+            }
+            // cancelled, or nothing left ticked
+            Some(_) => return Ok(RunResult::UpdatesAvailable(packages.len())),
+            None => {
+                print_message(
+                    "Unable to show the dashboard (needs a terminal and the tui feature), listing updates instead",
+                    Icon::Warning,
+                    config,
+                );
+                config
+            }
+        }
+    } else {
+        config
+    };
+
+    if config.is_order_by_dependents {
+        package::order_by_dependents(&mut packages, &dependent_counts);
+    }
+
+    // after ordering by dependents, as it keeps the order within each group
+    if config.is_group_majors_last {
+        package::group_majors_last(&mut packages);
+    }
+
+    if config.is_sort_majors_by_popularity {
+        let majors: Vec<&Package> = packages
+            .iter()
+            .filter(|pkg| pkg.upgrade_type == UpgradeType::Major)
+            .collect();
+        let downloads = popularity(&majors, limits, config);
+        package::sort_majors_by_popularity(&mut packages, &downloads);
+    }
+
+    let report = format::report_packages(&packages, config);
+
+    if let Some(template) = &config.template {
+        let output = format::templated(&report, template, config)?;
+        print_labelled(&output, config);
+    } else {
+        match config.output_format {
+            OutputFormat::Text if report.is_empty() => {}
+            OutputFormat::Text => {
+                // whether each bump widens its range is for reviewing before it happens
+                let declared = config.is_dry_run.then_some(&declared);
+                let output = format::text(&report, declared, config);
+                print_labelled(&output, config)
+            }
+            OutputFormat::Diff => {
+                let manifest = manifest::read_manifest()?;
+                let diff = format::diff(&report, &manifest, config);
+                print_labelled(&diff, config);
+            }
+            // printed with the plan, once the install args are known
+            OutputFormat::Json => {}
+            // left unlabelled, so it can be pasted as is
+            OutputFormat::Markdown => print!("{}", format::markdown(&report, config)),
+            // printed with the outcome, once it's known what was bumped
+            OutputFormat::Env => {}
+        }
+    }
+
+    if is_showing_skipped {
+        print_labelled(&format::skipped(&skipped, config), config);
+    }
+
+    if !peer_conflicts.is_empty() {
+        let conflicts = format::peer_conflicts(&peer_conflicts, config);
+        print_labelled(&conflicts, config);
+    }
+
+    if config.is_explain_major {
+        let majors: Vec<&Package> = packages
+            .iter()
+            .filter(|pkg| pkg.upgrade_type == UpgradeType::Major)
+            .collect();
+        let explained = fetch::attach(
+            &fetch::MajorHintsClient::new(config),
+            &majors,
+            |pkg| pkg.upgrade_version(&config.upgrade_style),
+            limits,
+        );
+
+        if !explained.is_empty() {
+            let explanations = format::major_explanations(&explained, config);
+            print_labelled(&explanations, config);
+        }
+    }
+
+    if config.is_fail_on_deprecated {
+        let targets: Vec<&Package> = packages.iter().collect();
+        let checked = fetch::attach(
+            &fetch::DeprecationClient::new(config),
+            &targets,
+            |pkg| pkg.install_version.as_str(),
+            limits,
+        );
+        let deprecated = fetch::deprecated_targets(&checked, |pkg| pkg.install_version.as_str());
+
+        if !deprecated.is_empty() {
+            let deprecated: Vec<String> = deprecated
+                .iter()
+                .map(|target| format!("{} ({})", target.spec, target.message))
+                .collect();
+            return Err(
+                format!("Target versions are deprecated: {}", deprecated.join("; ")).into(),
+            );
+        }
+    }
+
+    print_labelled("\n", config);
+
+    let mut install_order: Vec<&Package> = packages.iter().collect();
+
+    if let Some(order) = config.install_order {
+        let declared = manifest::declared_names(&manifest_src).unwrap_or_default();
+        install::order_for_install(&mut install_order, order, &declared);
+    }
+
+    let package_args: Vec<String> = install_order
+        .iter()
+        .map(|pkg| pkg.install_spec(config).to_string())
+        .collect();
+
+    let conflict_args: Vec<String> = peer_conflicts
+        .iter()
+        .filter(|conflict| !packages.iter().any(|pkg| pkg.name == conflict.name))
+        .map(|conflict| conflict.install_cmd())
+        .collect();
+
+    let mut cmd_args: Vec<String> = package_args.iter().chain(&conflict_args).cloned().collect();
+
+    let conflicts = install::find_conflicts(&cmd_args);
+
+    if !conflicts.is_empty() {
+        let conflicts: Vec<String> = conflicts.iter().map(|c| c.to_string()).collect();
+        return Err(format!(
+            "Packages would be installed at more than one version: {}",
+            conflicts.join("; ")
+        )
+        .into());
+    }
+
+    let plan = format::json(
+        &format::plan(&packages, &skipped, &cmd_args, config),
+        config,
+    )?;
+
+    if config.output_format == OutputFormat::Json {
+        println!("{}", plan);
+    }
+
+    if let Some(path) = &config.report_diff_against {
+        let earlier = plan_diff::load(path, config)?;
+        let changes = plan_diff::diff(&earlier, &packages, &config.upgrade_style);
+        print_labelled(&format::plan_diff(&changes, config), config);
+    }
+
+    if let Some(fd) = config.plan_fd {
+        utility::write_to_fd(fd, &plan)?;
+    }
+
+    if let Some(path) = config.artifact_path(utility::PLAN_ARTIFACT) {
+        utility::write_artifact(&path, &plan)?;
+        print_message(
+            &format!("Plan written to {}", path.display()),
+            Icon::PointRight,
+            config,
         );
     }
-    println!();
+
+    if config.is_print_install_cmd_only {
+        // left unlabelled, so it can be copied as is
+        println!("{}", npm_cmd::install_command_line(&cmd_args, config));
+        return Ok(RunResult::UpdatesAvailable(cmd_args.len()));
+    }
 
     if config.is_dry_run {
+        let outcome = RunOutcome::new(&packages, &skipped, filtered_out, None, config);
+        outcome.write(config)?;
+
+        if config.output_format == OutputFormat::Env {
+            print!("{}", format::env(&outcome));
+        }
+
         print_message(
             &format!(
-                "{} updates available, pass --update or -u to update",
-                packages.len(),
+                "{} updates available ({}), pass --update or -u to update",
+                cmd_args.len(),
+                format::upgrade_counts(&packages, config),
             ),
-            &ROCKET,
+            Icon::Rocket,
+            config,
         );
-        process::exit(0);
+        return Ok(RunResult::UpdatesAvailable(cmd_args.len()));
     }
 
-    let cmd_args: Vec<String> = packages
-        .iter()
-        .map(|pkg| String::from(&pkg.install_cmd))
-        .collect();
+    if install::is_below_min_bumps(cmd_args.len(), config.min_bumps) {
+        print_message(
+            &format!(
+                "Not bumping - only {} updates available, fewer than --min-bumps {}",
+                cmd_args.len(),
+                config.min_bumps.unwrap_or_default()
+            ),
+            Icon::PointRight,
+            config,
+        );
+        return Ok(RunResult::UpdatesAvailable(cmd_args.len()));
+    }
 
-    print_message(&format!("Upgrading {} packages", cmd_args.len()), &DIZZY);
+    if let Some(seed) = config.randomize_seed {
+        install::shuffle(&mut cmd_args, seed);
+        print_message(
+            &format!("Shuffled the install order with seed {}", seed),
+            Icon::PointRight,
+            config,
+        );
+    }
 
-    let mut install = process::Command::new(NPM)
-        .stdout(config.stdout_method)
-        .stderr(config.stderr_method)
-        .arg("i")
-        .args(&cmd_args)
-        .args(&config.additional_install_args)
-        .spawn()
-        .unwrap_or_else(|err| {
-            eprintln!("{}", err);
-            process::exit(70)
-        });
+    print_message(
+        &format!(
+            "Upgrading {} packages ({})",
+            cmd_args.len(),
+            format::upgrade_counts(&packages, config)
+        ),
+        Icon::Dizzy,
+        config,
+    );
 
-    let status = install.wait().unwrap_or_else(|err| {
-        eprintln!("{}", err);
-        process::exit(70)
-    });
+    // npm writes every bumped range with the same prefix, whatever was declared before
+    let manifest_before = if config.is_keep_range_width {
+        Some(manifest::read_manifest()?)
+    } else {
+        None
+    };
 
-    if status.success() {
-        print_message("All packages bumped", &TROPHY);
+    let mut results = if config.is_widen_mode {
+        npm_cmd::widen_ranges(&packages, config)?;
+        install::widened(&package_args, &conflict_args, config, deadline)?
+    } else if config.is_isolated {
+        install::isolated(
+            &cmd_args,
+            &commit_messages(&packages, config)?,
+            config,
+            deadline,
+        )?
     } else {
-        print_message("Issue installing packages - try running manually", &CROSS);
+        install::batched(&cmd_args, config, deadline)?
+    };
+
+    if config.is_fallback_to_wanted {
+        let fell_back = install::fall_back_to_wanted(&packages, &mut results, config, deadline)?;
+
+        if !fell_back.is_empty() {
+            print_message(
+                &format!(
+                    "Fell back to the wanted version for {}",
+                    fell_back.join(", ")
+                ),
+                Icon::Warning,
+                config,
+            );
+        }
+    }
+
+    let summary = install::summarize(&results);
+
+    if let Some(before) = manifest_before {
+        if let Some(kept) = manifest::keep_range_operators(&before, &manifest::read_manifest()?)? {
+            manifest::write_manifest(&kept)?;
+            print_message(
+                "Put back the declared range operators in package.json",
+                Icon::PointRight,
+                config,
+            );
+
+            // otherwise the lockfile's root ranges no longer match, and `npm ci` refuses it
+            if !config.is_no_lockfile && !npm_cmd::sync_lockfile(config, deadline)?.success() {
+                return Err("npm install --package-lock-only failed - try running manually".into());
+            }
+        }
     }
+
+    let distance = version::total_distance(
+        packages
+            .iter()
+            .filter(|pkg| {
+                summary
+                    .installed
+                    .contains(&pkg.install_spec(config).to_string())
+            })
+            .map(|pkg| {
+                (
+                    pkg.current_version.as_str(),
+                    pkg.upgrade_version(&config.upgrade_style),
+                )
+            }),
+    );
+
+    if !distance.is_zero() {
+        print_message(
+            &format!("Bumped across {}", distance),
+            Icon::PointRight,
+            config,
+        );
+    }
+
+    let (message, icon) = summary.message();
+    print_message(&message, icon, config);
+
+    let outcome = RunOutcome::new(&packages, &skipped, filtered_out, Some(&summary), config);
+    outcome.write(config)?;
+
+    if config.output_format == OutputFormat::Env {
+        print!("{}", format::env(&outcome));
+    }
+
+    if config.is_verify {
+        print_message(
+            "Verifying the bumped packages...",
+            Icon::MagnifyingGlass,
+            config,
+        );
+
+        let after = npm_cmd::run(config, deadline)?;
+        let unapplied = install::find_unapplied(&packages, &after, &config.upgrade_style);
+
+        if unapplied.is_empty() {
+            print_message("All bumped packages verified", Icon::Trophy, config);
+        } else {
+            let unapplied: Vec<String> = unapplied.iter().map(|u| u.to_string()).collect();
+            print_message(
+                &format!(
+                    "{} packages are still outdated after bumping: {}",
+                    unapplied.len(),
+                    unapplied.join("; ")
+                ),
+                Icon::Cross,
+                config,
+            );
+        }
+    }
+
+    if config.is_verify_checksums && !summary.installed.is_empty() {
+        let bumped: Vec<&str> = packages
+            .iter()
+            .filter(|pkg| {
+                summary
+                    .installed
+                    .contains(&pkg.install_spec(config).to_string())
+            })
+            .map(|pkg| pkg.name.as_str())
+            .collect();
+
+        match manifest::read_lockfile() {
+            Ok(lockfile) => {
+                let problems = manifest::integrity_problems(&lockfile, &bumped)?;
+
+                if problems.is_empty() {
+                    print_message(
+                        "All bumped packages have integrity hashes",
+                        Icon::Trophy,
+                        config,
+                    );
+                } else {
+                    let problems: Vec<String> = problems
+                        .iter()
+                        .map(|(name, problem)| format!("{} ({})", name, problem))
+                        .collect();
+                    print_message(
+                        &format!(
+                            "Integrity hashes look wrong, which can mean a registry or tarball issue: {}",
+                            problems.join("; ")
+                        ),
+                        Icon::Cross,
+                        config,
+                    );
+                }
+            }
+            Err(_) => print_message(
+                "No package-lock.json to check the integrity hashes in, skipping --verify-checksums",
+                Icon::Warning,
+                config,
+            ),
+        }
+    }
+
+    if config.is_smoke_test && !summary.installed.is_empty() {
+        print_message(
+            "Checking the bumped packages still load...",
+            Icon::MagnifyingGlass,
+            config,
+        );
+
+        let bumped = packages
+            .iter()
+            .filter(|pkg| {
+                summary
+                    .installed
+                    .contains(&pkg.install_spec(config).to_string())
+            })
+            .map(|pkg| pkg.name.as_str());
+        let results = smoke::check_all(bumped, |name| smoke::require(name, config, deadline))?;
+        let failed = smoke::failed_names(&results);
+
+        if failed.is_empty() {
+            print_message("All bumped packages load", Icon::Trophy, config);
+        } else {
+            print_message(
+                &format!(
+                    "{} bumped packages fail to load: {}",
+                    failed.len(),
+                    failed.join(", ")
+                ),
+                Icon::Cross,
+                config,
+            );
+        }
+    }
+
+    if !config.follow_up_steps.is_empty() {
+        if summary.failed.is_empty() || config.is_keep_going {
+            let results =
+                steps::run_steps(&config.follow_up_steps, config.is_keep_going, |step| {
+                    print_message(
+                        &format!("Running npm {}", step.command()),
+                        Icon::Dizzy,
+                        config,
+                    );
+                    Ok::<bool, Box<dyn Error>>(npm_cmd::run_step(step, config, deadline)?.success())
+                })?;
+            let failed = steps::failed_commands(&results);
+
+            if !failed.is_empty() {
+                return Err(format!("Follow-up steps failed: {}", failed.join(", ")).into());
+            }
+        } else {
+            print_message(
+                "Skipping follow-up steps after the failed install (pass --keep-going to run them)",
+                Icon::Warning,
+                config,
+            );
+        }
+    }
+
+    Ok(RunResult::Bumped {
+        installed: summary.installed.len(),
+        failed: summary.failed.len(),
+    })
 }
@@ -0,0 +1,865 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::BTreeMap, error::Error, fmt, fs, path::Path};
+
+use crate::utility::{Config, SavePrefix};
+
+pub const MANIFEST: &str = "package.json";
+
+pub const LOCKFILE: &str = "package-lock.json";
+
+pub const DEPENDENCY_SECTIONS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "optionalDependencies",
+    "peerDependencies",
+];
+
+pub fn read_manifest() -> Result<String, Box<dyn Error>> {
+    Ok(fs::read_to_string(MANIFEST)?)
+}
+
+pub fn write_manifest(contents: &str) -> Result<(), Box<dyn Error>> {
+    Ok(fs::write(MANIFEST, contents)?)
+}
+
+pub fn read_lockfile() -> Result<String, Box<dyn Error>> {
+    Ok(fs::read_to_string(LOCKFILE)?)
+}
+
+/// The files a bump changes, leaving out the lockfile when there isn't one (e.g. --no-lockfile)
+pub fn committed_files() -> Vec<&'static str> {
+    [MANIFEST, LOCKFILE]
+        .iter()
+        .copied()
+        .filter(|path| Path::new(path).exists())
+        .collect()
+}
+
+/// Which section of package.json a dependency is declared in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyType {
+    /// dependencies
+    Prod,
+    /// devDependencies
+    Dev,
+    /// optionalDependencies
+    Optional,
+    /// peerDependencies
+    Peer,
+}
+
+impl DependencyType {
+    fn from_section(section: &str) -> Option<DependencyType> {
+        match section {
+            "dependencies" => Some(DependencyType::Prod),
+            "devDependencies" => Some(DependencyType::Dev),
+            "optionalDependencies" => Some(DependencyType::Optional),
+            "peerDependencies" => Some(DependencyType::Peer),
+            _ => None,
+        }
+    }
+}
+
+/// Where a declared dependency is installed from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    Registry,
+    LocalPath,
+    Git,
+}
+
+const LOCAL_PREFIXES: [&str; 2] = ["file:", "link:"];
+
+const GIT_PREFIXES: [&str; 5] = ["git:", "git+", "github:", "gitlab:", "bitbucket:"];
+
+/// Works out the source from a declared range, e.g. `file:../local` or `git+https://...`.
+/// GitHub `owner/repo` shorthand counts as git, while tarball urls count as a local path as
+/// neither has a registry version to bump to
+pub fn source(range: &str) -> Source {
+    let range = range.trim();
+
+    if LOCAL_PREFIXES.iter().any(|p| range.starts_with(p))
+        || range.starts_with("http://")
+        || range.starts_with("https://")
+        || range.starts_with('.')
+        || range.starts_with('/')
+    {
+        return Source::LocalPath;
+    }
+
+    let is_shorthand = range.contains('/') && !range.contains(' ') && !range.starts_with("npm:");
+
+    if GIT_PREFIXES.iter().any(|p| range.starts_with(p)) || is_shorthand {
+        return Source::Git;
+    }
+
+    Source::Registry
+}
+
+/// Every declared dependency and its range, across all the dependency sections
+pub fn dependency_map(manifest: &str) -> Result<BTreeMap<String, String>, serde_json::Error> {
+    let manifest: Value = serde_json::from_str(manifest)?;
+    let mut deps = BTreeMap::new();
+
+    for section in DEPENDENCY_SECTIONS.iter() {
+        if let Some(entries) = manifest.get(section).and_then(|s| s.as_object()) {
+            for (name, range) in entries.iter() {
+                if let Some(range) = range.as_str() {
+                    deps.insert(name.into(), range.into());
+                }
+            }
+        }
+    }
+
+    Ok(deps)
+}
+
+/// The names of the declared dependencies, in the order package.json lists them
+pub fn declared_names(manifest: &str) -> Result<Vec<String>, serde_json::Error> {
+    let manifest: Value = serde_json::from_str(manifest)?;
+    let mut names: Vec<String> = vec![];
+
+    for section in DEPENDENCY_SECTIONS.iter() {
+        if let Some(entries) = manifest.get(section).and_then(|s| s.as_object()) {
+            for name in entries.keys() {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+        }
+    }
+
+    Ok(names)
+}
+
+/// The sections each declared dependency appears in, e.g. a peer dependency is usually also a
+/// dev dependency
+pub fn dependency_types(
+    manifest: &str,
+) -> Result<BTreeMap<String, Vec<DependencyType>>, serde_json::Error> {
+    let manifest: Value = serde_json::from_str(manifest)?;
+    let mut types: BTreeMap<String, Vec<DependencyType>> = BTreeMap::new();
+
+    for section in DEPENDENCY_SECTIONS.iter() {
+        let dep_type = match DependencyType::from_section(section) {
+            Some(dep_type) => dep_type,
+            None => continue,
+        };
+
+        if let Some(entries) = manifest.get(section).and_then(|s| s.as_object()) {
+            for name in entries.keys() {
+                types.entry(name.into()).or_default().push(dep_type);
+            }
+        }
+    }
+
+    Ok(types)
+}
+
+/// The installed version of each top level package in a package-lock.json, from the `packages`
+/// section (lockfile v2+) or the `dependencies` section (v1)
+pub fn lockfile_versions(lockfile: &str) -> Result<BTreeMap<String, String>, serde_json::Error> {
+    let lockfile: Value = serde_json::from_str(lockfile)?;
+    let mut versions = BTreeMap::new();
+
+    for (name, entry) in top_level_entries(&lockfile) {
+        if let Some(version) = entry.get("version").and_then(|v| v.as_str()) {
+            versions.insert(name.into(), version.into());
+        }
+    }
+
+    Ok(versions)
+}
+
+/// The lockfile entry of each top level package, by name
+fn top_level_entries(lockfile: &Value) -> Vec<(&str, &Value)> {
+    if let Some(packages) = lockfile.get("packages").and_then(|p| p.as_object()) {
+        packages
+            .iter()
+            .filter_map(|(path, entry)| {
+                // nested node_modules are other packages' dependencies
+                match path.strip_prefix("node_modules/") {
+                    Some(name) if !name.contains("/node_modules/") => Some((name, entry)),
+                    _ => None,
+                }
+            })
+            .collect()
+    } else if let Some(deps) = lockfile.get("dependencies").and_then(|d| d.as_object()) {
+        deps.iter()
+            .map(|(name, entry)| (name.as_str(), entry))
+            .collect()
+    } else {
+        vec![]
+    }
+}
+
+/// What's wrong with a package's `integrity` field in the lockfile
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IntegrityProblem {
+    /// The package isn't in the lockfile at all
+    NotLocked,
+    Missing,
+    /// Not a `sha512-` hash of the right length
+    Malformed(String),
+}
+
+impl fmt::Display for IntegrityProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntegrityProblem::NotLocked => write!(f, "not in {}", LOCKFILE),
+            IntegrityProblem::Missing => write!(f, "no integrity hash"),
+            IntegrityProblem::Malformed(integrity) => {
+                write!(f, "malformed integrity hash \"{}\"", integrity)
+            }
+        }
+    }
+}
+
+/// Whether an `integrity` value has a well-formed sha512 hash, e.g. "sha512-<88 base64 chars>".
+/// Several space separated hashes are allowed, as long as one of them is sha512
+pub fn check_integrity(integrity: Option<&str>) -> Option<IntegrityProblem> {
+    let integrity = match integrity {
+        Some(integrity) if !integrity.trim().is_empty() => integrity,
+        _ => return Some(IntegrityProblem::Missing),
+    };
+
+    let is_sha512 = |hash: &str| {
+        // 64 bytes of base64, padded
+        hash.strip_prefix("sha512-").is_some_and(|digest| {
+            digest.len() == 88
+                && digest.ends_with("==")
+                && digest[..86]
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/')
+        })
+    };
+
+    if integrity.split_whitespace().any(is_sha512) {
+        None
+    } else {
+        Some(IntegrityProblem::Malformed(integrity.into()))
+    }
+}
+
+/// The named packages whose lockfile integrity is missing or malformed. Linked packages are
+/// left out, as they aren't fetched from a registry
+pub fn integrity_problems(
+    lockfile: &str,
+    names: &[&str],
+) -> Result<Vec<(String, IntegrityProblem)>, serde_json::Error> {
+    let lockfile: Value = serde_json::from_str(lockfile)?;
+    let entries: BTreeMap<&str, &Value> = top_level_entries(&lockfile).into_iter().collect();
+
+    let problems = names
+        .iter()
+        .filter_map(|name| {
+            let problem = match entries.get(name) {
+                None => Some(IntegrityProblem::NotLocked),
+                Some(entry) if entry.get("link").and_then(|l| l.as_bool()) == Some(true) => None,
+                Some(entry) => check_integrity(entry.get("integrity").and_then(|i| i.as_str())),
+            };
+
+            problem.map(|problem| (String::from(*name), problem))
+        })
+        .collect();
+
+    Ok(problems)
+}
+
+/// A dependency which differs between two versions of a manifest or lockfile
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DependencyChange {
+    pub name: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Every dependency added, removed or changed between two maps, sorted by name
+pub fn dependency_changes(
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, String>,
+) -> Vec<DependencyChange> {
+    let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .filter(|name| before.get(*name) != after.get(*name))
+        .map(|name| DependencyChange {
+            name: name.clone(),
+            before: before.get(name).cloned(),
+            after: after.get(name).cloned(),
+        })
+        .collect()
+}
+
+/// Names of the dependencies added, or whose range changed, between two dependency maps
+pub fn changed_dependencies(
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, String>,
+) -> Vec<String> {
+    after
+        .iter()
+        .filter(|(name, range)| before.get(*name) != Some(*range))
+        .map(|(name, _)| name.into())
+        .collect()
+}
+
+fn is_exact_version(range: &str) -> bool {
+    let core = range.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<&str> = core.split('.').collect();
+
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// The range npm will write for `version`, with the --save-prefix (`^` by default). With
+/// --keep-range-width the declared operator is put back afterwards, so it's kept here too
+pub fn bumped_range(declared: &str, version: &str, config: &Config) -> String {
+    let prefix = config.save_prefix.unwrap_or(SavePrefix::Caret);
+    let written = format!("{}{}", prefix.prefix(), version);
+
+    if config.is_keep_range_width {
+        if let Some(kept) = with_original_operator(declared, &written) {
+            return kept;
+        }
+    }
+
+    written
+}
+
+/// Whether a file still has the markers git leaves around a merge conflict
+pub fn has_conflict_markers(src: &str) -> bool {
+    src.lines().any(|line| {
+        line.starts_with("<<<<<<<")
+            || line.starts_with(">>>>>>>")
+            || line.starts_with("|||||||")
+            || line == "======="
+    })
+}
+
+// The operator of a plain `^1.2.3`, `~1.2.3` or `1.2.3` range, None for anything else
+fn range_operator(range: &str) -> Option<SavePrefix> {
+    let (prefix, version) = match range.chars().next() {
+        Some('^') => (SavePrefix::Caret, &range[1..]),
+        Some('~') => (SavePrefix::Tilde, &range[1..]),
+        _ => (SavePrefix::Exact, range),
+    };
+
+    is_exact_version(version).then_some(prefix)
+}
+
+/// The range npm wrote with the operator of the range declared before, e.g. `~1.2.0` then
+/// `^1.3.0` gives `~1.3.0`. None when either isn't a plain operator and version
+pub fn with_original_operator(before: &str, after: &str) -> Option<String> {
+    let prefix = range_operator(before)?;
+    range_operator(after)?;
+    let version = after.trim_start_matches(['^', '~']);
+
+    Some(format!("{}{}", prefix.prefix(), version))
+}
+
+/// Puts back the operators `before` declared, in the ranges of `after`. None when there's
+/// nothing to put back
+pub fn keep_range_operators(
+    before: &str,
+    after: &str,
+) -> Result<Option<String>, serde_json::Error> {
+    let before: Value = serde_json::from_str(before)?;
+    let mut after: Value = serde_json::from_str(after)?;
+    let mut is_changed = false;
+
+    for section in DEPENDENCY_SECTIONS.iter() {
+        let deps = match after.get_mut(section).and_then(|deps| deps.as_object_mut()) {
+            Some(deps) => deps,
+            None => continue,
+        };
+
+        for (name, range) in deps.iter_mut() {
+            let declared = before[section][name].as_str();
+            let kept = declared
+                .zip(range.as_str())
+                .and_then(|(declared, range)| with_original_operator(declared, range));
+
+            if let Some(kept) = kept {
+                if range.as_str() != Some(kept.as_str()) {
+                    *range = Value::from(kept);
+                    is_changed = true;
+                }
+            }
+        }
+    }
+
+    if !is_changed {
+        return Ok(None);
+    }
+
+    Ok(Some(format!("{}\n", serde_json::to_string_pretty(&after)?)))
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod has_conflict_markers_tests {
+    use super::*;
+
+    #[test]
+    fn finds_conflicts() {
+        let lockfile = r#"{
+  "name": "test",
+  "lockfileVersion": 3,
+  "packages": {
+    "node_modules/debug": {
+<<<<<<< HEAD
+      "version": "4.3.4",
+=======
+      "version": "4.3.2",
+>>>>>>> feature/bump
+      "license": "MIT"
+    }
+  }
+}
+"#;
+
+        assert!(has_conflict_markers(lockfile));
+    }
+
+    #[test]
+    fn ignores_resolved_lockfiles() {
+        let lockfile = r#"{
+  "name": "test",
+  "packages": {
+    "node_modules/debug": { "version": "4.3.4", "resolved": "https://example.com/=======.tgz" }
+  }
+}
+"#;
+
+        assert!(!has_conflict_markers(lockfile));
+        assert!(!has_conflict_markers(""));
+    }
+}
+
+#[cfg(test)]
+mod keep_range_operators_tests {
+    use super::*;
+
+    #[test]
+    fn puts_back_the_operator() {
+        assert_eq!(
+            with_original_operator("~1.2.0", "^1.3.0"),
+            Some(String::from("~1.3.0"))
+        );
+        assert_eq!(
+            with_original_operator("1.2.0", "^2.0.0"),
+            Some(String::from("2.0.0"))
+        );
+        assert_eq!(
+            with_original_operator("^1.0.0", "2.0.0"),
+            Some(String::from("^2.0.0"))
+        );
+        assert_eq!(
+            with_original_operator("^1.0.0", "^2.0.0-beta.1"),
+            Some(String::from("^2.0.0-beta.1"))
+        );
+    }
+
+    #[test]
+    fn leaves_other_ranges_alone() {
+        assert_eq!(with_original_operator(">=1.0.0", "^2.0.0"), None);
+        assert_eq!(with_original_operator("1.x", "^2.0.0"), None);
+        assert_eq!(with_original_operator("^1.0.0", "github:user/repo"), None);
+    }
+
+    #[test]
+    fn rewrites_only_changed_ranges() {
+        let before = r#"{
+  "name": "test",
+  "dependencies": { "polished": "~3.6.5", "react": "^18.1.0", "left-pad": "*" },
+  "devDependencies": { "debug": "4.3.2" }
+}"#;
+        let after = r#"{
+  "name": "test",
+  "dependencies": { "polished": "^3.7.2", "react": "^18.2.0", "left-pad": "^1.3.0" },
+  "devDependencies": { "debug": "^4.3.4", "ms": "^2.1.3" }
+}"#;
+        let expected = r#"{
+  "name": "test",
+  "dependencies": {
+    "polished": "~3.7.2",
+    "react": "^18.2.0",
+    "left-pad": "^1.3.0"
+  },
+  "devDependencies": {
+    "debug": "4.3.4",
+    "ms": "^2.1.3"
+  }
+}
+"#;
+
+        assert_eq!(
+            keep_range_operators(before, after).unwrap(),
+            Some(String::from(expected))
+        );
+    }
+
+    #[test]
+    fn nothing_to_put_back() {
+        let manifest = r#"{ "dependencies": { "react": "^18.2.0" } }"#;
+
+        assert_eq!(keep_range_operators(manifest, manifest).unwrap(), None);
+    }
+}
+
+#[cfg(test)]
+mod bumped_range_tests {
+    use super::*;
+    use crate::utility::Args;
+
+    #[test]
+    fn uses_caret_by_default() {
+        let config = Config::new_from_args(Args::default());
+
+        assert_eq!(bumped_range("^1.2.0", "1.3.0", &config), "^1.3.0");
+        assert_eq!(bumped_range("~1.2.0", "1.2.5", &config), "^1.2.5");
+        assert_eq!(bumped_range("1.2.0", "1.2.5", &config), "^1.2.5");
+        assert_eq!(bumped_range("*", "2.0.0", &config), "^2.0.0");
+        assert_eq!(bumped_range("", "3.0.0", &config), "^3.0.0");
+    }
+
+    #[test]
+    fn uses_save_prefix() {
+        let config = Config::new_from_args(Args {
+            save_prefix: Some(SavePrefix::Tilde),
+            ..Args::default()
+        });
+
+        assert_eq!(bumped_range("^1.2.0", "1.3.0", &config), "~1.3.0");
+        assert_eq!(bumped_range("1.2.0", "1.2.5", &config), "~1.2.5");
+    }
+
+    #[test]
+    fn keeps_declared_operator_with_keep_range_width() {
+        let config = Config::new_from_args(Args {
+            keep_range_width: true,
+            ..Args::default()
+        });
+
+        assert_eq!(bumped_range("^1.2.0", "1.3.0", &config), "^1.3.0");
+        assert_eq!(bumped_range("~1.2.0", "1.2.5", &config), "~1.2.5");
+        assert_eq!(bumped_range("1.2.0", "1.2.5", &config), "1.2.5");
+        // anything more exotic is left as npm wrote it
+        assert_eq!(bumped_range("1.x", "1.4.0", &config), "^1.4.0");
+        assert_eq!(bumped_range(">=1", "3.0.0", &config), "^3.0.0");
+    }
+}
+
+#[cfg(test)]
+mod changed_dependencies_tests {
+    use super::*;
+
+    const BEFORE: &str = r#"{
+        "dependencies": { "left-pad": "^1.2.0", "polished": "^3.6.5" },
+        "devDependencies": { "debug": "^4.3.2", "removed": "1.0.0" }
+    }"#;
+
+    const AFTER: &str = r#"{
+        "dependencies": { "left-pad": "^1.2.0", "polished": "^4.0.0", "@jonshort/cenv": "^0.0.6" },
+        "devDependencies": { "debug": "^4.3.2" },
+        "peerDependencies": { "react": ">=17" }
+    }"#;
+
+    #[test]
+    fn lists_added_and_changed() {
+        let before = dependency_map(BEFORE).unwrap();
+        let after = dependency_map(AFTER).unwrap();
+
+        assert_eq!(
+            changed_dependencies(&before, &after),
+            vec!["@jonshort/cenv", "polished", "react"]
+        );
+    }
+
+    #[test]
+    fn nothing_changed() {
+        let deps = dependency_map(BEFORE).unwrap();
+
+        assert!(changed_dependencies(&deps, &deps).is_empty());
+    }
+
+    #[test]
+    fn everything_is_new_without_history() {
+        let after = dependency_map(AFTER).unwrap();
+
+        assert_eq!(changed_dependencies(&BTreeMap::new(), &after).len(), 5);
+    }
+
+    #[test]
+    fn reads_all_sections() {
+        let deps = dependency_map(AFTER).unwrap();
+
+        assert_eq!(deps.get("debug"), Some(&String::from("^4.3.2")));
+        assert_eq!(deps.get("react"), Some(&String::from(">=17")));
+        assert!(dependency_map("[]").unwrap().is_empty());
+        assert!(dependency_map("not json").is_err());
+    }
+}
+
+#[cfg(test)]
+mod declared_names_tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_declared_order() {
+        let manifest = r#"{
+            "dependencies": { "polished": "^4.0.0", "debug": "^4.3.2" },
+            "devDependencies": { "react": "^18.0.0", "chalk": "^4.1.0" },
+            "peerDependencies": { "react": ">=17" }
+        }"#;
+
+        assert_eq!(
+            declared_names(manifest).unwrap(),
+            vec!["polished", "debug", "react", "chalk"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod integrity_problems_tests {
+    use super::*;
+
+    const SHA512: &str = "sha512-A8GKp77lZVWGdw/OAHZcOSfurNwvFDGrxvR14qdVQbJjoB/+AgPy/hpzv4YjG2rMoYV6PKMtJ3dDBqP5Jy/3eQ==";
+
+    #[test]
+    fn accepts_sha512() {
+        assert_eq!(check_integrity(Some(SHA512)), None);
+        // older lockfiles can list a sha1 alongside
+        assert_eq!(
+            check_integrity(Some(&format!(
+                "sha1-2jmNCr9zZsMoYkq2Aqn8y+jfxB8= {}",
+                SHA512
+            ))),
+            None
+        );
+    }
+
+    #[test]
+    fn flags_missing_and_malformed() {
+        assert_eq!(check_integrity(None), Some(IntegrityProblem::Missing));
+        assert_eq!(check_integrity(Some("")), Some(IntegrityProblem::Missing));
+        assert_eq!(
+            check_integrity(Some("sha1-2jmNCr9zZsMoYkq2Aqn8y+jfxB8=")),
+            Some(IntegrityProblem::Malformed(String::from(
+                "sha1-2jmNCr9zZsMoYkq2Aqn8y+jfxB8="
+            )))
+        );
+        assert_eq!(
+            check_integrity(Some("sha512-truncated==")),
+            Some(IntegrityProblem::Malformed(String::from(
+                "sha512-truncated=="
+            )))
+        );
+    }
+
+    #[test]
+    fn checks_the_named_packages() {
+        let lockfile = format!(
+            r#"{{
+            "lockfileVersion": 3,
+            "packages": {{
+                "": {{ "name": "app" }},
+                "node_modules/debug": {{ "version": "4.3.4", "integrity": "{0}" }},
+                "node_modules/ms": {{ "version": "2.1.3" }},
+                "node_modules/polished": {{ "version": "4.2.2", "integrity": "sha512-" }},
+                "node_modules/linked": {{ "resolved": "../linked", "link": true }},
+                "node_modules/react": {{ "version": "18.2.0", "integrity": "{0}" }}
+            }}
+        }}"#,
+            SHA512
+        );
+
+        assert_eq!(
+            integrity_problems(&lockfile, &["debug", "ms", "polished", "linked", "chalk"]).unwrap(),
+            vec![
+                (String::from("ms"), IntegrityProblem::Missing),
+                (
+                    String::from("polished"),
+                    IntegrityProblem::Malformed(String::from("sha512-"))
+                ),
+                (String::from("chalk"), IntegrityProblem::NotLocked),
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod dependency_types_tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"{
+        "dependencies": { "polished": "^4.0.0" },
+        "devDependencies": { "debug": "^4.3.2", "react": "^18.0.0" },
+        "optionalDependencies": { "fsevents": "^2.3.2" },
+        "peerDependencies": { "react": ">=17" }
+    }"#;
+
+    #[test]
+    fn classifies_by_section() {
+        let types = dependency_types(MANIFEST).unwrap();
+
+        assert_eq!(types.get("polished"), Some(&vec![DependencyType::Prod]));
+        assert_eq!(types.get("debug"), Some(&vec![DependencyType::Dev]));
+        assert_eq!(types.get("fsevents"), Some(&vec![DependencyType::Optional]));
+        assert_eq!(types.get("left-pad"), None);
+    }
+
+    #[test]
+    fn keeps_every_section() {
+        let types = dependency_types(MANIFEST).unwrap();
+
+        assert_eq!(
+            types.get("react"),
+            Some(&vec![DependencyType::Dev, DependencyType::Peer])
+        );
+    }
+
+    #[test]
+    fn handles_missing_sections() {
+        assert!(dependency_types("{}").unwrap().is_empty());
+        assert!(dependency_types("not json").is_err());
+    }
+}
+
+#[cfg(test)]
+mod source_tests {
+    use super::*;
+
+    #[test]
+    fn detects_local_paths() {
+        for range in [
+            "file:../local",
+            "file:vendor/pkg.tgz",
+            "link:../linked",
+            "../relative",
+            "/abs/path",
+            "https://example.com/pkg-1.0.0.tgz",
+        ] {
+            assert_eq!(source(range), Source::LocalPath, "{}", range);
+        }
+    }
+
+    #[test]
+    fn detects_git() {
+        for range in [
+            "git+https://github.com/JonShort/cenv.git",
+            "git+ssh://git@github.com/JonShort/cenv.git#v1.0.0",
+            "git://github.com/JonShort/cenv.git",
+            "github:JonShort/cenv",
+            "gitlab:group/project",
+            "JonShort/cenv#main",
+        ] {
+            assert_eq!(source(range), Source::Git, "{}", range);
+        }
+    }
+
+    #[test]
+    fn registry_ranges() {
+        for range in [
+            "^1.2.0",
+            "~3.6.5",
+            "1.0.0 - 2.0.0",
+            ">=1.0.0 <2.0.0 || 3.x",
+            "latest",
+            "*",
+            "npm:debug@^4.0.0",
+        ] {
+            assert_eq!(source(range), Source::Registry, "{}", range);
+        }
+    }
+}
+
+#[cfg(test)]
+mod dependency_changes_tests {
+    use super::*;
+
+    fn map(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(name, version)| (String::from(*name), String::from(*version)))
+            .collect()
+    }
+
+    fn change(name: &str, before: Option<&str>, after: Option<&str>) -> DependencyChange {
+        DependencyChange {
+            name: name.into(),
+            before: before.map(String::from),
+            after: after.map(String::from),
+        }
+    }
+
+    #[test]
+    fn finds_added_removed_and_changed() {
+        let before = map(&[("debug", "4.3.2"), ("left-pad", "1.2.0"), ("ms", "2.1.2")]);
+        let after = map(&[
+            ("@jonshort/cenv", "0.0.6"),
+            ("debug", "4.3.4"),
+            ("ms", "2.1.2"),
+        ]);
+
+        assert_eq!(
+            dependency_changes(&before, &after),
+            vec![
+                change("@jonshort/cenv", None, Some("0.0.6")),
+                change("debug", Some("4.3.2"), Some("4.3.4")),
+                change("left-pad", Some("1.2.0"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_changed() {
+        let deps = map(&[("debug", "4.3.2")]);
+
+        assert!(dependency_changes(&deps, &deps).is_empty());
+    }
+
+    #[test]
+    fn reads_lockfile_v3() {
+        let lockfile = r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app", "dependencies": { "debug": "^4.3.2" } },
+                "node_modules/debug": { "version": "4.3.4" },
+                "node_modules/@jonshort/cenv": { "version": "0.0.6" },
+                "node_modules/debug/node_modules/ms": { "version": "2.1.2" },
+                "node_modules/linked": { "resolved": "../linked", "link": true }
+            }
+        }"#;
+
+        assert_eq!(
+            lockfile_versions(lockfile).unwrap(),
+            map(&[("@jonshort/cenv", "0.0.6"), ("debug", "4.3.4")])
+        );
+    }
+
+    #[test]
+    fn reads_lockfile_v1() {
+        let lockfile = r#"{
+            "lockfileVersion": 1,
+            "dependencies": {
+                "debug": { "version": "4.3.4", "requires": { "ms": "2.1.2" } },
+                "ms": { "version": "2.1.2" }
+            }
+        }"#;
+
+        assert_eq!(
+            lockfile_versions(lockfile).unwrap(),
+            map(&[("debug", "4.3.4"), ("ms", "2.1.2")])
+        );
+    }
+}
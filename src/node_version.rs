@@ -0,0 +1,145 @@
+use std::{fs, path::Path};
+
+/// A Node version a project declares, and the file it was declared in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeVersion {
+    pub version: String,
+    pub source: &'static str,
+}
+
+type Parser = fn(&str) -> Option<String>;
+
+/// The version from an `.nvmrc`, e.g. "20.11.0", "v18" or "lts/iron"
+pub fn parse_nvmrc(src: &str) -> Option<String> {
+    src.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .find(|line| !line.is_empty())
+        .map(String::from)
+}
+
+/// The version from the `nodejs` line of an asdf `.tool-versions`, e.g. "nodejs 20.11.0". When
+/// several versions are listed, the first is the one asdf uses
+pub fn parse_tool_versions(src: &str) -> Option<String> {
+    src.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .find_map(|line| {
+            let mut fields = line.split_whitespace();
+
+            match fields.next() {
+                Some("nodejs") => fields.next().map(String::from),
+                _ => None,
+            }
+        })
+}
+
+/// The Node version declared in `dir`, trying `.nvmrc` and then `.tool-versions`
+pub fn detect(dir: &Path) -> Option<NodeVersion> {
+    let sources: [(&'static str, Parser); 2] = [
+        (".nvmrc", parse_nvmrc),
+        (".tool-versions", parse_tool_versions),
+    ];
+
+    sources.iter().find_map(|(source, parse)| {
+        let src = fs::read_to_string(dir.join(source)).ok()?;
+
+        parse(&src).map(|version| NodeVersion { version, source })
+    })
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod parse_tool_versions_tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_nodejs_line() {
+        let src = "\
+# managed by asdf
+python 3.12.1
+nodejs 20.11.0 18.19.0 # the first one is used
+ruby 3.3.0
+";
+
+        assert_eq!(parse_tool_versions(src), Some(String::from("20.11.0")));
+    }
+
+    #[test]
+    fn none_without_nodejs() {
+        assert_eq!(parse_tool_versions("python 3.12.1\n"), None);
+        assert_eq!(parse_tool_versions("# nodejs 20.11.0\n"), None);
+        assert_eq!(parse_tool_versions("nodejs\n"), None);
+        assert_eq!(parse_tool_versions(""), None);
+    }
+}
+
+#[cfg(test)]
+mod parse_nvmrc_tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_first_version() {
+        assert_eq!(parse_nvmrc("v20.11.0\n"), Some(String::from("v20.11.0")));
+        assert_eq!(
+            parse_nvmrc("# comment\n\nlts/iron\n"),
+            Some(String::from("lts/iron"))
+        );
+        assert_eq!(parse_nvmrc("\n"), None);
+    }
+}
+
+#[cfg(test)]
+mod detect_tests {
+    use super::*;
+
+    fn project(files: &[(&str, &str)]) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "npm-bumpall-node-version-{}-{}",
+            std::process::id(),
+            files.len()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+
+        for (name, contents) in files.iter() {
+            fs::write(dir.join(name), contents).unwrap();
+        }
+
+        dir
+    }
+
+    #[test]
+    fn falls_back_to_tool_versions() {
+        let dir = project(&[(".tool-versions", "nodejs 18.19.0\n")]);
+
+        assert_eq!(
+            detect(&dir),
+            Some(NodeVersion {
+                version: String::from("18.19.0"),
+                source: ".tool-versions",
+            })
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn prefers_nvmrc() {
+        let dir = project(&[
+            (".nvmrc", "20.11.0\n"),
+            (".tool-versions", "nodejs 18.19.0\n"),
+        ]);
+
+        assert_eq!(detect(&dir).map(|node| node.source), Some(".nvmrc"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn nothing_declared() {
+        let dir = project(&[]);
+
+        assert_eq!(detect(&dir), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
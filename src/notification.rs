@@ -0,0 +1,169 @@
+use crate::utility::Config;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RunResult {
+    UpToDate,
+    UpdatesAvailable(usize),
+    Bumped { installed: usize, failed: usize },
+    Stale(usize),
+}
+
+impl RunResult {
+    /// Combines the results of runs across several directories into one
+    pub fn merge(self, other: RunResult) -> RunResult {
+        use RunResult::*;
+
+        match (self, other) {
+            (Stale(a), Stale(b)) => Stale(a + b),
+            (Stale(a), _) | (_, Stale(a)) => Stale(a),
+            (
+                Bumped { installed, failed },
+                Bumped {
+                    installed: other_installed,
+                    failed: other_failed,
+                },
+            ) => Bumped {
+                installed: installed + other_installed,
+                failed: failed + other_failed,
+            },
+            (bumped @ Bumped { .. }, _) | (_, bumped @ Bumped { .. }) => bumped,
+            (UpdatesAvailable(a), UpdatesAvailable(b)) => UpdatesAvailable(a + b),
+            (available @ UpdatesAvailable(_), UpToDate) | (UpToDate, available) => available,
+        }
+    }
+}
+
+pub fn message(result: &RunResult) -> String {
+    match result {
+        RunResult::UpToDate => String::from("No outdated packages found"),
+        RunResult::UpdatesAvailable(count) => format!("{} updates available", count),
+        RunResult::Bumped { installed, failed } if *failed == 0 => {
+            format!("Bumped {} packages", installed)
+        }
+        RunResult::Bumped { installed, failed } => format!(
+            "Bumped {} of {} packages, {} failed",
+            installed,
+            installed + failed,
+            failed
+        ),
+        RunResult::Stale(count) => format!("{} in-range updates available", count),
+    }
+}
+
+#[cfg(feature = "desktop-notify")]
+fn show(body: &str) {
+    // not every platform/session can display notifications, which isn't worth failing over
+    let _ = notify_rust::Notification::new()
+        .summary("npm-bumpall")
+        .body(body)
+        .show();
+}
+
+#[cfg(not(feature = "desktop-notify"))]
+fn show(_body: &str) {}
+
+/// Fires a desktop notification summarizing the run, if --notify was passed
+pub fn send(result: &RunResult, config: &Config) {
+    if config.is_notify_enabled {
+        show(&message(result));
+    }
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod message_tests {
+    use super::*;
+
+    #[test]
+    fn up_to_date() {
+        assert_eq!(
+            message(&RunResult::UpToDate),
+            String::from("No outdated packages found")
+        );
+    }
+
+    #[test]
+    fn updates_available() {
+        assert_eq!(
+            message(&RunResult::UpdatesAvailable(4)),
+            String::from("4 updates available")
+        );
+    }
+
+    #[test]
+    fn all_bumped() {
+        assert_eq!(
+            message(&RunResult::Bumped {
+                installed: 3,
+                failed: 0
+            }),
+            String::from("Bumped 3 packages")
+        );
+    }
+
+    #[test]
+    fn some_failed() {
+        assert_eq!(
+            message(&RunResult::Bumped {
+                installed: 3,
+                failed: 2
+            }),
+            String::from("Bumped 3 of 5 packages, 2 failed")
+        );
+    }
+}
+
+#[cfg(test)]
+mod merge_tests {
+    use super::*;
+
+    #[test]
+    fn sums_like_results() {
+        assert_eq!(
+            RunResult::UpdatesAvailable(2).merge(RunResult::UpdatesAvailable(3)),
+            RunResult::UpdatesAvailable(5)
+        );
+        assert_eq!(
+            RunResult::Bumped {
+                installed: 2,
+                failed: 1
+            }
+            .merge(RunResult::Bumped {
+                installed: 4,
+                failed: 0
+            }),
+            RunResult::Bumped {
+                installed: 6,
+                failed: 1
+            }
+        );
+        assert_eq!(
+            RunResult::UpToDate.merge(RunResult::UpToDate),
+            RunResult::UpToDate
+        );
+    }
+
+    #[test]
+    fn keeps_most_significant_result() {
+        assert_eq!(
+            RunResult::UpToDate.merge(RunResult::UpdatesAvailable(1)),
+            RunResult::UpdatesAvailable(1)
+        );
+        assert_eq!(
+            RunResult::Bumped {
+                installed: 1,
+                failed: 0
+            }
+            .merge(RunResult::UpToDate),
+            RunResult::Bumped {
+                installed: 1,
+                failed: 0
+            }
+        );
+        assert_eq!(
+            RunResult::UpToDate.merge(RunResult::Stale(2)),
+            RunResult::Stale(2)
+        );
+    }
+}
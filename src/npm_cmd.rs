@@ -1,7 +1,24 @@
+use semver::Version;
+use serde::Deserialize;
 use serde_json::{Map, Value};
-use std::{error::Error, fs, process};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process, thread,
+    time::Duration,
+};
 
-use crate::utility::Config;
+use crate::audit::{self, SecurityFix};
+use crate::deadline::Deadline;
+use crate::emojis::Icon;
+use crate::manifest;
+use crate::package::{Package, MISSING};
+use crate::self_check;
+use crate::steps::Step;
+use crate::utility::{self, print_message, Config, SavePrefix};
 
 #[cfg(windows)]
 pub const NPM: &str = "npm.cmd";
@@ -9,17 +26,48 @@ pub const NPM: &str = "npm.cmd";
 #[cfg(not(windows))]
 pub const NPM: &str = "npm";
 
-fn prefix_with_tilde(pkg_version: &str) -> String {
+#[cfg(windows)]
+pub const PNPM: &str = "pnpm.cmd";
+
+#[cfg(not(windows))]
+pub const PNPM: &str = "pnpm";
+
+#[cfg(windows)]
+const CURL: &str = "curl.exe";
+
+#[cfg(not(windows))]
+const CURL: &str = "curl";
+
+/// Download counts are only published for the public registry
+const DOWNLOADS_API: &str = "https://api.npmjs.org/downloads/point/last-week";
+
+/// The package manager which owns the project, going by its lockfile
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Manager {
+    Npm,
+    Pnpm,
+}
+
+pub fn detect_manager(dir: &Path) -> Manager {
+    if dir.join("pnpm-lock.yaml").is_file() {
+        Manager::Pnpm
+    } else {
+        Manager::Npm
+    }
+}
+
+/// Swaps any leading `^` or `~` of a range for the prefix of `style`
+fn prefix_range(pkg_version: &str, style: SavePrefix) -> String {
     let mut chars = pkg_version.chars();
     let string_to_prefix: String = match chars.next().unwrap_or('a') {
-        '^' => chars.collect(),
+        '^' | '~' => chars.collect(),
         _ => String::from(pkg_version),
     };
 
-    format!("~{}", string_to_prefix)
+    format!("{}{}", style.prefix(), string_to_prefix)
 }
 
-fn prefix_all_entries_with_tilde(obj: &mut Value, dep_section: &str) {
+fn rewrite_all_entries(obj: &mut Value, dep_section: &str, rewrite: impl Fn(&str, &str) -> String) {
     if let Some(deps) = obj.get_mut(dep_section) {
         let deps = match deps.as_object_mut() {
             Some(d) => d,
@@ -32,7 +80,7 @@ fn prefix_all_entries_with_tilde(obj: &mut Value, dep_section: &str) {
 
         for (key, val) in deps.iter() {
             let new_val = match val.as_str() {
-                Some(v) => Value::from(prefix_with_tilde(v)),
+                Some(v) => Value::from(rewrite(key, v)),
                 None => val.clone(),
             };
 
@@ -43,295 +91,2270 @@ fn prefix_all_entries_with_tilde(obj: &mut Value, dep_section: &str) {
     }
 }
 
-fn patch_mode_init() -> Result<(), Box<dyn Error>> {
-    fs::copy("package.json", "package.json.bkup")?;
+fn prefix_all_entries(obj: &mut Value, dep_section: &str, style: SavePrefix) {
+    rewrite_all_entries(obj, dep_section, |_, v| prefix_range(v, style));
+}
+
+fn widen_all_entries(obj: &mut Value, dep_section: &str, targets: &HashMap<&str, &str>) {
+    rewrite_all_entries(obj, dep_section, |name, range| match targets.get(name) {
+        Some(version) => format!("^{}", version),
+        None => String::from(range),
+    });
+}
+
+// e.g. package.json.bkup, alongside the manifest
+fn backup_path(manifest: &Path) -> PathBuf {
+    let mut path = manifest.as_os_str().to_owned();
+    path.push(".bkup");
+    PathBuf::from(path)
+}
+
+fn patch_mode_init(manifest: &Path, style: SavePrefix) -> Result<(), Box<dyn Error>> {
+    fs::copy(manifest, backup_path(manifest))?;
 
     // write new package.json
-    let pkg = fs::read_to_string("package.json")?;
+    let pkg = fs::read_to_string(manifest)?;
 
     let mut v: Value = serde_json::from_str(&pkg)?;
 
-    prefix_all_entries_with_tilde(&mut v, "dependencies");
-    prefix_all_entries_with_tilde(&mut v, "devDependencies");
+    prefix_all_entries(&mut v, "dependencies", style);
+    prefix_all_entries(&mut v, "devDependencies", style);
 
     let v = serde_json::to_string(&v)?;
 
-    fs::write("package.json", v)?;
+    fs::write(manifest, v)?;
 
     Ok(())
 }
 
-fn patch_mode_cleanup() -> Result<(), Box<dyn Error>> {
-    fs::copy("package.json.bkup", "package.json")?;
-    fs::remove_file("package.json.bkup")?;
+/// Rewrites the declared ranges in package.json to a caret range of each package's target
+pub fn widen_ranges(packages: &[Package], config: &Config) -> Result<(), Box<dyn Error>> {
+    let targets: HashMap<&str, &str> = packages
+        .iter()
+        .map(|pkg| {
+            (
+                pkg.name.as_str(),
+                pkg.upgrade_version(&config.upgrade_style),
+            )
+        })
+        .collect();
 
-    Ok(())
-}
+    let pkg = fs::read_to_string("package.json")?;
 
-pub fn run(config: &Config) -> Result<String, Box<dyn Error>> {
-    if config.is_patch_mode {
-        patch_mode_init()?;
-    }
+    let mut v: Value = serde_json::from_str(&pkg)?;
 
-    let output = process::Command::new(NPM)
-        .arg("outdated")
-        .arg("--parseable")
-        .output()
-        .unwrap_or_else(|err| {
-            // worst case scenario where they both fail just panic
-            patch_mode_cleanup().unwrap();
-            eprintln!("{}", err);
-            process::exit(70)
-        });
+    // peer ranges are left alone, as npm i would do
+    widen_all_entries(&mut v, "dependencies", &targets);
+    widen_all_entries(&mut v, "devDependencies", &targets);
+    widen_all_entries(&mut v, "optionalDependencies", &targets);
 
-    if config.is_patch_mode {
-        patch_mode_cleanup()?;
-    }
+    let v = serde_json::to_string_pretty(&v)?;
 
-    let output = String::from_utf8(output.stdout)?;
+    fs::write("package.json", format!("{}\n", v))?;
 
-    Ok(output)
+    Ok(())
 }
 
-// Tests --------------------------------------------------------------
-
-#[cfg(test)]
-mod prefix_with_tilde_tests {
-    use super::*;
+/// Restores the manifest from the backup, removing it unless `keep_backup`
+fn patch_mode_cleanup(manifest: &Path, keep_backup: bool) -> Result<(), Box<dyn Error>> {
+    let backup = backup_path(manifest);
+    fs::copy(&backup, manifest)?;
 
-    #[test]
-    fn prefixes_strings() {
-        assert_eq!(prefix_with_tilde("hello"), String::from("~hello"));
-        assert_eq!(prefix_with_tilde("123456"), String::from("~123456"));
-        assert_eq!(prefix_with_tilde("@something"), String::from("~@something"));
-        assert_eq!(prefix_with_tilde(""), String::from("~"));
+    if !keep_backup {
+        fs::remove_file(&backup)?;
     }
 
-    #[test]
-    fn handles_empty() {
-        assert_eq!(prefix_with_tilde(""), String::from("~"));
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerConflict {
+    pub installed_version: Option<String>,
+    pub name: String,
+    pub required_by: String,
+    pub required_range: String,
+}
+
+impl PeerConflict {
+    pub fn install_cmd(&self) -> String {
+        format!("{}@{}", self.name, self.required_range)
     }
+}
 
-    #[test]
-    fn replaces_first_carat() {
-        assert_eq!(prefix_with_tilde("^something"), String::from("~something"));
-        assert_eq!(prefix_with_tilde("^@package"), String::from("~@package"));
-        assert_eq!(
-            prefix_with_tilde("^^fdjshafda"),
-            String::from("~^fdjshafda")
-        );
-        assert_eq!(prefix_with_tilde("^1234"), String::from("~1234"));
+// npm 7+ describes an invalid dependency as `"<range>" from <location>`
+fn split_invalid_description(invalid: &str) -> Option<(String, String)> {
+    let (range, from) = invalid.split_once(" from ")?;
+    let range = range.trim().trim_matches('"');
+
+    if range.is_empty() {
+        return None;
     }
+
+    Some((range.to_string(), from.trim().to_string()))
 }
 
-#[cfg(test)]
-mod prefix_all_entries_with_tilde_tests {
-    use super::*;
+fn collect_peer_conflicts(
+    deps: &Map<String, Value>,
+    parent: &str,
+    conflicts: &mut Vec<PeerConflict>,
+) {
+    for (name, dep) in deps.iter() {
+        let installed_version = dep
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        let required = dep.get("required").and_then(|r| r.as_str());
+        let is_flagged = |key: &str| dep.get(key).and_then(|v| v.as_bool()) == Some(true);
 
-    fn test_input() -> Value {
-        let example = r#"
-        {
-            "name": "John Doe",
-            "age": 43,
-            "dependencies": {
-                "package": "^1.2.3",
-                "@org/package": "^5.0.0",
-                "p": "1.0.0"
-            },
-            "devDependencies": {
-                "something": "^0.0.1",
-                "@abc/tree": "^6.0.0",
-                "blob": "1135.3.0"
-            }
-        }"#;
+        let conflict = match dep.get("invalid") {
+            Some(Value::String(invalid)) => split_invalid_description(invalid),
+            // older npm versions only flag the entry
+            Some(Value::Bool(true)) => required.map(|r| (r.to_string(), parent.to_string())),
+            _ if is_flagged("peerMissing") => required.map(|r| (r.to_string(), parent.to_string())),
+            _ => None,
+        };
 
-        serde_json::from_str(example).unwrap()
-    }
+        if let Some((required_range, required_by)) = conflict {
+            let conflict = PeerConflict {
+                installed_version,
+                name: name.into(),
+                required_by,
+                required_range,
+            };
 
-    #[test]
-    fn updates_as_expected_1() {
-        let mut input = test_input();
-        let expected = r#"
-        {
-            "name": "John Doe",
-            "age": 43,
-            "dependencies": {
-                "package": "~1.2.3",
-                "@org/package": "~5.0.0",
-                "p": "~1.0.0"
-            },
-            "devDependencies": {
-                "something": "^0.0.1",
-                "@abc/tree": "^6.0.0",
-                "blob": "1135.3.0"
+            let is_duplicate = conflicts
+                .iter()
+                .any(|c| c.name == conflict.name && c.required_range == conflict.required_range);
+
+            if !is_duplicate {
+                conflicts.push(conflict);
             }
-        }"#;
-        let expected: Value = serde_json::from_str(expected).unwrap();
+        }
 
-        prefix_all_entries_with_tilde(&mut input, "dependencies");
+        if let Some(children) = dep.get("dependencies").and_then(|d| d.as_object()) {
+            collect_peer_conflicts(children, name, conflicts);
+        }
+    }
+}
 
-        assert_eq!(input, expected);
+/// Parses the output of `npm ls --json`, returning any dependency whose installed version
+/// doesn't satisfy what its dependents (usually via peerDependencies) require
+pub fn parse_peer_conflicts(src: &str) -> Result<Vec<PeerConflict>, serde_json::Error> {
+    let tree: Value = serde_json::from_str(src)?;
+    let mut conflicts = vec![];
+
+    if let Some(deps) = tree.get("dependencies").and_then(|d| d.as_object()) {
+        collect_peer_conflicts(deps, "the root project", &mut conflicts);
     }
 
-    #[test]
-    fn updates_as_expected_2() {
-        let mut input = test_input();
-        let expected = r#"
-        {
-            "name": "John Doe",
-            "age": 43,
-            "dependencies": {
-                "package": "^1.2.3",
-                "@org/package": "^5.0.0",
-                "p": "1.0.0"
-            },
-            "devDependencies": {
-                "something": "~0.0.1",
-                "@abc/tree": "~6.0.0",
-                "blob": "~1135.3.0"
-            }
-        }"#;
-        let expected: Value = serde_json::from_str(expected).unwrap();
+    Ok(conflicts)
+}
 
-        prefix_all_entries_with_tilde(&mut input, "devDependencies");
+// `name@"range"` (or `name@version`), keeping the leading @ of scoped packages. Unlike a
+// version, a range can contain an @ (e.g. `npm:@scope/pkg@^1`), so this splits on the first one
+fn split_spec(spec: &str) -> Option<(String, String)> {
+    let idx = spec.get(1..)?.find('@')? + 1;
+    let range = spec[idx + 1..].trim().trim_matches('"');
 
-        assert_eq!(input, expected);
+    if range.is_empty() {
+        return None;
     }
 
-    #[test]
-    fn updates_as_expected_3() {
-        let mut input = test_input();
-        let expected = r#"
-        {
-            "name": "John Doe",
-            "age": 43,
-            "dependencies": {
-                "package": "^1.2.3",
-                "@org/package": "^5.0.0",
-                "p": "1.0.0"
-            },
-            "devDependencies": {
-                "something": "^0.0.1",
-                "@abc/tree": "^6.0.0",
-                "blob": "1135.3.0"
-            }
-        }"#;
-        let expected: Value = serde_json::from_str(expected).unwrap();
+    Some((spec[..idx].to_string(), range.to_string()))
+}
 
-        prefix_all_entries_with_tilde(&mut input, "doesNotExist");
+/// Parses the peer dependencies npm couldn't satisfy from an ERESOLVE install error, e.g.
+/// `peer react@"^18.0.0" from react-dom@18.2.0`
+pub fn parse_eresolve(stderr: &str) -> Vec<PeerConflict> {
+    let mut found: HashMap<String, String> = HashMap::new();
+    let mut conflicts: Vec<PeerConflict> = vec![];
 
-        assert_eq!(input, expected);
+    if !stderr.contains("ERESOLVE") {
+        return conflicts;
     }
 
-    #[test]
-    fn handles_no_dev_deps() {
-        let mut input = serde_json::from_str(
-            r#"
-        {
-            "name": "John Doe",
-            "age": 43,
-            "dependencies": {
-                "package": "^1.2.3",
-                "@org/package": "^5.0.0",
-                "p": "1.0.0"
+    for line in stderr.lines() {
+        // npm 9+ prefixes with `npm error`, older versions with `npm ERR!`
+        let line = line
+            .trim_start_matches("npm ERR!")
+            .trim_start_matches("npm error")
+            .trim();
+
+        if let Some(spec) = line.strip_prefix("Found: ") {
+            if let Some((name, version)) = split_spec(spec) {
+                found.insert(name, version);
             }
-        }"#,
-        )
-        .unwrap();
-        let expected = r#"
+            continue;
+        }
+
+        let (spec, required_by) = match line
+            .strip_prefix("peer ")
+            .and_then(|rest| rest.split_once(" from "))
         {
-            "name": "John Doe",
-            "age": 43,
-            "dependencies": {
-                "package": "^1.2.3",
-                "@org/package": "^5.0.0",
-                "p": "1.0.0"
-            }
-        }"#;
-        let expected: Value = serde_json::from_str(expected).unwrap();
+            Some(parts) => parts,
+            None => continue,
+        };
 
-        prefix_all_entries_with_tilde(&mut input, "devDependencies");
+        if let Some((name, required_range)) = split_spec(spec) {
+            let is_duplicate = conflicts
+                .iter()
+                .any(|c| c.name == name && c.required_range == required_range);
 
-        assert_eq!(input, expected);
+            if !is_duplicate {
+                conflicts.push(PeerConflict {
+                    installed_version: None,
+                    name,
+                    required_by: required_by.trim().into(),
+                    required_range,
+                });
+            }
+        }
     }
 
-    #[test]
-    fn handles_no_deps() {
-        let mut input = serde_json::from_str(
-            r#"
-        {
-            "name": "John Doe",
-            "age": 43,
-            "devDependencies": {
-                "something": "^0.0.1",
-                "@abc/tree": "^6.0.0",
-                "blob": "1135.3.0"
-            }
-        }"#,
-        )
-        .unwrap();
-        let expected = r#"
-        {
-            "name": "John Doe",
-            "age": 43,
-            "devDependencies": {
-                "something": "^0.0.1",
-                "@abc/tree": "^6.0.0",
-                "blob": "1135.3.0"
-            }
-        }"#;
-        let expected: Value = serde_json::from_str(expected).unwrap();
+    for conflict in conflicts.iter_mut() {
+        conflict.installed_version = found.get(&conflict.name).cloned();
+    }
 
-        prefix_all_entries_with_tilde(&mut input, "dependencies");
+    conflicts
+}
 
-        assert_eq!(input, expected);
-    }
+pub fn list_peer_conflicts() -> Result<Vec<PeerConflict>, Box<dyn Error>> {
+    // npm ls exits non-zero when it finds problems, but still prints the tree
+    let output = process::Command::new(NPM)
+        .arg("ls")
+        .arg("--json")
+        .arg("--all")
+        .output()?;
+
+    let output = String::from_utf8(output.stdout)?;
+
+    Ok(parse_peer_conflicts(&output)?)
 }
 
-#[cfg(test)]
-mod patch_mode_init_tests {
-    use super::*;
-    use serial_test::serial;
-    use std::{env, path::Path};
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MajorHints {
+    pub bugs: Option<String>,
+    pub changelog: Option<String>,
+    pub homepage: Option<String>,
+}
 
-    #[test]
-    #[serial]
-    fn patch_mode_init_works() {
-        let current = env::current_dir().unwrap();
+// bugs and repository can each be either a plain url or an object with a url
+fn url_field(metadata: &Value, key: &str) -> Option<String> {
+    let field = metadata.get(key)?;
+    let url = field.as_str().or_else(|| field.get("url")?.as_str())?;
 
-        env::set_current_dir("./src/test_files").unwrap();
-        patch_mode_init().unwrap();
+    Some(url.to_string())
+}
 
-        assert!(Path::new("./package.json").exists());
-        assert!(Path::new("./package.json.bkup").exists());
+// Only GitHub has a predictable place for release notes
+fn changelog_url(repository: &str) -> Option<String> {
+    let path = repository
+        .trim_start_matches("git+")
+        .trim_end_matches(".git")
+        .split("github.com")
+        .nth(1)?
+        .trim_start_matches([':', '/']);
 
-        fs::copy("package.json.bkup", "package.json").unwrap();
-        fs::remove_file("package.json.bkup").unwrap();
+    if path.split('/').filter(|s| !s.is_empty()).count() != 2 {
+        return None;
+    }
 
-        env::set_current_dir(current).unwrap();
+    Some(format!("https://github.com/{}/releases", path))
+}
+
+/// Parses the output of `npm view <pkg>@<version> bugs homepage repository --json`
+pub fn parse_major_hints(src: &str) -> Result<MajorHints, serde_json::Error> {
+    let metadata: Value = serde_json::from_str(src)?;
+
+    Ok(MajorHints {
+        bugs: url_field(&metadata, "bugs"),
+        changelog: url_field(&metadata, "repository").and_then(|r| changelog_url(&r)),
+        homepage: url_field(&metadata, "homepage"),
+    })
+}
+
+/// The deprecation message from `npm view <name>@<version> deprecated --json`, which prints
+/// nothing when the version isn't deprecated
+pub fn parse_deprecated(src: &str) -> Result<Option<String>, serde_json::Error> {
+    if src.trim().is_empty() {
+        return Ok(None);
     }
+
+    let message = match serde_json::from_str(src)? {
+        Value::String(message) => Some(message),
+        // a range matching several versions lists each one's message
+        Value::Array(messages) => messages
+            .into_iter()
+            .find_map(|m| m.as_str().map(String::from)),
+        _ => None,
+    };
+
+    Ok(message.filter(|m| !m.trim().is_empty()))
 }
 
-#[cfg(test)]
-mod patch_mode_cleanup_tests {
-    use serial_test::serial;
+/// How `npm view` reaches the registry for metadata
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ViewOptions {
+    /// Gives up on a single fetch after this long
+    pub timeout: Option<Duration>,
+    /// TLS settings, e.g. `--cafile=<path>`
+    pub flags: Vec<String>,
+}
 
-    use super::*;
-    use std::{env, path::Path};
+impl ViewOptions {
+    pub fn new(config: &Config) -> ViewOptions {
+        let mut flags = vec![];
 
-    #[test]
-    #[serial]
-    fn cleanup_files() {
-        let current = env::current_dir().unwrap();
+        if config.is_insecure {
+            flags.push(String::from("--strict-ssl=false"));
+        }
 
-        env::set_current_dir("./src/test_files").unwrap();
-        fs::copy("package.json", "package.json.bkup").unwrap();
+        if let Some(path) = &config.cacert {
+            flags.push(format!("--cafile={}", path.display()));
+        }
 
-        patch_mode_cleanup().unwrap();
+        ViewOptions {
+            timeout: config.registry_timeout,
+            flags,
+        }
+    }
 
-        assert!(Path::new("./package.json").exists());
-        assert!(!Path::new("./package.json.bkup").exists());
+    /// The same settings, as curl spells them
+    pub fn new_for_curl(config: &Config) -> ViewOptions {
+        let mut flags = vec![];
 
-        env::set_current_dir(current).unwrap();
+        if config.is_insecure {
+            flags.push(String::from("--insecure"));
+        }
+
+        if let Some(path) = &config.cacert {
+            flags.push(format!("--cacert={}", path.display()));
+        }
+
+        ViewOptions {
+            timeout: config.registry_timeout,
+            flags,
+        }
+    }
+}
+
+pub fn view_deprecated(
+    name: &str,
+    version: &str,
+    options: &ViewOptions,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let mut command = process::Command::new(NPM);
+    command
+        .arg("view")
+        .arg(format!("{}@{}", name, version))
+        .arg("deprecated")
+        .arg("--json")
+        .args(&options.flags)
+        .stderr(process::Stdio::null());
+
+    let (status, output) = piped_output(&mut command, &Deadline::new(options.timeout))?;
+
+    if !status.success() {
+        return Err(format!("Unable to fetch metadata for {}@{}", name, version).into());
+    }
+
+    Ok(parse_deprecated(&output)?)
+}
+
+/// How many times the package was downloaded over the last week, from npm's download counts
+pub fn last_week_downloads(name: &str, options: &ViewOptions) -> Result<u64, Box<dyn Error>> {
+    let mut command = process::Command::new(CURL);
+    command
+        .arg("--silent")
+        .arg("--fail")
+        .args(&options.flags)
+        .arg(format!("{}/{}", DOWNLOADS_API, name))
+        .stderr(process::Stdio::null());
+
+    let (status, output) = piped_output(&mut command, &Deadline::new(options.timeout))?;
+
+    if !status.success() {
+        return Err(format!("Unable to fetch the download count of {}", name).into());
+    }
+
+    parse_downloads(&output)
+}
+
+/// The count from a downloads point response, e.g. `{"downloads":123,"package":"debug",...}`
+pub fn parse_downloads(src: &str) -> Result<u64, Box<dyn Error>> {
+    let value: Value = serde_json::from_str(src)?;
+
+    value
+        .get("downloads")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| "No download count in the response".into())
+}
+
+/// The day each version of the package was published
+pub fn view_release_days(
+    name: &str,
+    options: &ViewOptions,
+) -> Result<BTreeMap<Version, i64>, Box<dyn Error>> {
+    let mut command = process::Command::new(NPM);
+    command
+        .arg("view")
+        .arg(name)
+        .arg("time")
+        .arg("--json")
+        .args(&options.flags)
+        .stderr(process::Stdio::null());
+
+    let (status, output) = piped_output(&mut command, &Deadline::new(options.timeout))?;
+
+    if !status.success() {
+        return Err(format!("Unable to fetch the releases of {}", name).into());
+    }
+
+    Ok(self_check::parse_release_days(&output)?)
+}
+
+/// Gives up (killing npm) if the registry takes longer than `options.timeout` to respond
+pub fn view_major_hints(
+    name: &str,
+    version: &str,
+    options: &ViewOptions,
+) -> Result<MajorHints, Box<dyn Error>> {
+    let mut command = process::Command::new(NPM);
+    command
+        .arg("view")
+        .arg(format!("{}@{}", name, version))
+        .arg("bugs")
+        .arg("homepage")
+        .arg("repository")
+        .arg("--json")
+        .args(&options.flags)
+        .stderr(process::Stdio::null());
+
+    let (status, output) = piped_output(&mut command, &Deadline::new(options.timeout))?;
+
+    if !status.success() {
+        return Err(format!("Unable to fetch metadata for {}@{}", name, version).into());
+    }
+
+    Ok(parse_major_hints(&output)?)
+}
+
+pub fn list_security_fixes() -> Result<Vec<SecurityFix>, Box<dyn Error>> {
+    // npm audit exits non-zero when it finds vulnerabilities, but still prints the report
+    let output = process::Command::new(NPM)
+        .arg("audit")
+        .arg("--json")
+        .output()?;
+
+    let output = String::from_utf8(output.stdout)?;
+
+    Ok(audit::parse_audit_report(&output)?)
+}
+
+/// A single entry from `pnpm outdated --format json`, keyed by package name
+#[derive(Debug, Deserialize)]
+struct PnpmOutdated {
+    // missing when the package isn't installed
+    current: Option<String>,
+    latest: String,
+    wanted: String,
+}
+
+/// Parses the output of `pnpm outdated --format json`
+pub fn parse_pnpm_outdated(src: &str, config: &Config) -> Result<Vec<Package>, serde_json::Error> {
+    // pnpm exits without output when there is nothing to report
+    if src.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    let outdated: BTreeMap<String, PnpmOutdated> = serde_json::from_str(src)?;
+
+    // pnpm only reports the project it was run in, so there's no workspace to compare against
+    let install_dir_name = config.current_dir_name.clone().unwrap_or_default();
+
+    Ok(outdated
+        .into_iter()
+        .map(|(name, entry)| {
+            Package::from_versions(
+                name,
+                entry.wanted,
+                entry.current.unwrap_or_else(|| String::from(MISSING)),
+                entry.latest,
+                install_dir_name.clone(),
+                config,
+            )
+        })
+        .collect())
+}
+
+/// A line of `npm outdated --parseable` which isn't a package
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnparsedLine {
+    /// Counting from 1
+    pub number: usize,
+    pub content: String,
+}
+
+/// Parses the output of `npm outdated --parseable`, setting aside any lines which don't parse
+pub fn parse_npm_outdated(src: &str, config: &Config) -> (Vec<Package>, Vec<UnparsedLine>) {
+    let mut packages = vec![];
+    let mut unparsed = vec![];
+
+    for (index, line) in src.split_terminator('\n').enumerate() {
+        match Package::new(line.into(), config) {
+            Ok(pkg) => packages.push(pkg),
+            Err(_) if line.trim().is_empty() => {}
+            Err(_) => unparsed.push(UnparsedLine {
+                number: index + 1,
+                content: String::from(line.trim_end()),
+            }),
+        }
+    }
+
+    (packages, unparsed)
+}
+
+/// A single entry from `npm outdated --json`, keyed by package name
+#[derive(Debug, Deserialize)]
+struct NpmOutdated {
+    // missing when the package isn't installed
+    current: Option<String>,
+    wanted: String,
+    latest: String,
+    // left out by npm versions before workspaces
+    dependent: Option<String>,
+}
+
+/// Parses the output of `npm outdated --json`, for npm versions whose `--parseable` output
+/// isn't understood. Entries are read one at a time, so a malformed one only sets aside that
+/// package, whose name is returned alongside the rest
+pub fn parse_npm_outdated_json(
+    src: &str,
+    config: &Config,
+) -> Result<(Vec<Package>, Vec<String>), serde_json::Error> {
+    // npm exits without output when there is nothing to report
+    if src.trim().is_empty() {
+        return Ok((vec![], vec![]));
+    }
+
+    let outdated: BTreeMap<String, Value> = serde_json::from_str(src)?;
+    let mut packages = vec![];
+    let mut malformed = vec![];
+
+    for (name, entries) in outdated {
+        // npm lists a package once per workspace depending on it, as an array when there's more
+        // than one
+        let entries = match entries {
+            Value::Array(entries) => entries,
+            entry => vec![entry],
+        };
+
+        for entry in entries {
+            let entry: NpmOutdated = match serde_json::from_value(entry) {
+                Ok(entry) => entry,
+                Err(_) => {
+                    malformed.push(name.clone());
+                    continue;
+                }
+            };
+
+            // without a dependent, assume it belongs here as with pnpm
+            let install_dir_name = entry
+                .dependent
+                .or_else(|| config.current_dir_name.clone())
+                .unwrap_or_default();
+
+            packages.push(Package::from_versions(
+                name.clone(),
+                entry.wanted,
+                entry.current.unwrap_or_else(|| String::from(MISSING)),
+                entry.latest,
+                install_dir_name,
+                config,
+            ));
+        }
+    }
+
+    Ok((packages, malformed))
+}
+
+fn npm_outdated_json(deadline: &Deadline) -> Result<String, Box<dyn Error>> {
+    let mut command = process::Command::new(NPM);
+    command.arg("outdated").arg("--json");
+    let (_, output) = piped_output(&mut command, deadline)?;
+
+    Ok(output)
+}
+
+fn outdated(
+    manager: Manager,
+    deadline: &Deadline,
+) -> Result<(process::ExitStatus, String), Box<dyn Error>> {
+    let mut command = match manager {
+        Manager::Npm => {
+            let mut command = process::Command::new(NPM);
+            command.arg("outdated").arg("--parseable");
+            command
+        }
+        Manager::Pnpm => {
+            let mut command = process::Command::new(PNPM);
+            command.arg("outdated").arg("--format").arg("json");
+            command
+        }
+    };
+    piped_output(&mut command, deadline)
+}
+
+/// Runs the command until it exits, or is killed at the deadline, returning what it printed
+fn piped_output(
+    command: &mut process::Command,
+    deadline: &Deadline,
+) -> Result<(process::ExitStatus, String), Box<dyn Error>> {
+    let mut child = command.stdout(process::Stdio::piped()).spawn()?;
+
+    // drain stdout while waiting, otherwise a full pipe would block npm forever
+    let mut stdout = child.stdout.take().ok_or("Unable to read npm output")?;
+    let reader = thread::spawn(move || {
+        let mut output = vec![];
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+
+    let status = deadline.wait(&mut child)?;
+
+    let output = reader.join().map_err(|_| "Unable to read npm output")??;
+
+    Ok((status, String::from_utf8(output)?))
+}
+
+/// Where the last outdated result is kept, alongside the caches of other tools
+const OUTDATED_CACHE: &str = "node_modules/.cache/npm-bumpall/outdated.json";
+
+fn lockfile_name(manager: Manager) -> &'static str {
+    match manager {
+        Manager::Npm => "package-lock.json",
+        Manager::Pnpm => "pnpm-lock.yaml",
+    }
+}
+
+/// 64-bit FNV-1a, which unlike the std hasher is stable between Rust releases
+fn fnv1a(inputs: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    for input in inputs.iter() {
+        for byte in input.iter() {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        // keeps e.g. "ab" + "c" apart from "a" + "bc"
+        hash ^= 0xff;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
+
+/// Identifies the dependencies in `dir`, from package.json and the lockfile. Without a lockfile
+/// the installed versions could change underneath, so there is no key
+fn outdated_cache_key(dir: &Path, manager: Manager) -> Option<String> {
+    let manifest = fs::read(dir.join("package.json")).ok()?;
+    let lockfile = fs::read(dir.join(lockfile_name(manager))).ok()?;
+
+    Some(format!("{:016x}", fnv1a(&[&manifest, &lockfile])))
+}
+
+/// The cached output, if it was stored under the same key
+fn read_outdated_cache(path: &Path, key: &str) -> Option<String> {
+    let cache: Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+
+    if cache["key"].as_str()? != key {
+        return None;
+    }
+
+    cache["output"].as_str().map(String::from)
+}
+
+fn write_outdated_cache(path: &Path, key: &str, output: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let cache = serde_json::json!({ "key": key, "output": output });
+    fs::write(path, serde_json::to_string(&cache)?)?;
+
+    Ok(())
+}
+
+/// Whether an outdated check ran to completion, rather than failing (e.g. offline, or a registry
+/// error) with nothing to report. Both managers exit with 1 when anything is outdated, so only
+/// output which parses into packages tells the two apart
+fn is_complete_check(
+    manager: Manager,
+    exit_code: Option<i32>,
+    output: &str,
+    config: &Config,
+) -> bool {
+    match exit_code {
+        Some(0) => true,
+        Some(1) => match manager {
+            Manager::Npm => {
+                let (packages, unparsed) = parse_npm_outdated(output, config);
+                !packages.is_empty() && unparsed.is_empty()
+            }
+            Manager::Pnpm => {
+                parse_pnpm_outdated(output, config).is_ok_and(|packages| !packages.is_empty())
+            }
+        },
+        _ => false,
+    }
+}
+
+/// Stores the output of an outdated check under `key`, unless the check failed, as that would
+/// report nothing outdated until the dependencies change
+fn cache_complete_check(
+    path: &Path,
+    key: &str,
+    manager: Manager,
+    exit_code: Option<i32>,
+    output: &str,
+    config: &Config,
+) {
+    if is_complete_check(manager, exit_code, output, config) {
+        // a missed cache write only means checking again next time
+        let _ = write_outdated_cache(path, key, output);
+    }
+}
+
+/// Checks for outdated packages, reusing the last result with --cache-outdated while the
+/// dependencies are unchanged
+fn cached_outdated(
+    manager: Manager,
+    config: &Config,
+    deadline: &Deadline,
+) -> Result<String, Box<dyn Error>> {
+    let cache_path = Path::new(OUTDATED_CACHE);
+    let key = if config.is_cache_outdated {
+        outdated_cache_key(Path::new("."), manager)
+    } else {
+        None
+    };
+
+    if let Some(output) = key
+        .as_ref()
+        .and_then(|k| read_outdated_cache(cache_path, k))
+    {
+        return Ok(output);
+    }
+
+    let (status, output) = outdated(manager, deadline)?;
+
+    if let Some(key) = key {
+        cache_complete_check(cache_path, &key, manager, status.code(), &output, config);
+    }
+
+    Ok(output)
+}
+
+/// The outdated packages in the current directory, as reported by its package manager
+pub fn run(config: &Config, deadline: &Deadline) -> Result<Vec<Package>, Box<dyn Error>> {
+    let manager = detect_manager(Path::new("."));
+
+    let manifest = config
+        .manifest
+        .as_deref()
+        .unwrap_or(Path::new(manifest::MANIFEST));
+
+    if config.is_patch_mode {
+        patch_mode_init(manifest, config.range_style.unwrap_or(SavePrefix::Tilde))?;
+    }
+
+    // keyed on the rewritten package.json in patch mode, so each range style is kept apart
+    let output = cached_outdated(manager, config, deadline);
+
+    if config.is_patch_mode {
+        patch_mode_cleanup(manifest, config.is_keep_backup)?;
+    }
+
+    match manager {
+        Manager::Npm => {
+            let (mut packages, unparsed) = parse_npm_outdated(&output?, config);
+
+            // most likely a warning npm printed, so only worth the noise when diagnosing
+            if config.is_verbose {
+                for line in unparsed.iter() {
+                    print_message(
+                        &format!(
+                            "Unable to parse line {} of npm outdated: {}",
+                            line.number, line.content
+                        ),
+                        Icon::Warning,
+                        config,
+                    );
+                }
+            }
+
+            // nothing made sense, so likely an npm version with its own --parseable format
+            if packages.is_empty() && !unparsed.is_empty() {
+                if config.is_verbose {
+                    print_message("Falling back to npm outdated --json", Icon::Warning, config);
+                }
+
+                let (json_packages, malformed) =
+                    parse_npm_outdated_json(&npm_outdated_json(deadline)?, config)?;
+                packages = json_packages;
+
+                if config.is_verbose {
+                    for name in malformed.iter() {
+                        print_message(
+                            &format!("Unable to parse the npm outdated entry for {}", name),
+                            Icon::Warning,
+                            config,
+                        );
+                    }
+                }
+            }
+
+            Ok(packages)
+        }
+        Manager::Pnpm => Ok(parse_pnpm_outdated(&output?, config)?),
+    }
+}
+
+/// The flags every install is run with, after the specs
+pub fn install_flags(config: &Config) -> Vec<String> {
+    let mut flags = vec![];
+
+    if let Some(version) = config.lockfile_version {
+        flags.push(format!("--lockfile-version={}", version));
+    }
+
+    if config.is_no_lockfile {
+        flags.push(String::from("--no-package-lock"));
+    }
+
+    if config.is_dependencies_only {
+        flags.push(String::from("--omit=dev"));
+    }
+
+    if config.is_dev_dependencies_only {
+        flags.push(String::from("--include=dev"));
+    }
+
+    if let Some(prefix) = config.save_prefix {
+        flags.push(prefix.install_arg());
+    }
+
+    flags.extend(config.additional_install_args.iter().cloned());
+    flags
+}
+
+/// Everything passed to `npm` for an install of the given specs
+pub fn install_args(cmd_args: &[String], config: &Config) -> Vec<String> {
+    let mut args = vec![String::from("i")];
+    args.extend(cmd_args.iter().cloned());
+    args.extend(install_flags(config));
+    args
+}
+
+/// The install as a single command to paste into a shell
+pub fn install_command_line(cmd_args: &[String], config: &Config) -> String {
+    let args: Vec<String> = install_args(cmd_args, config)
+        .iter()
+        .map(|arg| utility::quote_arg(arg))
+        .collect();
+
+    format!("{} {}", NPM, args.join(" "))
+}
+
+/// The outcome of an npm install, with whatever npm wrote to stderr
+#[derive(Debug)]
+pub struct InstallOutput {
+    pub status: process::ExitStatus,
+    pub stderr: String,
+}
+
+pub fn install(
+    cmd_args: &[String],
+    config: &Config,
+    deadline: &Deadline,
+) -> Result<InstallOutput, Box<dyn Error>> {
+    deadline.check()?;
+
+    let mut child = process::Command::new(NPM)
+        .stdout(config.stdout_method())
+        .stderr(process::Stdio::piped())
+        .args(install_args(cmd_args, config))
+        .spawn()?;
+
+    // kept for working out peer conflicts, still shown as it arrives when verbose
+    let mut stderr = child.stderr.take().ok_or("Unable to read npm output")?;
+    let is_verbose = config.is_verbose;
+    let reader = thread::spawn(move || {
+        let mut output = vec![];
+        let mut chunk = [0; 4096];
+
+        loop {
+            let read = stderr.read(&mut chunk)?;
+
+            if read == 0 {
+                return Ok::<Vec<u8>, io::Error>(output);
+            }
+
+            if is_verbose {
+                io::stderr().write_all(&chunk[..read])?;
+            }
+            output.extend_from_slice(&chunk[..read]);
+        }
+    });
+
+    let status = deadline.wait(&mut child)?;
+    let stderr = reader.join().map_err(|_| "Unable to read npm output")??;
+
+    Ok(InstallOutput {
+        status,
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+    })
+}
+
+pub fn run_step(
+    step: Step,
+    config: &Config,
+    deadline: &Deadline,
+) -> Result<process::ExitStatus, Box<dyn Error>> {
+    deadline.check()?;
+
+    let mut child = process::Command::new(NPM)
+        .stdout(config.stdout_method())
+        .stderr(config.stderr_method())
+        .arg(step.command())
+        .spawn()?;
+
+    deadline.wait(&mut child)
+}
+
+/// Everything passed to `npm` for a dedupe, which reinstalls the tree so shares the install's
+/// lockfile version and extra args
+pub fn dedupe_args(config: &Config) -> Vec<String> {
+    let mut args = vec![String::from("dedupe")];
+
+    if let Some(version) = config.lockfile_version {
+        args.push(format!("--lockfile-version={}", version));
+    }
+
+    args.extend(config.additional_install_args.iter().cloned());
+    args
+}
+
+pub fn dedupe(config: &Config, deadline: &Deadline) -> Result<process::ExitStatus, Box<dyn Error>> {
+    deadline.check()?;
+
+    let mut child = process::Command::new(NPM)
+        .stdout(config.stdout_method())
+        .stderr(config.stderr_method())
+        .args(dedupe_args(config))
+        .spawn()?;
+
+    deadline.wait(&mut child)
+}
+
+/// Everything passed to `npm` to bring the lockfile's root ranges back in line with package.json,
+/// without touching what's installed
+pub fn sync_lockfile_args(config: &Config) -> Vec<String> {
+    let mut args = vec![String::from("i"), String::from("--package-lock-only")];
+
+    if let Some(version) = config.lockfile_version {
+        args.push(format!("--lockfile-version={}", version));
+    }
+
+    args.extend(config.additional_install_args.iter().cloned());
+    args
+}
+
+pub fn sync_lockfile(
+    config: &Config,
+    deadline: &Deadline,
+) -> Result<process::ExitStatus, Box<dyn Error>> {
+    deadline.check()?;
+
+    let mut child = process::Command::new(NPM)
+        .stdout(config.stdout_method())
+        .stderr(config.stderr_method())
+        .args(sync_lockfile_args(config))
+        .spawn()?;
+
+    deadline.wait(&mut child)
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod prefix_range_tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_strings() {
+        assert_eq!(
+            prefix_range("hello", SavePrefix::Tilde),
+            String::from("~hello")
+        );
+        assert_eq!(
+            prefix_range("123456", SavePrefix::Tilde),
+            String::from("~123456")
+        );
+        assert_eq!(
+            prefix_range("@something", SavePrefix::Tilde),
+            String::from("~@something")
+        );
+        assert_eq!(prefix_range("", SavePrefix::Tilde), String::from("~"));
+    }
+
+    #[test]
+    fn handles_empty() {
+        assert_eq!(prefix_range("", SavePrefix::Tilde), String::from("~"));
+    }
+
+    #[test]
+    fn replaces_first_carat() {
+        assert_eq!(
+            prefix_range("^something", SavePrefix::Tilde),
+            String::from("~something")
+        );
+        assert_eq!(
+            prefix_range("^@package", SavePrefix::Tilde),
+            String::from("~@package")
+        );
+        assert_eq!(
+            prefix_range("^^fdjshafda", SavePrefix::Tilde),
+            String::from("~^fdjshafda")
+        );
+        assert_eq!(
+            prefix_range("^1234", SavePrefix::Tilde),
+            String::from("~1234")
+        );
+    }
+
+    #[test]
+    fn replaces_first_tilde() {
+        assert_eq!(
+            prefix_range("~1.2.3", SavePrefix::Tilde),
+            String::from("~1.2.3")
+        );
+        assert_eq!(
+            prefix_range("~1.2.3", SavePrefix::Caret),
+            String::from("^1.2.3")
+        );
+    }
+
+    #[test]
+    fn prefixes_with_caret() {
+        assert_eq!(
+            prefix_range("1.2.3", SavePrefix::Caret),
+            String::from("^1.2.3")
+        );
+        assert_eq!(
+            prefix_range("^1.2.3", SavePrefix::Caret),
+            String::from("^1.2.3")
+        );
+        assert_eq!(prefix_range("", SavePrefix::Caret), String::from("^"));
+    }
+
+    #[test]
+    fn strips_for_exact() {
+        assert_eq!(
+            prefix_range("^1.2.3", SavePrefix::Exact),
+            String::from("1.2.3")
+        );
+        assert_eq!(
+            prefix_range("~1.2.3", SavePrefix::Exact),
+            String::from("1.2.3")
+        );
+        assert_eq!(
+            prefix_range("1.2.3", SavePrefix::Exact),
+            String::from("1.2.3")
+        );
+    }
+}
+
+#[cfg(test)]
+mod prefix_all_entries_tests {
+    use super::*;
+
+    fn test_input() -> Value {
+        let example = r#"
+        {
+            "name": "John Doe",
+            "age": 43,
+            "dependencies": {
+                "package": "^1.2.3",
+                "@org/package": "^5.0.0",
+                "p": "1.0.0"
+            },
+            "devDependencies": {
+                "something": "^0.0.1",
+                "@abc/tree": "^6.0.0",
+                "blob": "1135.3.0"
+            }
+        }"#;
+
+        serde_json::from_str(example).unwrap()
+    }
+
+    #[test]
+    fn updates_as_expected_1() {
+        let mut input = test_input();
+        let expected = r#"
+        {
+            "name": "John Doe",
+            "age": 43,
+            "dependencies": {
+                "package": "~1.2.3",
+                "@org/package": "~5.0.0",
+                "p": "~1.0.0"
+            },
+            "devDependencies": {
+                "something": "^0.0.1",
+                "@abc/tree": "^6.0.0",
+                "blob": "1135.3.0"
+            }
+        }"#;
+        let expected: Value = serde_json::from_str(expected).unwrap();
+
+        prefix_all_entries(&mut input, "dependencies", SavePrefix::Tilde);
+
+        assert_eq!(input, expected);
+    }
+
+    #[test]
+    fn updates_as_expected_2() {
+        let mut input = test_input();
+        let expected = r#"
+        {
+            "name": "John Doe",
+            "age": 43,
+            "dependencies": {
+                "package": "^1.2.3",
+                "@org/package": "^5.0.0",
+                "p": "1.0.0"
+            },
+            "devDependencies": {
+                "something": "~0.0.1",
+                "@abc/tree": "~6.0.0",
+                "blob": "~1135.3.0"
+            }
+        }"#;
+        let expected: Value = serde_json::from_str(expected).unwrap();
+
+        prefix_all_entries(&mut input, "devDependencies", SavePrefix::Tilde);
+
+        assert_eq!(input, expected);
+    }
+
+    #[test]
+    fn prefixes_with_caret() {
+        let mut input = test_input();
+        let expected = r#"
+        {
+            "name": "John Doe",
+            "age": 43,
+            "dependencies": {
+                "package": "^1.2.3",
+                "@org/package": "^5.0.0",
+                "p": "^1.0.0"
+            },
+            "devDependencies": {
+                "something": "^0.0.1",
+                "@abc/tree": "^6.0.0",
+                "blob": "1135.3.0"
+            }
+        }"#;
+        let expected: Value = serde_json::from_str(expected).unwrap();
+
+        prefix_all_entries(&mut input, "dependencies", SavePrefix::Caret);
+
+        assert_eq!(input, expected);
+    }
+
+    #[test]
+    fn prefixes_exact() {
+        let mut input = test_input();
+        let expected = r#"
+        {
+            "name": "John Doe",
+            "age": 43,
+            "dependencies": {
+                "package": "^1.2.3",
+                "@org/package": "^5.0.0",
+                "p": "1.0.0"
+            },
+            "devDependencies": {
+                "something": "0.0.1",
+                "@abc/tree": "6.0.0",
+                "blob": "1135.3.0"
+            }
+        }"#;
+        let expected: Value = serde_json::from_str(expected).unwrap();
+
+        prefix_all_entries(&mut input, "devDependencies", SavePrefix::Exact);
+
+        assert_eq!(input, expected);
+    }
+
+    #[test]
+    fn updates_as_expected_3() {
+        let mut input = test_input();
+        let expected = r#"
+        {
+            "name": "John Doe",
+            "age": 43,
+            "dependencies": {
+                "package": "^1.2.3",
+                "@org/package": "^5.0.0",
+                "p": "1.0.0"
+            },
+            "devDependencies": {
+                "something": "^0.0.1",
+                "@abc/tree": "^6.0.0",
+                "blob": "1135.3.0"
+            }
+        }"#;
+        let expected: Value = serde_json::from_str(expected).unwrap();
+
+        prefix_all_entries(&mut input, "doesNotExist", SavePrefix::Tilde);
+
+        assert_eq!(input, expected);
+    }
+
+    #[test]
+    fn handles_no_dev_deps() {
+        let mut input = serde_json::from_str(
+            r#"
+        {
+            "name": "John Doe",
+            "age": 43,
+            "dependencies": {
+                "package": "^1.2.3",
+                "@org/package": "^5.0.0",
+                "p": "1.0.0"
+            }
+        }"#,
+        )
+        .unwrap();
+        let expected = r#"
+        {
+            "name": "John Doe",
+            "age": 43,
+            "dependencies": {
+                "package": "^1.2.3",
+                "@org/package": "^5.0.0",
+                "p": "1.0.0"
+            }
+        }"#;
+        let expected: Value = serde_json::from_str(expected).unwrap();
+
+        prefix_all_entries(&mut input, "devDependencies", SavePrefix::Tilde);
+
+        assert_eq!(input, expected);
+    }
+
+    #[test]
+    fn handles_no_deps() {
+        let mut input = serde_json::from_str(
+            r#"
+        {
+            "name": "John Doe",
+            "age": 43,
+            "devDependencies": {
+                "something": "^0.0.1",
+                "@abc/tree": "^6.0.0",
+                "blob": "1135.3.0"
+            }
+        }"#,
+        )
+        .unwrap();
+        let expected = r#"
+        {
+            "name": "John Doe",
+            "age": 43,
+            "devDependencies": {
+                "something": "^0.0.1",
+                "@abc/tree": "^6.0.0",
+                "blob": "1135.3.0"
+            }
+        }"#;
+        let expected: Value = serde_json::from_str(expected).unwrap();
+
+        prefix_all_entries(&mut input, "dependencies", SavePrefix::Tilde);
+
+        assert_eq!(input, expected);
+    }
+}
+
+#[cfg(test)]
+mod widen_all_entries_tests {
+    use super::*;
+
+    fn test_input() -> Value {
+        let example = r#"
+        {
+            "name": "John Doe",
+            "dependencies": {
+                "package": "^1.2.3",
+                "@org/package": "~5.0.0",
+                "p": "1.0.0"
+            },
+            "devDependencies": {
+                "something": "^0.0.1"
+            }
+        }"#;
+
+        serde_json::from_str(example).unwrap()
+    }
+
+    #[test]
+    fn widens_targeted_entries_to_caret() {
+        let mut input = test_input();
+        let targets = HashMap::from([("package", "2.0.1"), ("@org/package", "6.1.0")]);
+        let expected = r#"
+        {
+            "name": "John Doe",
+            "dependencies": {
+                "package": "^2.0.1",
+                "@org/package": "^6.1.0",
+                "p": "1.0.0"
+            },
+            "devDependencies": {
+                "something": "^0.0.1"
+            }
+        }"#;
+        let expected: Value = serde_json::from_str(expected).unwrap();
+
+        widen_all_entries(&mut input, "dependencies", &targets);
+
+        assert_eq!(input, expected);
+    }
+
+    #[test]
+    fn widens_exact_pins() {
+        let mut input = test_input();
+        let targets = HashMap::from([("p", "3.0.0")]);
+
+        widen_all_entries(&mut input, "dependencies", &targets);
+
+        assert_eq!(input["dependencies"]["p"], Value::from("^3.0.0"));
+    }
+
+    #[test]
+    fn leaves_other_sections_alone() {
+        let mut input = test_input();
+        let targets = HashMap::from([("something", "1.0.0")]);
+
+        widen_all_entries(&mut input, "dependencies", &targets);
+        widen_all_entries(&mut input, "doesNotExist", &targets);
+
+        assert_eq!(input, test_input());
+    }
+}
+
+#[cfg(test)]
+mod outdated_cache_tests {
+    use super::*;
+    use std::env;
+
+    fn project(name: &str) -> std::path::PathBuf {
+        let dir = env::temp_dir().join(format!("npm-bumpall-{}-{}", name, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("package.json"),
+            r#"{"dependencies":{"debug":"^4.3.2"}}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("package-lock.json"), r#"{"lockfileVersion":3}"#).unwrap();
+        dir
+    }
+
+    #[test]
+    fn reuses_while_unchanged() {
+        let dir = project("cache-reuse");
+        let cache = dir.join(OUTDATED_CACHE);
+        let key = outdated_cache_key(&dir, Manager::Npm).unwrap();
+
+        write_outdated_cache(&cache, &key, "outdated output").unwrap();
+
+        let key = outdated_cache_key(&dir, Manager::Npm).unwrap();
+        assert_eq!(
+            read_outdated_cache(&cache, &key),
+            Some(String::from("outdated output"))
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn only_caches_complete_checks() {
+        let dir = project("cache-complete");
+        let cache = dir.join(OUTDATED_CACHE);
+        let key = outdated_cache_key(&dir, Manager::Npm).unwrap();
+        let config = Config::default();
+        let outdated = "location:debug@4.3.4:debug@4.3.2:debug@4.3.4:test\n";
+
+        // offline, npm exits with 1 and prints nothing to stdout
+        cache_complete_check(&cache, &key, Manager::Npm, Some(1), "", &config);
+        assert_eq!(read_outdated_cache(&cache, &key), None);
+
+        cache_complete_check(&cache, &key, Manager::Npm, None, outdated, &config);
+        assert_eq!(read_outdated_cache(&cache, &key), None);
+
+        cache_complete_check(&cache, &key, Manager::Npm, Some(1), outdated, &config);
+        assert_eq!(
+            read_outdated_cache(&cache, &key),
+            Some(String::from(outdated))
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn complete_checks() {
+        let config = Config::default();
+        let npm = "location:debug@4.3.4:debug@4.3.2:debug@4.3.4:test\n";
+        let pnpm = r#"{ "debug": { "current": "4.3.2", "wanted": "4.3.4", "latest": "4.3.4" } }"#;
+
+        assert!(is_complete_check(Manager::Npm, Some(0), "", &config));
+        assert!(is_complete_check(Manager::Npm, Some(1), npm, &config));
+        assert!(is_complete_check(Manager::Pnpm, Some(1), pnpm, &config));
+
+        assert!(!is_complete_check(Manager::Npm, Some(1), "", &config));
+        assert!(!is_complete_check(
+            Manager::Npm,
+            Some(1),
+            "npm error code E401",
+            &config
+        ));
+        assert!(!is_complete_check(
+            Manager::Pnpm,
+            Some(1),
+            "ERR_PNPM_FETCH",
+            &config
+        ));
+        assert!(!is_complete_check(Manager::Npm, Some(254), npm, &config));
+    }
+
+    #[test]
+    fn invalidates_on_changes() {
+        let dir = project("cache-invalidate");
+        let cache = dir.join(OUTDATED_CACHE);
+        let key = outdated_cache_key(&dir, Manager::Npm).unwrap();
+        write_outdated_cache(&cache, &key, "outdated output").unwrap();
+
+        fs::write(
+            dir.join("package.json"),
+            r#"{"dependencies":{"debug":"^4.3.4"}}"#,
+        )
+        .unwrap();
+        let manifest_changed = outdated_cache_key(&dir, Manager::Npm).unwrap();
+        assert_ne!(manifest_changed, key);
+        assert_eq!(read_outdated_cache(&cache, &manifest_changed), None);
+
+        fs::write(dir.join("package-lock.json"), r#"{"lockfileVersion":2}"#).unwrap();
+        let lockfile_changed = outdated_cache_key(&dir, Manager::Npm).unwrap();
+        assert_ne!(lockfile_changed, manifest_changed);
+        assert_eq!(read_outdated_cache(&cache, &lockfile_changed), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn no_key_without_a_lockfile() {
+        let dir = project("cache-no-lockfile");
+
+        assert_eq!(outdated_cache_key(&dir, Manager::Pnpm), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_inputs_apart() {
+        assert_ne!(fnv1a(&[b"ab", b"c"]), fnv1a(&[b"a", b"bc"]));
+    }
+}
+
+#[cfg(test)]
+mod patch_mode_init_tests {
+    use super::*;
+    use serial_test::serial;
+    use std::{env, path::Path};
+
+    #[test]
+    #[serial]
+    fn patch_mode_init_works() {
+        let current = env::current_dir().unwrap();
+
+        env::set_current_dir("./src/test_files").unwrap();
+        patch_mode_init(Path::new("package.json"), SavePrefix::Tilde).unwrap();
+
+        assert!(Path::new("./package.json").exists());
+        assert!(Path::new("./package.json.bkup").exists());
+
+        fs::copy("package.json.bkup", "package.json").unwrap();
+        fs::remove_file("package.json.bkup").unwrap();
+
+        env::set_current_dir(current).unwrap();
+    }
+
+    #[test]
+    fn patch_mode_init_custom_manifest() {
+        let dir = env::temp_dir().join(format!("npm-bumpall-manifest-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let manifest = dir.join("app.json");
+        let original =
+            r#"{"dependencies":{"polished":"^3.6.5"},"devDependencies":{"debug":"4.3.4"}}"#;
+        fs::write(&manifest, original).unwrap();
+
+        patch_mode_init(&manifest, SavePrefix::Tilde).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&manifest).unwrap(),
+            r#"{"dependencies":{"polished":"~3.6.5"},"devDependencies":{"debug":"~4.3.4"}}"#
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("app.json.bkup")).unwrap(),
+            original
+        );
+        assert!(!dir.join("package.json").exists());
+
+        patch_mode_cleanup(&manifest, false).unwrap();
+
+        assert_eq!(fs::read_to_string(&manifest).unwrap(), original);
+        assert!(!dir.join("app.json.bkup").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod patch_mode_cleanup_tests {
+    use serial_test::serial;
+
+    use super::*;
+    use std::{env, path::Path};
+
+    #[test]
+    #[serial]
+    fn cleanup_files() {
+        let current = env::current_dir().unwrap();
+
+        env::set_current_dir("./src/test_files").unwrap();
+        fs::copy("package.json", "package.json.bkup").unwrap();
+
+        patch_mode_cleanup(Path::new("package.json"), false).unwrap();
+
+        assert!(Path::new("./package.json").exists());
+        assert!(!Path::new("./package.json.bkup").exists());
+
+        env::set_current_dir(current).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn keeps_backup() {
+        let current = env::current_dir().unwrap();
+
+        env::set_current_dir("./src/test_files").unwrap();
+        let original = fs::read_to_string("package.json").unwrap();
+        fs::copy("package.json", "package.json.bkup").unwrap();
+        fs::write("package.json", "{}").unwrap();
+
+        patch_mode_cleanup(Path::new("package.json"), true).unwrap();
+
+        assert_eq!(fs::read_to_string("package.json").unwrap(), original);
+        assert!(Path::new("./package.json.bkup").exists());
+
+        fs::remove_file("package.json.bkup").unwrap();
+        env::set_current_dir(current).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod parse_peer_conflicts_tests {
+    use super::*;
+
+    #[test]
+    fn finds_invalid_and_missing_peers() {
+        let src = include_str!("test_files/npm_ls_peer_conflicts.json");
+        let expected = vec![
+            PeerConflict {
+                installed_version: Some(String::from("17.0.2")),
+                name: String::from("react"),
+                required_by: String::from("node_modules/react-dom"),
+                required_range: String::from("^18.2.0"),
+            },
+            PeerConflict {
+                installed_version: None,
+                name: String::from("styled-components"),
+                required_by: String::from("polished"),
+                required_range: String::from(">= 5"),
+            },
+        ];
+
+        assert_eq!(parse_peer_conflicts(src).unwrap(), expected);
+    }
+
+    #[test]
+    fn handles_legacy_invalid_flag() {
+        let src = r#"
+        {
+            "name": "test",
+            "dependencies": {
+                "react": {
+                    "version": "17.0.2",
+                    "required": "^18.0.0",
+                    "invalid": true
+                }
+            }
+        }"#;
+        let expected = vec![PeerConflict {
+            installed_version: Some(String::from("17.0.2")),
+            name: String::from("react"),
+            required_by: String::from("the root project"),
+            required_range: String::from("^18.0.0"),
+        }];
+
+        assert_eq!(parse_peer_conflicts(src).unwrap(), expected);
+    }
+
+    #[test]
+    fn handles_no_conflicts() {
+        let src = r#"{ "name": "test", "dependencies": { "left-pad": { "version": "1.3.0" } } }"#;
+
+        assert_eq!(parse_peer_conflicts(src).unwrap(), vec![]);
+        assert_eq!(
+            parse_peer_conflicts(r#"{ "name": "test" }"#).unwrap(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn err_on_invalid_json() {
+        assert!(parse_peer_conflicts("npm ERR! something went wrong").is_err());
+    }
+
+    #[test]
+    fn builds_install_cmd() {
+        let conflict = PeerConflict {
+            installed_version: Some(String::from("17.0.2")),
+            name: String::from("react"),
+            required_by: String::from("node_modules/react-dom"),
+            required_range: String::from("^18.2.0"),
+        };
+
+        assert_eq!(conflict.install_cmd(), String::from("react@^18.2.0"));
+    }
+}
+
+#[cfg(test)]
+mod split_spec_tests {
+    use super::*;
+
+    #[test]
+    fn splits_scoped_and_unscoped() {
+        assert_eq!(
+            split_spec("react@\"^18.2.0\""),
+            Some((String::from("react"), String::from("^18.2.0")))
+        );
+        assert_eq!(
+            split_spec("@storybook/react@7.6.17"),
+            Some((String::from("@storybook/react"), String::from("7.6.17")))
+        );
+    }
+
+    #[test]
+    fn keeps_at_in_ranges() {
+        assert_eq!(
+            split_spec("react@\"npm:@preact/compat@^17.1.2\""),
+            Some((
+                String::from("react"),
+                String::from("npm:@preact/compat@^17.1.2")
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_missing_range() {
+        assert_eq!(split_spec("react@"), None);
+        assert_eq!(split_spec("@storybook/react"), None);
+        assert_eq!(split_spec(""), None);
+    }
+}
+
+#[cfg(test)]
+mod parse_eresolve_tests {
+    use super::*;
+
+    #[test]
+    fn finds_unresolved_peers() {
+        let conflicts = parse_eresolve(include_str!("test_files/npm_eresolve.txt"));
+
+        assert_eq!(
+            conflicts,
+            vec![PeerConflict {
+                installed_version: Some(String::from("17.0.2")),
+                name: String::from("react"),
+                required_by: String::from("react-dom@18.2.0"),
+                required_range: String::from("^18.2.0"),
+            }]
+        );
+        assert_eq!(conflicts[0].install_cmd(), "react@^18.2.0");
+    }
+
+    #[test]
+    fn handles_npm_9_prefix_and_scoped_peers() {
+        let stderr = "npm error code ERESOLVE
+npm error ERESOLVE could not resolve
+npm error
+npm error While resolving: @storybook/addon-docs@7.6.17
+npm error Found: @storybook/react@6.5.16
+npm error node_modules/@storybook/react
+npm error   dev @storybook/react@\"^6.5.16\" from the root project
+npm error
+npm error Could not resolve dependency:
+npm error peer @storybook/react@\"^7.0.0\" from @storybook/addon-docs@7.6.17
+npm error node_modules/@storybook/addon-docs
+";
+
+        assert_eq!(
+            parse_eresolve(stderr),
+            vec![PeerConflict {
+                installed_version: Some(String::from("6.5.16")),
+                name: String::from("@storybook/react"),
+                required_by: String::from("@storybook/addon-docs@7.6.17"),
+                required_range: String::from("^7.0.0"),
+            }]
+        );
+    }
+
+    #[test]
+    fn ignores_other_failures() {
+        let stderr = "npm ERR! code E404
+npm ERR! 404 Not Found - GET https://registry.npmjs.org/not-a-package
+npm ERR! peer react@\"^18.0.0\" from react-dom@18.2.0
+";
+
+        assert!(parse_eresolve(stderr).is_empty());
+        assert!(parse_eresolve("").is_empty());
+    }
+}
+
+#[cfg(test)]
+mod parse_deprecated_tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_message() {
+        assert_eq!(
+            parse_deprecated("\"request has been deprecated, see https://github.com/request/request/issues/3142\"\n")
+                .unwrap(),
+            Some(String::from(
+                "request has been deprecated, see https://github.com/request/request/issues/3142"
+            ))
+        );
+        assert_eq!(
+            parse_deprecated(r#"["Use 2.x instead", "Use 2.x instead"]"#).unwrap(),
+            Some(String::from("Use 2.x instead"))
+        );
+    }
+
+    #[test]
+    fn none_when_not_deprecated() {
+        assert_eq!(parse_deprecated("").unwrap(), None);
+        assert_eq!(parse_deprecated("\n").unwrap(), None);
+        assert_eq!(parse_deprecated("\"\"").unwrap(), None);
+        assert!(parse_deprecated("npm error code E404").is_err());
+    }
+}
+
+#[cfg(test)]
+mod parse_downloads_tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_count() {
+        let src =
+            r#"{"downloads":281845432,"start":"2026-10-08","end":"2026-10-14","package":"debug"}"#;
+
+        assert_eq!(parse_downloads(src).unwrap(), 281845432);
+    }
+
+    #[test]
+    fn errors_without_a_count() {
+        assert!(parse_downloads(r#"{"error":"package debugg not found"}"#).is_err());
+        assert!(parse_downloads("").is_err());
+    }
+}
+
+#[cfg(test)]
+mod parse_major_hints_tests {
+    use super::*;
+
+    #[test]
+    fn reads_object_and_string_fields() {
+        let src = r#"{
+            "bugs": { "url": "https://github.com/styled-components/polished/issues" },
+            "homepage": "https://polished.js.org",
+            "repository": {
+                "type": "git",
+                "url": "git+https://github.com/styled-components/polished.git"
+            }
+        }"#;
+        let expected = MajorHints {
+            bugs: Some(String::from(
+                "https://github.com/styled-components/polished/issues",
+            )),
+            changelog: Some(String::from(
+                "https://github.com/styled-components/polished/releases",
+            )),
+            homepage: Some(String::from("https://polished.js.org")),
+        };
+
+        assert_eq!(parse_major_hints(src).unwrap(), expected);
+    }
+
+    #[test]
+    fn handles_missing_fields() {
+        assert_eq!(parse_major_hints("{}").unwrap(), MajorHints::default());
+        assert_eq!(
+            parse_major_hints(r#"{ "bugs": "https://example.com/issues" }"#).unwrap(),
+            MajorHints {
+                bugs: Some(String::from("https://example.com/issues")),
+                ..MajorHints::default()
+            }
+        );
+    }
+
+    #[test]
+    fn changelog_only_for_github() {
+        assert_eq!(
+            changelog_url("git@github.com:JonShort/npm-bumpall.git"),
+            Some(String::from(
+                "https://github.com/JonShort/npm-bumpall/releases"
+            ))
+        );
+        assert_eq!(changelog_url("https://gitlab.com/org/repo.git"), None);
+        assert_eq!(changelog_url("https://github.com/org"), None);
+    }
+
+    #[test]
+    fn err_on_invalid_json() {
+        assert!(parse_major_hints("npm error code E404").is_err());
+    }
+}
+
+#[cfg(test)]
+mod install_args_tests {
+    use super::*;
+    use crate::utility::Args;
+    use clap::Parser;
+
+    fn specs() -> Vec<String> {
+        vec![String::from("polished@3.7.2"), String::from("debug@3.2.7")]
+    }
+
+    #[test]
+    fn specs_only_by_default() {
+        let config = Config::new_from_args(Args::default());
+
+        assert_eq!(
+            install_args(&specs(), &config),
+            vec!["i", "polished@3.7.2", "debug@3.2.7"]
+        );
+    }
+
+    #[test]
+    fn appends_save_prefix() {
+        for (raw, expected) in [
+            ("^", "--save-prefix=^"),
+            ("~", "--save-prefix=~"),
+            ("exact", "--save-prefix="),
+        ] {
+            let config =
+                Config::new_from_args(Args::parse_from(["npm-bumpall", "--save-prefix", raw]));
+
+            assert_eq!(
+                install_args(&specs(), &config),
+                vec!["i", "polished@3.7.2", "debug@3.2.7", expected]
+            );
+        }
+    }
+
+    #[test]
+    fn scopes_to_dependency_types() {
+        let config =
+            Config::new_from_args(Args::parse_from(["npm-bumpall", "--dependencies-only"]));
+        assert_eq!(
+            install_args(&specs(), &config),
+            vec!["i", "polished@3.7.2", "debug@3.2.7", "--omit=dev"]
+        );
+
+        let config =
+            Config::new_from_args(Args::parse_from(["npm-bumpall", "--dev-dependencies-only"]));
+        assert_eq!(
+            install_args(&specs(), &config),
+            vec!["i", "polished@3.7.2", "debug@3.2.7", "--include=dev"]
+        );
+    }
+
+    #[test]
+    fn assembles_command_line() {
+        let config = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--legacy-peer-deps",
+            "--install-args",
+            "--no-audit",
+        ]));
+        let cmd_args = vec![
+            String::from("polished@3.7.2"),
+            String::from("react@>=17 <19"),
+        ];
+
+        assert_eq!(
+            install_command_line(&cmd_args, &config),
+            format!(
+                "{} i polished@3.7.2 'react@>=17 <19' --legacy-peer-deps --no-audit",
+                NPM
+            )
+        );
+    }
+
+    #[test]
+    fn appends_no_package_lock() {
+        let config = Config::new_from_args(Args::parse_from(["npm-bumpall", "--no-lockfile"]));
+
+        assert_eq!(
+            install_args(&specs(), &config),
+            vec!["i", "polished@3.7.2", "debug@3.2.7", "--no-package-lock"]
+        );
+    }
+
+    #[test]
+    fn appends_npm_config_before_install_args() {
+        let config = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--lockfile-version",
+            "3",
+            "--save-prefix",
+            "~",
+            "--legacy-peer-deps",
+            "--install-args",
+            "--no-audit",
+        ]));
+
+        assert_eq!(
+            install_args(&specs(), &config),
+            vec![
+                "i",
+                "polished@3.7.2",
+                "debug@3.2.7",
+                "--lockfile-version=3",
+                "--save-prefix=~",
+                "--legacy-peer-deps",
+                "--no-audit"
+            ]
+        );
+    }
+}
+
+#[cfg(all(test, unix))]
+mod piped_output_tests {
+    use super::*;
+    use crate::deadline;
+    use std::time::Instant;
+
+    #[test]
+    fn returns_output() {
+        let mut command = process::Command::new("sh");
+        command.arg("-c").arg("echo '{}'; exit 3");
+
+        let (status, output) = piped_output(&mut command, &Deadline::new(None)).unwrap();
+
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(output, "{}\n");
+    }
+
+    #[test]
+    fn gives_up_on_slow_commands() {
+        let started = Instant::now();
+        let mut command = process::Command::new("sh");
+        command.arg("-c").arg("sleep 5; echo late");
+
+        let err = piped_output(
+            &mut command,
+            &Deadline::new(Some(Duration::from_millis(100))),
+        )
+        .unwrap_err();
+
+        assert!(deadline::is_timeout(err.as_ref()));
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+}
+
+#[cfg(test)]
+mod dedupe_args_tests {
+    use super::*;
+    use crate::utility::Args;
+    use clap::Parser;
+
+    #[test]
+    fn dedupe_only_by_default() {
+        let config = Config::new_from_args(Args::default());
+
+        assert_eq!(dedupe_args(&config), vec!["dedupe"]);
+    }
+
+    #[test]
+    fn passes_lockfile_version_and_install_args() {
+        let config = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--lockfile-version",
+            "2",
+            "--save-prefix",
+            "~",
+            "--legacy-peer-deps",
+            "--install-args",
+            "--no-audit",
+            "dedupe",
+        ]));
+
+        assert_eq!(
+            dedupe_args(&config),
+            vec![
+                "dedupe",
+                "--lockfile-version=2",
+                "--legacy-peer-deps",
+                "--no-audit"
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod sync_lockfile_args_tests {
+    use super::*;
+    use crate::utility::Args;
+    use clap::Parser;
+
+    #[test]
+    fn lockfile_only_by_default() {
+        let config = Config::new_from_args(Args::default());
+
+        assert_eq!(
+            sync_lockfile_args(&config),
+            vec!["i", "--package-lock-only"]
+        );
+    }
+
+    #[test]
+    fn passes_lockfile_version_and_install_args() {
+        let config = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--keep-range-width",
+            "--lockfile-version",
+            "3",
+            "--save-prefix",
+            "~",
+            "--install-args",
+            "--no-audit",
+        ]));
+
+        assert_eq!(
+            sync_lockfile_args(&config),
+            vec![
+                "i",
+                "--package-lock-only",
+                "--lockfile-version=3",
+                "--no-audit"
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod parse_outdated_tests {
+    use super::*;
+    use crate::package::SkipReason;
+    use crate::utility::Args;
+
+    fn config() -> Config {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = Some(String::from("npm-bumpall"));
+        config
+    }
+
+    #[test]
+    fn maps_pnpm_shape() {
+        let config = config();
+        let packages =
+            parse_pnpm_outdated(include_str!("test_files/pnpm_outdated.json"), &config).unwrap();
+        let summary: Vec<(&str, &str, &str, &str)> = packages
+            .iter()
+            .map(|pkg| {
+                (
+                    pkg.name.as_str(),
+                    pkg.current_version.as_str(),
+                    pkg.wanted_version.as_str(),
+                    pkg.latest_version.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("@jonshort/cenv", "1.0.2", "1.0.3", "1.0.3"),
+                ("debug", "MISSING", "4.3.4", "4.3.4"),
+                ("polished", "3.6.5", "3.7.2", "4.2.2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn pnpm_packages_belong_to_current_dir() {
+        let config = config();
+        let packages =
+            parse_pnpm_outdated(include_str!("test_files/pnpm_outdated.json"), &config).unwrap();
+
+        assert!(packages.iter().all(|pkg| pkg.skip.is_none()));
+        assert_eq!(packages[2].install_version, "3.7.2");
+    }
+
+    #[test]
+    fn pnpm_handles_no_output() {
+        assert!(parse_pnpm_outdated("", &config()).unwrap().is_empty());
+        assert!(parse_pnpm_outdated("{}", &config()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn pnpm_err_on_invalid_json() {
+        assert!(parse_pnpm_outdated("polished 3.6.5 4.2.2", &config()).is_err());
+    }
+
+    #[test]
+    fn maps_npm_json_shape() {
+        let config = config();
+        let (packages, malformed) =
+            parse_npm_outdated_json(include_str!("test_files/npm_outdated.json"), &config).unwrap();
+        let summary: Vec<(&str, &str, &str, &str, &str)> = packages
+            .iter()
+            .map(|pkg| {
+                (
+                    pkg.name.as_str(),
+                    pkg.current_version.as_str(),
+                    pkg.wanted_version.as_str(),
+                    pkg.latest_version.as_str(),
+                    pkg.install_dir_name.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("@jonshort/cenv", "MISSING", "1.0.3", "1.0.3", "npm-bumpall"),
+                ("debug", "3.2.6", "3.2.7", "4.3.4", "npm-bumpall"),
+                ("debug", "3.2.7", "3.2.7", "4.3.4", "other"),
+                ("polished", "3.6.5", "3.7.2", "4.2.2", "npm-bumpall"),
+            ]
+        );
+        assert_eq!(packages[2].skip, Some(SkipReason::WorkspaceDependency));
+        assert!(malformed.is_empty());
+    }
+
+    #[test]
+    fn npm_json_without_dependent_belongs_to_current_dir() {
+        let output = r#"{"polished":{"current":"3.6.5","wanted":"3.7.2","latest":"4.2.2"}}"#;
+        let (packages, _) = parse_npm_outdated_json(output, &config()).unwrap();
+
+        assert_eq!(packages[0].install_dir_name, "npm-bumpall");
+        assert_eq!(packages[0].skip, None);
+    }
+
+    #[test]
+    fn npm_json_handles_no_output() {
+        assert_eq!(
+            parse_npm_outdated_json("", &config()).unwrap(),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            parse_npm_outdated_json("{}", &config()).unwrap(),
+            (vec![], vec![])
+        );
+    }
+
+    #[test]
+    fn npm_json_sets_aside_malformed_entries() {
+        let output = r#"{
+            "polished": {"current": "3.6.5", "wanted": "3.7.2", "latest": "4.2.2"},
+            "broken": {"current": "1.0.0", "wanted": 2},
+            "debug": [
+                {"current": "3.2.6", "wanted": "3.2.7", "latest": "4.3.4", "dependent": "npm-bumpall"},
+                "not an entry"
+            ],
+            "ms": {"current": "2.1.2", "wanted": "2.1.3", "latest": "2.1.3"}
+        }"#;
+        let (packages, malformed) = parse_npm_outdated_json(output, &config()).unwrap();
+        let names: Vec<&str> = packages.iter().map(|pkg| pkg.name.as_str()).collect();
+
+        assert_eq!(names, vec!["debug", "ms", "polished"]);
+        assert_eq!(malformed, vec!["broken", "debug"]);
+    }
+
+    #[test]
+    fn npm_json_err_on_parseable_output() {
+        let output = "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:npm-bumpall";
+
+        assert!(parse_npm_outdated_json(output, &config()).is_err());
+    }
+
+    #[test]
+    fn npm_skips_unparseable_lines() {
+        let output = "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:npm-bumpall
+not a package
+location:debug@3.2.7:debug@3.2.7:debug@4.3.4:other
+";
+        let (packages, _) = parse_npm_outdated(output, &config());
+
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].skip, None);
+        assert_eq!(packages[1].skip, Some(SkipReason::WorkspaceDependency));
+    }
+
+    #[test]
+    fn npm_collects_unparseable_lines() {
+        let output = "npm WARN config production Use `--omit=dev` instead.
+location:polished@3.7.2:polished@3.6.5:polished@4.2.2:npm-bumpall
+
+location:debug@3.2.7:debug@3.2.7:
+";
+        let (packages, unparsed) = parse_npm_outdated(output, &config());
+
+        assert_eq!(packages.len(), 1);
+        assert_eq!(
+            unparsed,
+            vec![
+                UnparsedLine {
+                    number: 1,
+                    content: String::from("npm WARN config production Use `--omit=dev` instead."),
+                },
+                UnparsedLine {
+                    number: 4,
+                    content: String::from("location:debug@3.2.7:debug@3.2.7:"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_manager_from_lockfile() {
+        let test_files = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test_files");
+
+        assert_eq!(detect_manager(&test_files), Manager::Npm);
+        assert_eq!(detect_manager(&test_files.join("pnpm")), Manager::Pnpm);
     }
 }
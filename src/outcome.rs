@@ -0,0 +1,272 @@
+use serde::Serialize;
+use std::{error::Error, fs::OpenOptions, io::Write, path::Path};
+
+use crate::format;
+use crate::install::InstallSummary;
+use crate::package::{Package, SkipReason, UpgradeType};
+use crate::utility::{self, Config};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct UpgradeCounts {
+    pub safe: usize,
+    pub major: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SkippedPackage {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallStatus {
+    Success,
+    /// Some of the packages failed to install
+    Partial,
+    Failed,
+}
+
+impl InstallStatus {
+    pub fn from_summary(summary: &InstallSummary) -> InstallStatus {
+        if summary.failed.is_empty() {
+            InstallStatus::Success
+        } else if summary.installed.is_empty() {
+            InstallStatus::Failed
+        } else {
+            InstallStatus::Partial
+        }
+    }
+}
+
+/// The result of a run, for tooling embedding npm-bumpall. Fields are only ever added, so
+/// consumers can rely on the existing ones
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RunOutcome {
+    /// Every package npm reported as outdated, including those skipped or filtered out
+    pub total_outdated: usize,
+    /// Of the packages which were (or would be) bumped
+    pub upgrade_counts: UpgradeCounts,
+    pub bumped: Vec<String>,
+    pub skipped: Vec<SkippedPackage>,
+    /// `None` when nothing was installed, e.g. with --dry-run
+    pub install_status: Option<InstallStatus>,
+}
+
+impl RunOutcome {
+    pub fn new(
+        packages: &[Package],
+        skipped: &[(Package, SkipReason)],
+        filtered_out: usize,
+        summary: Option<&InstallSummary>,
+        config: &Config,
+    ) -> RunOutcome {
+        let major = packages
+            .iter()
+            .filter(|pkg| pkg.upgrade_type == UpgradeType::Major)
+            .count();
+
+        let bumped = match summary {
+            Some(summary) => packages
+                .iter()
+                .filter(|pkg| {
+                    summary
+                        .installed
+                        .contains(&pkg.install_spec(config).to_string())
+                })
+                .map(|pkg| pkg.name.clone())
+                .collect(),
+            None => vec![],
+        };
+
+        RunOutcome {
+            total_outdated: packages.len() + skipped.len() + filtered_out,
+            upgrade_counts: UpgradeCounts {
+                safe: packages.len() - major,
+                major,
+            },
+            bumped,
+            skipped: skipped
+                .iter()
+                .map(|(pkg, reason)| SkippedPackage {
+                    name: pkg.name.clone(),
+                    reason: reason.to_string(),
+                })
+                .collect(),
+            install_status: summary.map(InstallStatus::from_summary),
+        }
+    }
+
+    /// Writes the outcome into --output-dir, to --summary-json and to the GitHub Actions step
+    /// outputs, when given
+    pub fn write(&self, config: &Config) -> Result<(), Box<dyn Error>> {
+        let outcome = format::json(&serde_json::to_value(self)?, config)?;
+
+        if let Some(path) = config.artifact_path(utility::OUTCOME_ARTIFACT) {
+            utility::write_artifact(&path, &outcome)?;
+        }
+
+        if let Some(path) = &config.summary_json {
+            utility::write_artifact(path, &outcome)?;
+        }
+
+        if let Some(path) = &config.github_output {
+            self.write_github_output(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Appends step outputs to a GitHub Actions output file, always as compact JSON since
+    /// each output has to fit on one line
+    pub fn write_github_output(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        writeln!(file, "bumped_count={}", self.bumped.len())?;
+        writeln!(file, "safe_count={}", self.upgrade_counts.safe)?;
+        writeln!(file, "major_count={}", self.upgrade_counts.major)?;
+        writeln!(file, "outcome={}", serde_json::to_string(self)?)?;
+
+        Ok(())
+    }
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod run_outcome_tests {
+    use super::*;
+    use crate::utility::Args;
+    use clap::Parser;
+    use serde_json::json;
+
+    fn config() -> Config {
+        // the upgrade type is only major when bumping to latest
+        let mut config = Config::new_from_args(Args::parse_from(["npm-bumpall", "--latest"]));
+        config.current_dir_name = None;
+        config
+    }
+
+    fn pkg(src: &str) -> Package {
+        Package::new(String::from(src), &config()).unwrap()
+    }
+
+    fn packages() -> Vec<Package> {
+        vec![
+            pkg("location:debug@3.2.7:debug@3.1.0:debug@4.3.4:test"),
+            pkg("location:polished@3.7.2:polished@3.6.0:polished@3.7.2:test"),
+        ]
+    }
+
+    #[test]
+    fn counts_a_bump() {
+        let skipped = vec![(
+            pkg("location:react@18.2.0:react@18.2.0:react@18.2.0:test"),
+            SkipReason::AtTarget,
+        )];
+        let summary = InstallSummary {
+            installed: vec![String::from("polished@3.7.2")],
+            failed: vec![String::from("debug@4.3.4")],
+        };
+
+        let outcome = RunOutcome::new(&packages(), &skipped, 1, Some(&summary), &config());
+
+        assert_eq!(
+            outcome,
+            RunOutcome {
+                total_outdated: 4,
+                upgrade_counts: UpgradeCounts { safe: 1, major: 1 },
+                bumped: vec![String::from("polished")],
+                skipped: vec![SkippedPackage {
+                    name: String::from("react"),
+                    reason: String::from("already at the target version"),
+                }],
+                install_status: Some(InstallStatus::Partial),
+            }
+        );
+    }
+
+    #[test]
+    fn serializes() {
+        let summary = InstallSummary {
+            installed: vec![String::from("debug@4.3.4"), String::from("polished@3.7.2")],
+            failed: vec![],
+        };
+        let outcome = RunOutcome::new(&packages(), &[], 0, Some(&summary), &config());
+
+        assert_eq!(
+            serde_json::to_value(&outcome).unwrap(),
+            json!({
+                "total_outdated": 2,
+                "upgrade_counts": { "safe": 1, "major": 1 },
+                "bumped": ["debug", "polished"],
+                "skipped": [],
+                "install_status": "success",
+            })
+        );
+    }
+
+    #[test]
+    fn writes_summary_json() {
+        let path = std::env::temp_dir().join(format!(
+            "npm-bumpall-summary-{}/summary.json",
+            std::process::id()
+        ));
+        let config = Config {
+            summary_json: Some(path.clone()),
+            ..config()
+        };
+        let summary = InstallSummary {
+            installed: vec![String::from("polished@3.7.2")],
+            failed: vec![],
+        };
+
+        RunOutcome::new(&packages()[1..], &[], 0, Some(&summary), &config)
+            .write(&config)
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            r#"{"total_outdated":1,"upgrade_counts":{"safe":1,"major":0},"bumped":["polished"],"skipped":[],"install_status":"success"}"#
+        );
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn appends_github_output() {
+        let path =
+            std::env::temp_dir().join(format!("npm-bumpall-github-output-{}", std::process::id()));
+        std::fs::write(&path, "earlier=output\n").unwrap();
+        let summary = InstallSummary {
+            installed: vec![String::from("debug@4.3.4")],
+            failed: vec![],
+        };
+
+        RunOutcome::new(&packages(), &[], 0, Some(&summary), &config())
+            .write_github_output(&path)
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "earlier=output\n\
+             bumped_count=1\n\
+             safe_count=1\n\
+             major_count=1\n\
+             outcome={\"total_outdated\":2,\"upgrade_counts\":{\"safe\":1,\"major\":1},\"bumped\":[\"debug\"],\"skipped\":[],\"install_status\":\"success\"}\n"
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn no_install_status_without_an_install() {
+        let outcome = RunOutcome::new(&packages(), &[], 0, None, &config());
+
+        assert_eq!(outcome.bumped, Vec::<String>::new());
+        assert_eq!(
+            serde_json::to_value(&outcome).unwrap()["install_status"],
+            json!(null)
+        );
+    }
+}
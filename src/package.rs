@@ -1,6 +1,15 @@
-use std::{error::Error, fmt};
+use serde::Serialize;
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt,
+};
 
+use crate::manifest::{self, DependencyType, Source};
+use crate::npm_cmd;
 use crate::utility::{Config, UpgradeStyle};
+use crate::version;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ParseError;
@@ -21,7 +30,7 @@ fn val_or_err<T>(opt: Option<T>) -> Result<T, ParseError> {
     }
 }
 
-const MISSING: &str = "MISSING";
+pub const MISSING: &str = "MISSING";
 
 fn split_name_and_version(src: Option<&str>) -> Result<(String, String), ParseError> {
     let src = val_or_err(src)?;
@@ -30,40 +39,134 @@ fn split_name_and_version(src: Option<&str>) -> Result<(String, String), ParseEr
         return Ok((String::from(""), String::from(MISSING)));
     }
 
-    let is_scoped_package = src.starts_with('@');
-    let mut segments = src.split('@');
+    // a version never contains an @, while a name can start with one (scoped packages)
+    let (name, version) = src.rsplit_once('@').ok_or(ParseError)?;
 
-    if is_scoped_package {
-        // the first value is guaranteed to be an empty slice
-        segments.next();
+    if name.trim_start_matches('@').trim().is_empty() || version.trim().is_empty() {
+        return Err(ParseError);
     }
 
-    let name = val_or_err(segments.next())?;
-    let version = val_or_err(segments.next())?;
+    Ok((name.to_string(), version.to_string()))
+}
 
-    if name.trim().is_empty() || version.trim().is_empty() {
-        return Err(ParseError);
+// e.g. C:\ or C:/
+fn starts_with_drive(src: &str) -> bool {
+    match src.as_bytes() {
+        [drive, b':', b'\\' | b'/', ..] => drive.is_ascii_alphabetic(),
+        _ => false,
     }
+}
 
-    let prefix = if is_scoped_package { "@" } else { "" };
+/// Splits the location off the front of a parseable line, returning it and the remainder.
+/// On windows the location starts with drive information (e.g. D:\windows\dir), which
+/// clashes with splitting on ":", so the drive colon is kept as part of the location.
+fn split_location(src: &str) -> (&str, &str) {
+    let search_from = if starts_with_drive(src) { 2 } else { 0 };
 
-    Ok((format!("{}{}", prefix, name), version.to_string()))
+    match src[search_from..].find(':') {
+        Some(idx) => (&src[..search_from + idx], &src[search_from + idx + 1..]),
+        None => (src, ""),
+    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum UpgradeType {
     Safe,
     Major,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Why a package reported by npm isn't being bumped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The installed version is already the one it would be bumped to
+    AtTarget,
+    /// The only newer versions are outside the declared range
+    OutOfRange,
+    /// Installed for another workspace rather than this package
+    WorkspaceDependency,
+    /// Doesn't match the `--include` glob
+    ExcludedByGlob,
+    /// Doesn't match the `--name-regex` pattern
+    ExcludedByRegex,
+    /// Declared in a section excluded with `--exclude-type`
+    ExcludedByType,
+    /// Only has updates within the current major, excluded with `--exclude-current-major`
+    SameMajor,
+    /// Jumps more major versions than `--exclude-majors-over` allows
+    TooManyMajors,
+    /// The target version isn't within the range approved by `--allowlist-file`
+    NotAllowlisted,
+    /// npm reported versions which aren't valid semver
+    InvalidVersion,
+    /// Declared as a `file:` (or similar) dependency
+    LocalPath,
+    /// Declared as a git dependency
+    GitSource,
+}
+
+impl SkipReason {
+    /// Whether the package was left out by one of the filtering options, rather than having
+    /// nothing to bump to
+    pub fn is_filtered(&self) -> bool {
+        matches!(
+            self,
+            SkipReason::ExcludedByGlob
+                | SkipReason::ExcludedByRegex
+                | SkipReason::ExcludedByType
+                | SkipReason::SameMajor
+                | SkipReason::TooManyMajors
+                | SkipReason::NotAllowlisted
+        )
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            SkipReason::AtTarget => "already at the target version",
+            SkipReason::OutOfRange => {
+                "newer versions are outside the declared range (see --latest)"
+            }
+            SkipReason::WorkspaceDependency => "dependency of another workspace",
+            SkipReason::ExcludedByGlob => "excluded by --include",
+            SkipReason::ExcludedByRegex => "excluded by --name-regex",
+            SkipReason::ExcludedByType => "excluded by --exclude-type",
+            SkipReason::SameMajor => "only updates within the current major",
+            SkipReason::TooManyMajors => "jumps too many majors (see --exclude-majors-over)",
+            SkipReason::NotAllowlisted => "target version isn't approved by --allowlist-file",
+            SkipReason::InvalidVersion => "invalid version(s) from npm",
+            SkipReason::LocalPath => "installed from a local path, not the registry",
+            SkipReason::GitSource => "installed from git, not the registry",
+        };
+
+        write!(f, "{}", reason)
+    }
+}
+
+/// What npm installs for a package, with the flags the install is run with
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct InstallSpec {
+    pub name: String,
+    pub version: String,
+    pub flags: Vec<String>,
+}
+
+/// The `name@version` npm takes, e.g. `@jonshort/cenv@1.0.3`
+impl fmt::Display for InstallSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Package {
     pub current_version: String,
-    pub install_cmd: String,
     pub install_dir_name: String,
+    /// The version npm installs, usually the upgrade version
+    pub install_version: String,
     pub latest_version: String,
     pub name: String,
-    pub skip: bool,
+    pub skip: Option<SkipReason>,
     pub upgrade_type: UpgradeType,
     pub wanted_version: String,
 }
@@ -72,33 +175,79 @@ impl Package {
     pub fn new(src: String, config: &Config) -> Result<Package, ParseError> {
         // :name@wanted_version:MISSING:name@latest_version:project
         // location:name@wanted_version:name@current_version:name@latest_version:project
-        let mut segments = src.split(':');
-
-        // On windows, the location starts with drive information, e.g. D:\\windows\dir
-        // This clashes with the split on ":", so we handle this separately
-        let _location = if src.len() > 4 && &src[1..3] == ":\\" {
-            format!(
-                "{}:{}",
-                val_or_err(segments.next())?,
-                val_or_err(segments.next())?
-            )
-        } else {
-            val_or_err(segments.next()).unwrap().to_string()
-        };
+        let (_location, rest) = split_location(&src);
+        let mut segments = rest.split(':');
 
         let (name, wanted_version) = split_name_and_version(segments.next())?;
         let (_, current_version) = split_name_and_version(segments.next())?;
         let (_, latest_version) = split_name_and_version(segments.next())?;
         let install_dir_name: String = segments.collect::<Vec<&str>>().join(":").trim().to_owned();
 
+        Ok(Package::from_versions(
+            name,
+            wanted_version,
+            current_version,
+            latest_version,
+            install_dir_name,
+            config,
+        ))
+    }
+
+    /// Builds a package from versions already split out of the package manager's output
+    pub fn from_versions(
+        name: String,
+        wanted_version: String,
+        current_version: String,
+        latest_version: String,
+        install_dir_name: String,
+        config: &Config,
+    ) -> Package {
         let upgrade_string = match config.upgrade_style {
             UpgradeStyle::Latest => latest_version.clone(),
             UpgradeStyle::Wanted => wanted_version.clone(),
         };
 
-        let install_cmd = format!("{}@{}", name, upgrade_string);
-        let is_probably_workspace_dep = Some(install_dir_name.clone()) != config.current_dir_name;
-        let skip = current_version == upgrade_string || is_probably_workspace_dep;
+        // without a directory name to compare against there's no way to tell, so don't skip. An
+        // empty dependent is npm leaving it out, rather than naming another workspace
+        let is_probably_workspace_dep = !install_dir_name.is_empty()
+            && config
+                .current_dir_name
+                .as_ref()
+                .is_some_and(|dir| dir != &install_dir_name);
+        let is_excluded = config
+            .include_glob
+            .as_ref()
+            .is_some_and(|glob| !glob.matches(&name));
+        let is_excluded_by_regex = config
+            .name_regex
+            .as_ref()
+            .is_some_and(|regex| !regex.is_match(&name));
+        // the installed version already satisfies the declared range, unless told to bump anyway
+        let is_at_target = current_version == upgrade_string && !config.ignore_range_mismatch;
+        // a version which can't be compared (e.g. MISSING) isn't treated as the same major
+        let is_same_major = config.is_exclude_current_major
+            && version::crosses_major(&current_version, &upgrade_string) == Some(false);
+        let is_too_many_majors = config.exclude_majors_over.is_some_and(|max| {
+            version::distance(&current_version, &upgrade_string)
+                .is_some_and(|distance| distance.major > max)
+        });
+        let skip = if is_probably_workspace_dep {
+            Some(SkipReason::WorkspaceDependency)
+        } else if is_excluded {
+            Some(SkipReason::ExcludedByGlob)
+        } else if is_excluded_by_regex {
+            Some(SkipReason::ExcludedByRegex)
+        } else if is_at_target && latest_version != upgrade_string {
+            Some(SkipReason::OutOfRange)
+        } else if is_at_target {
+            Some(SkipReason::AtTarget)
+        } else if is_same_major {
+            Some(SkipReason::SameMajor)
+        } else if is_too_many_majors {
+            Some(SkipReason::TooManyMajors)
+        } else {
+            None
+        };
         let upgrade_type = match config.upgrade_style {
             UpgradeStyle::Wanted => UpgradeType::Safe,
             UpgradeStyle::Latest => {
@@ -110,16 +259,213 @@ impl Package {
             }
         };
 
-        Ok(Package {
+        Package {
             current_version,
-            install_cmd,
             install_dir_name,
+            install_version: upgrade_string,
             latest_version,
             name,
             skip,
             upgrade_type,
             wanted_version,
-        })
+        }
+    }
+
+    /// Skips the package if its declared range points somewhere other than the registry, as
+    /// there's no `name@version` to install
+    pub fn check_source(&mut self, declared_range: Option<&str>) {
+        if self.skip.is_some() {
+            return;
+        }
+
+        self.skip = match declared_range.map(manifest::source) {
+            Some(Source::LocalPath) => Some(SkipReason::LocalPath),
+            Some(Source::Git) => Some(SkipReason::GitSource),
+            Some(Source::Registry) | None => None,
+        };
+    }
+
+    /// Skips the package if it's declared in any of the excluded sections of package.json
+    pub fn check_type(&mut self, types: &[DependencyType], excluded: &[DependencyType]) {
+        if self.skip.is_some() {
+            return;
+        }
+
+        if types.iter().any(|t| excluded.contains(t)) {
+            self.skip = Some(SkipReason::ExcludedByType);
+        }
+    }
+
+    pub fn install_spec(&self, config: &Config) -> InstallSpec {
+        InstallSpec {
+            name: self.name.clone(),
+            version: self.install_version.clone(),
+            flags: npm_cmd::install_flags(config),
+        }
+    }
+
+    /// Whether a newer version is available outside the declared range
+    pub fn has_major_available(&self) -> bool {
+        self.latest_version != self.wanted_version
+    }
+
+    /// Whether a newer version is available within the declared range
+    pub fn has_in_range_update(&self) -> bool {
+        self.current_version != self.wanted_version
+    }
+
+    /// Any of the current/wanted/latest versions which aren't valid semver
+    pub fn invalid_versions(&self) -> Vec<&str> {
+        [
+            &self.current_version,
+            &self.wanted_version,
+            &self.latest_version,
+        ]
+        .iter()
+        .filter(|v| !version::is_valid(v))
+        .map(|v| v.as_str())
+        .collect()
+    }
+
+    /// Points the bump at another version, e.g. the one npm audit says fixes a vulnerability,
+    /// in place of whichever of wanted/latest the upgrade style targets. It's a major when it
+    /// leaves the compatible line of the wanted version
+    pub fn retarget(&mut self, target: &str, upgrade_style: &UpgradeStyle) {
+        self.upgrade_type = if version::crosses_major(&self.wanted_version, target) == Some(true) {
+            UpgradeType::Major
+        } else {
+            UpgradeType::Safe
+        };
+
+        match upgrade_style {
+            UpgradeStyle::Latest => self.latest_version = target.into(),
+            UpgradeStyle::Wanted => self.wanted_version = target.into(),
+        }
+
+        self.install_version = target.into();
+    }
+
+    pub fn upgrade_version(&self, upgrade_style: &UpgradeStyle) -> &str {
+        match upgrade_style {
+            UpgradeStyle::Latest => &self.latest_version,
+            UpgradeStyle::Wanted => &self.wanted_version,
+        }
+    }
+}
+
+/// The packages installed behind what their declared range allows, ignoring updates which are
+/// only available outside of it. None means the installed versions are frozen
+pub fn in_range_updates(packages: &[Package]) -> Vec<&Package> {
+    packages
+        .iter()
+        .filter(|pkg| pkg.has_in_range_update())
+        .collect()
+}
+
+/// Splits the packages into those which only have updates within their declared range, and
+/// those with a newer major available (to report rather than install)
+pub fn partition_by_major(packages: Vec<Package>) -> (Vec<Package>, Vec<Package>) {
+    packages
+        .into_iter()
+        .partition(|pkg| !pkg.has_major_available())
+}
+
+/// How many dependents (workspaces, going by `install_dir_name`) each outdated package is
+/// reported for, counting each dependent once
+pub fn dependent_counts(packages: &[Package]) -> HashMap<String, usize> {
+    let mut dependents: HashMap<&str, HashSet<&str>> = HashMap::new();
+
+    for pkg in packages.iter() {
+        dependents
+            .entry(pkg.name.as_str())
+            .or_default()
+            .insert(pkg.install_dir_name.as_str());
+    }
+
+    dependents
+        .into_iter()
+        .map(|(name, dirs)| (String::from(name), dirs.len()))
+        .collect()
+}
+
+/// Sorts by name, then by the directory it's installed for, as npm's order can differ between
+/// runs
+pub fn sort_by_name(packages: &mut [Package]) {
+    packages.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| a.install_dir_name.cmp(&b.install_dir_name))
+    });
+}
+
+/// Sorts the packages by name, unless --no-sort keeps the order npm reported them in
+pub fn apply_listing_order(packages: &mut [Package], config: &Config) {
+    if config.is_sorted {
+        sort_by_name(packages);
+    }
+}
+
+/// Moves the packages with the most dependents first, keeping the order between those with
+/// the same count
+pub fn order_by_dependents(packages: &mut [Package], counts: &HashMap<String, usize>) {
+    packages.sort_by_key(|pkg| Reverse(counts.get(&pkg.name).copied().unwrap_or(0)));
+}
+
+/// Moves the major updates after the rest, keeping the order within each group
+pub fn group_majors_last(packages: &mut [Package]) {
+    // sorting is stable, so this is a partition which keeps the existing order
+    packages.sort_by_key(|pkg| pkg.upgrade_type == UpgradeType::Major);
+}
+
+/// Moves the most downloaded packages first, going by their `downloads` counts. Packages
+/// without a count (e.g. when offline) go last, and ties are ordered by name
+pub fn sort_by_popularity(packages: &mut [Package], downloads: &HashMap<String, u64>) {
+    packages.sort_by(|a, b| {
+        downloads
+            .get(&b.name)
+            .cmp(&downloads.get(&a.name))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+}
+
+/// Sorts the major updates with `sort_by_popularity`, leaving the other packages where they are
+pub fn sort_majors_by_popularity(packages: &mut [Package], downloads: &HashMap<String, u64>) {
+    let slots: Vec<usize> = packages
+        .iter()
+        .enumerate()
+        .filter(|(_, pkg)| pkg.upgrade_type == UpgradeType::Major)
+        .map(|(index, _)| index)
+        .collect();
+    let mut majors: Vec<Package> = slots.iter().map(|&index| packages[index].clone()).collect();
+
+    sort_by_popularity(&mut majors, downloads);
+
+    for (index, pkg) in slots.into_iter().zip(majors) {
+        packages[index] = pkg;
+    }
+}
+
+/// Why there's nothing left to bump
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NothingToBump {
+    /// npm reported nothing which could be bumped
+    UpToDate,
+    /// There were updates, but the filtering options excluded all of them
+    AllFiltered(usize),
+}
+
+/// `filtered_out` counts the packages dropped after the outdated check, e.g. by --since-tag
+pub fn nothing_to_bump(skipped: &[(Package, SkipReason)], filtered_out: usize) -> NothingToBump {
+    let filtered = filtered_out
+        + skipped
+            .iter()
+            .filter(|(_, reason)| reason.is_filtered())
+            .count();
+
+    if filtered == 0 {
+        NothingToBump::UpToDate
+    } else {
+        NothingToBump::AllFiltered(filtered)
     }
 }
 
@@ -154,6 +500,8 @@ mod split_name_and_version_tests {
             Some("noversion@"),
             Some("@0.5.5"),
             Some("@jonshort/cenv0.1.0"),
+            Some("no-version"),
+            Some("@@0.1.0"),
         ];
 
         for case in test_cases {
@@ -176,11 +524,99 @@ mod split_name_and_version_tests {
             Ok((String::from("package-name"), String::from("0.1.0")))
         );
     }
+
+    #[test]
+    fn version_is_after_the_last_at() {
+        assert_eq!(
+            split_name_and_version(Some("@jonshort/cenv@alias@0.1.0")),
+            Ok((String::from("@jonshort/cenv@alias"), String::from("0.1.0")))
+        );
+        assert_eq!(
+            split_name_and_version(Some("package-name@alias@0.1.0-beta.1")),
+            Ok((
+                String::from("package-name@alias"),
+                String::from("0.1.0-beta.1")
+            ))
+        );
+    }
+
+    #[test]
+    fn scoped_with_prerelease_and_build() {
+        assert_eq!(
+            split_name_and_version(Some("@types/node@18.0.0-rc.1+build.5")),
+            Ok((
+                String::from("@types/node"),
+                String::from("18.0.0-rc.1+build.5")
+            ))
+        );
+    }
+
+    #[test]
+    fn passes_missing_through() {
+        assert_eq!(
+            split_name_and_version(Some(MISSING)),
+            Ok((String::from(""), String::from(MISSING)))
+        );
+    }
+}
+
+#[cfg(test)]
+mod split_location_tests {
+    use super::*;
+
+    #[test]
+    fn splits_unix_location() {
+        assert_eq!(
+            split_location("/home/me/app/node_modules/debug:debug@4.3.4:debug@4.3.2"),
+            ("/home/me/app/node_modules/debug", "debug@4.3.4:debug@4.3.2")
+        );
+    }
+
+    #[test]
+    fn keeps_drive_in_windows_location() {
+        assert_eq!(
+            split_location("D:\\git\\app\\node_modules\\debug:debug@4.3.4:debug@4.3.2"),
+            (
+                "D:\\git\\app\\node_modules\\debug",
+                "debug@4.3.4:debug@4.3.2"
+            )
+        );
+        assert_eq!(
+            split_location("c:/git/app/node_modules/debug:debug@4.3.4"),
+            ("c:/git/app/node_modules/debug", "debug@4.3.4")
+        );
+    }
+
+    #[test]
+    fn handles_missing_location() {
+        assert_eq!(
+            split_location(":debug@4.3.4:MISSING"),
+            ("", "debug@4.3.4:MISSING")
+        );
+        assert_eq!(split_location(""), ("", ""));
+        assert_eq!(split_location("D:"), ("D", ""));
+    }
+
+    #[test]
+    fn does_not_mistake_other_prefixes_for_drives() {
+        // UNC paths have no drive letter
+        assert_eq!(
+            split_location("\\\\server\\share\\debug:debug@4.3.4"),
+            ("\\\\server\\share\\debug", "debug@4.3.4")
+        );
+        // multi-byte characters mustn't be sliced through
+        assert_eq!(
+            split_location("é:\\dir:debug@4.3.4"),
+            ("é", "\\dir:debug@4.3.4")
+        );
+        assert_eq!(split_location("1:\\dir:x"), ("1", "\\dir:x"));
+    }
 }
 
 #[cfg(test)]
 mod package_tests {
     use crate::utility::Args;
+    use clap::Parser;
 
     use super::*;
     use serial_test::serial;
@@ -230,11 +666,11 @@ mod package_tests {
 
         let expected = Package {
             current_version: String::from("1.7.3"),
-            install_cmd: String::from("myPackage@1.23.0"),
+            install_version: String::from("1.23.0"),
             install_dir_name: String::from("my_dir"),
             latest_version: String::from("2.0.1"),
             name: String::from("myPackage"),
-            skip: true,
+            skip: Some(SkipReason::WorkspaceDependency),
             upgrade_type: UpgradeType::Safe,
             wanted_version: String::from("1.23.0"),
         };
@@ -255,11 +691,11 @@ mod package_tests {
 
         let expected = Package {
             current_version: String::from("1.7.3"),
-            install_cmd: String::from("myPackage@2.0.1"),
+            install_version: String::from("2.0.1"),
             install_dir_name: String::from("dirNameThing"),
             latest_version: String::from("2.0.1"),
             name: String::from("myPackage"),
-            skip: true,
+            skip: Some(SkipReason::WorkspaceDependency),
             upgrade_type: UpgradeType::Major,
             wanted_version: String::from("1.23.0"),
         };
@@ -276,11 +712,11 @@ mod package_tests {
 
         let expected = Package {
             current_version: String::from("125.24222.1"),
-            install_cmd: String::from("@jonshort/cenv@125.24567.2"),
+            install_version: String::from("125.24567.2"),
             install_dir_name: String::from("my-dir_with:special chars"),
             latest_version: String::from("5412.0.0"),
             name: String::from("@jonshort/cenv"),
-            skip: true,
+            skip: Some(SkipReason::WorkspaceDependency),
             upgrade_type: UpgradeType::Safe,
             wanted_version: String::from("125.24567.2"),
         };
@@ -300,11 +736,11 @@ mod package_tests {
 
         let expected = Package {
             current_version: String::from("125.24222.1"),
-            install_cmd: String::from("@jonshort/cenv@5412.0.0"),
+            install_version: String::from("5412.0.0"),
             install_dir_name: String::from("a"),
             latest_version: String::from("5412.0.0"),
             name: String::from("@jonshort/cenv"),
-            skip: true,
+            skip: Some(SkipReason::WorkspaceDependency),
             upgrade_type: UpgradeType::Major,
             wanted_version: String::from("125.24567.2"),
         };
@@ -328,11 +764,11 @@ mod package_tests {
 
         let expected = Package {
             current_version: String::from("1.0.2"),
-            install_cmd: String::from("@jonshort/cenv@1.0.2"),
+            install_version: String::from("1.0.2"),
             install_dir_name: String::from("test_files"),
             latest_version: String::from("2.1.0"),
             name: String::from("@jonshort/cenv"),
-            skip: true,
+            skip: Some(SkipReason::OutOfRange),
             upgrade_type: UpgradeType::Safe,
             wanted_version: String::from("1.0.2"),
         };
@@ -357,11 +793,11 @@ mod package_tests {
 
         let expected = Package {
             current_version: String::from("1.0.2"),
-            install_cmd: String::from("@jonshort/cenv@1.0.3"),
+            install_version: String::from("1.0.3"),
             install_dir_name: String::from("[]{}()dir*"),
             latest_version: String::from("1.0.3"),
             name: String::from("@jonshort/cenv"),
-            skip: true,
+            skip: Some(SkipReason::WorkspaceDependency),
             upgrade_type: UpgradeType::Safe,
             wanted_version: String::from("1.0.3"),
         };
@@ -382,11 +818,11 @@ mod package_tests {
 
         let expected = Package {
             current_version: String::from("MISSING"),
-            install_cmd: String::from("@jonshort/cenv@1.0.3"),
+            install_version: String::from("1.0.3"),
             install_dir_name: String::from("\\|~#;<>"),
             latest_version: String::from("1.0.3"),
             name: String::from("@jonshort/cenv"),
-            skip: true,
+            skip: Some(SkipReason::WorkspaceDependency),
             upgrade_type: UpgradeType::Safe,
             wanted_version: String::from("1.0.3"),
         };
@@ -408,11 +844,11 @@ mod package_tests {
 
         let expected = Package {
             current_version: String::from("1.0.2"),
-            install_cmd: String::from("@jonshort/cenv@1.0.3"),
+            install_version: String::from("1.0.3"),
             install_dir_name: String::from("a"),
             latest_version: String::from("1.0.3"),
             name: String::from("@jonshort/cenv"),
-            skip: true,
+            skip: Some(SkipReason::WorkspaceDependency),
             upgrade_type: UpgradeType::Safe,
             wanted_version: String::from("1.0.3"),
         };
@@ -420,6 +856,24 @@ mod package_tests {
         Ok(())
     }
 
+    #[test]
+    fn expected_result_on_valid_input_windows_forward_slashes() -> Result<(), ParseError> {
+        let config = Config::new_from_args(Args {
+            latest: true,
+            ..Args::default()
+        });
+        let provided = String::from(
+            "C:/Users/me/git/a/node_modules/polished:polished@3.7.2:polished@3.6.5:polished@4.2.2:a",
+        );
+        let pkg = Package::new(provided, &config)?;
+
+        assert_eq!(pkg.name, String::from("polished"));
+        assert_eq!(pkg.current_version, String::from("3.6.5"));
+        assert_eq!(pkg.install_dir_name, String::from("a"));
+        assert_eq!(pkg.install_version, String::from("4.2.2"));
+        Ok(())
+    }
+
     #[test]
     #[serial]
     fn does_not_skip_direct_dep() -> Result<(), ParseError> {
@@ -439,11 +893,11 @@ mod package_tests {
 
         let expected = Package {
             current_version: String::from("1.0.2"),
-            install_cmd: String::from("@jonshort/cenv@1.0.3"),
+            install_version: String::from("1.0.3"),
             install_dir_name: String::from("test_files"),
             latest_version: String::from("1.0.3"),
             name: String::from("@jonshort/cenv"),
-            skip: false,
+            skip: None,
             upgrade_type: UpgradeType::Safe,
             wanted_version: String::from("1.0.3"),
         };
@@ -452,4 +906,810 @@ mod package_tests {
         env::set_current_dir(current).unwrap();
         Ok(())
     }
+
+    #[test]
+    fn does_not_skip_without_dir_name() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = None;
+        // location:name@wanted_version:name@current_version:name@latest_version:project
+        let provided =
+            String::from("location:myPackage@1.23.0:myPackage@1.7.3:myPackage@2.0.1:my_dir");
+        let pkg = Package::new(provided, &config)?;
+
+        let expected = Package {
+            current_version: String::from("1.7.3"),
+            install_version: String::from("1.23.0"),
+            install_dir_name: String::from("my_dir"),
+            latest_version: String::from("2.0.1"),
+            name: String::from("myPackage"),
+            skip: None,
+            upgrade_type: UpgradeType::Safe,
+            wanted_version: String::from("1.23.0"),
+        };
+        assert_eq!(pkg, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn skips_when_range_satisfied() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = None;
+        // already at wanted, only a new major is available
+        let provided =
+            String::from("location:myPackage@1.23.0:myPackage@1.23.0:myPackage@2.0.1:my_dir");
+        let pkg = Package::new(provided, &config)?;
+
+        assert_eq!(pkg.skip, Some(SkipReason::OutOfRange));
+        Ok(())
+    }
+
+    #[test]
+    fn ignore_range_mismatch_does_not_skip() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args {
+            ignore_range_mismatch: true,
+            ..Args::default()
+        });
+        config.current_dir_name = None;
+        let provided =
+            String::from("location:myPackage@1.23.0:myPackage@1.23.0:myPackage@2.0.1:my_dir");
+        let pkg = Package::new(provided, &config)?;
+
+        assert_eq!(pkg.skip, None);
+        assert_eq!(pkg.install_version, String::from("1.23.0"));
+        Ok(())
+    }
+
+    #[test]
+    fn ignore_range_mismatch_still_skips_workspace_deps() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args {
+            ignore_range_mismatch: true,
+            ..Args::default()
+        });
+        config.current_dir_name = Some(String::from("root"));
+        let provided =
+            String::from("location:myPackage@1.23.0:myPackage@1.23.0:myPackage@2.0.1:my_dir");
+        let pkg = Package::new(provided, &config)?;
+
+        assert_eq!(pkg.skip, Some(SkipReason::WorkspaceDependency));
+        Ok(())
+    }
+
+    #[test]
+    fn empty_dependent_is_not_a_workspace_dep() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = Some(String::from("root"));
+        let provided = String::from("location:myPackage@1.23.0:myPackage@1.22.0:myPackage@1.23.0:");
+        let pkg = Package::new(provided, &config)?;
+
+        assert_eq!(pkg.install_dir_name, "");
+        assert_eq!(pkg.skip, None);
+        Ok(())
+    }
+
+    #[test]
+    fn skips_at_target() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args {
+            latest: true,
+            ..Args::default()
+        });
+        config.current_dir_name = None;
+        let provided =
+            String::from("location:myPackage@2.0.1:myPackage@2.0.1:myPackage@2.0.1:my_dir");
+        let pkg = Package::new(provided, &config)?;
+
+        assert_eq!(pkg.skip, Some(SkipReason::AtTarget));
+        Ok(())
+    }
+
+    #[test]
+    fn skips_excluded_by_glob() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args {
+            include: Some(String::from("@jonshort/*")),
+            ..Args::default()
+        });
+        config.current_dir_name = None;
+        let provided =
+            String::from("location:myPackage@1.23.0:myPackage@1.7.3:myPackage@2.0.1:my_dir");
+        let pkg = Package::new(provided, &config)?;
+
+        assert_eq!(pkg.skip, Some(SkipReason::ExcludedByGlob));
+        Ok(())
+    }
+
+    #[test]
+    fn name_regex_filters() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--name-regex",
+            "^(debug|@jonshort/.+)$",
+        ]));
+        config.current_dir_name = None;
+        // location:name@wanted_version:name@current_version:name@latest_version
+        let cases = [
+            ("location:debug@3.2.7:debug@3.2.6:debug@4.3.4:my_dir", None),
+            (
+                "location:debug-utils@3.2.7:debug-utils@3.2.6:debug-utils@4.3.4:my_dir",
+                Some(SkipReason::ExcludedByRegex),
+            ),
+            (
+                "location:@jonshort/cenv@1.0.3:@jonshort/cenv@1.0.2:@jonshort/cenv@1.0.3:my_dir",
+                None,
+            ),
+            (
+                "location:@types/debug@4.1.12:@types/debug@4.1.7:@types/debug@4.1.12:my_dir",
+                Some(SkipReason::ExcludedByRegex),
+            ),
+        ];
+
+        for (provided, expected) in cases {
+            let pkg = Package::new(String::from(provided), &config)?;
+            assert_eq!(pkg.skip, expected, "{}", provided);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn exclude_current_major_filters() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--latest",
+            "--exclude-current-major",
+        ]));
+        config.current_dir_name = None;
+        // location:name@wanted_version:name@current_version:name@latest_version
+        let cases = [
+            ("location:debug@3.2.7:debug@3.2.6:debug@4.3.4:my_dir", None),
+            (
+                "location:debug@3.2.7:debug@3.2.6:debug@3.2.7:my_dir",
+                Some(SkipReason::SameMajor),
+            ),
+            (
+                "location:polished@0.3.2:polished@0.3.1:polished@0.4.0:my_dir",
+                None,
+            ),
+            (
+                "location:polished@0.3.2:polished@0.3.1:polished@0.3.2:my_dir",
+                Some(SkipReason::SameMajor),
+            ),
+            // not installed, so there's no current major to compare with
+            ("location:debug@3.2.7:MISSING:debug@3.2.7:my_dir", None),
+        ];
+
+        for (provided, expected) in cases {
+            let pkg = Package::new(String::from(provided), &config)?;
+            assert_eq!(pkg.skip, expected, "{}", provided);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn exclude_majors_over_filters() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--latest",
+            "--exclude-majors-over",
+            "2",
+        ]));
+        config.current_dir_name = None;
+        // location:name@wanted_version:name@current_version:name@latest_version
+        let cases = [
+            ("location:debug@2.6.9:debug@2.6.8:debug@4.3.4:my_dir", None),
+            (
+                "location:debug@2.6.9:debug@2.6.8:debug@6.0.0:my_dir",
+                Some(SkipReason::TooManyMajors),
+            ),
+            ("location:debug@2.6.9:debug@2.6.8:debug@2.6.9:my_dir", None),
+            // not installed, so there's no jump to measure
+            ("location:debug@2.6.9:MISSING:debug@6.0.0:my_dir", None),
+        ];
+
+        for (provided, expected) in cases {
+            let pkg = Package::new(String::from(provided), &config)?;
+            assert_eq!(pkg.skip, expected, "{}", provided);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn workspace_dep_takes_precedence() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args {
+            include: Some(String::from("@jonshort/*")),
+            ..Args::default()
+        });
+        config.current_dir_name = Some(String::from("root"));
+        let provided =
+            String::from("location:myPackage@1.23.0:myPackage@1.23.0:myPackage@2.0.1:my_dir");
+        let pkg = Package::new(provided, &config)?;
+
+        assert_eq!(pkg.skip, Some(SkipReason::WorkspaceDependency));
+        Ok(())
+    }
+
+    #[test]
+    fn skips_non_registry_sources() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = None;
+        // npm reports whatever it finds in the lockfile for these
+        let provided =
+            String::from("location:local-utils@1.0.0:local-utils@0.9.0:local-utils@1.0.0:my_dir");
+
+        let mut pkg = Package::new(provided.clone(), &config)?;
+        pkg.check_source(Some("file:../local-utils"));
+        assert_eq!(pkg.skip, Some(SkipReason::LocalPath));
+
+        let mut pkg = Package::new(provided.clone(), &config)?;
+        pkg.check_source(Some("git+https://github.com/JonShort/local-utils.git"));
+        assert_eq!(pkg.skip, Some(SkipReason::GitSource));
+
+        let mut pkg = Package::new(provided.clone(), &config)?;
+        pkg.check_source(Some("^0.9.0"));
+        assert_eq!(pkg.skip, None);
+
+        let mut pkg = Package::new(provided, &config)?;
+        pkg.check_source(None);
+        assert_eq!(pkg.skip, None);
+        Ok(())
+    }
+
+    #[test]
+    fn check_source_keeps_earlier_reason() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = Some(String::from("root"));
+        let provided =
+            String::from("location:local-utils@1.0.0:local-utils@0.9.0:local-utils@1.0.0:my_dir");
+        let mut pkg = Package::new(provided, &config)?;
+        pkg.check_source(Some("file:../local-utils"));
+
+        assert_eq!(pkg.skip, Some(SkipReason::WorkspaceDependency));
+        Ok(())
+    }
+
+    #[test]
+    fn skips_excluded_types() -> Result<(), ParseError> {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = None;
+        let provided = String::from("location:debug@4.3.4:debug@4.3.2:debug@4.3.4:my_dir");
+
+        let mut pkg = Package::new(provided.clone(), &config)?;
+        pkg.check_type(
+            &[DependencyType::Dev, DependencyType::Peer],
+            &[DependencyType::Dev],
+        );
+        assert_eq!(pkg.skip, Some(SkipReason::ExcludedByType));
+
+        let mut pkg = Package::new(provided.clone(), &config)?;
+        pkg.check_type(&[DependencyType::Prod], &[DependencyType::Dev]);
+        assert_eq!(pkg.skip, None);
+
+        let mut pkg = Package::new(provided, &config)?;
+        pkg.check_type(&[], &[DependencyType::Dev]);
+        assert_eq!(pkg.skip, None);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_versions_on_missing_current() -> Result<(), ParseError> {
+        let config = Config::new_from_args(Args {
+            strict_semver: true,
+            ..Args::default()
+        });
+        // location:name@wanted_version:MISSING:name@latest_version
+        let provided =
+            String::from("location:@jonshort/cenv@1.0.3:MISSING:@jonshort/cenv@1.0.3:test");
+        let pkg = Package::new(provided, &config)?;
+
+        assert_eq!(pkg.invalid_versions(), vec!["MISSING"]);
+        Ok(())
+    }
+
+    #[test]
+    fn no_invalid_versions_on_valid_input() -> Result<(), ParseError> {
+        let config = Config::new_from_args(Args {
+            strict_semver: true,
+            ..Args::default()
+        });
+        // location:name@wanted_version:name@current_version:name@latest_version
+        let provided =
+            String::from("location:myPackage@1.23.0:myPackage@1.7.3:myPackage@2.0.1:my_dir");
+        let pkg = Package::new(provided, &config)?;
+
+        assert_eq!(pkg.invalid_versions(), Vec::<&str>::new());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod install_spec_tests {
+    use crate::utility::Args;
+    use clap::Parser;
+
+    use super::*;
+
+    fn config(args: &[&str]) -> Config {
+        let mut config = Config::new_from_args(Args::parse_from(args));
+        config.current_dir_name = None;
+        config
+    }
+
+    #[test]
+    fn unscoped_package() {
+        let config = config(&["npm-bumpall", "--latest"]);
+        let pkg = Package::new(
+            String::from("location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test"),
+            &config,
+        )
+        .unwrap();
+        let spec = pkg.install_spec(&config);
+
+        assert_eq!(
+            spec,
+            InstallSpec {
+                name: String::from("polished"),
+                version: String::from("4.2.2"),
+                flags: vec![],
+            }
+        );
+        assert_eq!(spec.to_string(), "polished@4.2.2");
+    }
+
+    #[test]
+    fn scoped_package() {
+        let config = config(&["npm-bumpall"]);
+        let pkg = Package::new(
+            String::from(
+                "location:@jonshort/cenv@1.0.3:@jonshort/cenv@1.0.2:@jonshort/cenv@2.0.0:test",
+            ),
+            &config,
+        )
+        .unwrap();
+        let spec = pkg.install_spec(&config);
+
+        assert_eq!(spec.name, "@jonshort/cenv");
+        assert_eq!(spec.version, "1.0.3");
+        assert_eq!(spec.to_string(), "@jonshort/cenv@1.0.3");
+    }
+
+    #[test]
+    fn includes_install_flags() {
+        let config = config(&[
+            "npm-bumpall",
+            "--lockfile-version",
+            "3",
+            "--legacy-peer-deps",
+        ]);
+        let pkg = Package::new(
+            String::from("location:debug@4.3.4:debug@4.3.2:debug@4.3.4:test"),
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(
+            pkg.install_spec(&config).flags,
+            vec!["--lockfile-version=3", "--legacy-peer-deps"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod in_range_updates_tests {
+    use crate::utility::Args;
+
+    use super::*;
+
+    fn packages(src: &[&str]) -> Vec<Package> {
+        let mut config = Config::new_from_args(Args {
+            latest: true,
+            ..Args::default()
+        });
+        config.current_dir_name = None;
+
+        src.iter()
+            .map(|s| Package::new(String::from(*s), &config).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn frozen_with_only_out_of_range_updates() {
+        // location:name@wanted_version:name@current_version:name@latest_version:project
+        let pkgs = packages(&[
+            "location:polished@3.7.2:polished@3.7.2:polished@4.2.2:test",
+            "location:debug@3.2.7:debug@3.2.7:debug@4.3.4:test",
+        ]);
+
+        assert!(in_range_updates(&pkgs).is_empty());
+    }
+
+    #[test]
+    fn not_frozen_with_in_range_update() {
+        let pkgs = packages(&[
+            "location:polished@3.7.2:polished@3.7.2:polished@4.2.2:test",
+            "location:left-pad@1.3.0:left-pad@1.2.0:left-pad@1.3.0:test",
+        ]);
+
+        assert!(!pkgs[0].has_in_range_update());
+        assert!(pkgs[1].has_in_range_update());
+        assert_eq!(in_range_updates(&pkgs), vec![&pkgs[1]]);
+    }
+
+    #[test]
+    fn not_frozen_with_missing_package() {
+        let pkgs = packages(&["location:left-pad@1.3.0:MISSING:left-pad@1.3.0:test"]);
+
+        assert!(!in_range_updates(&pkgs).is_empty());
+    }
+
+    #[test]
+    fn frozen_with_nothing_outdated() {
+        assert!(in_range_updates(&[]).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod partition_by_major_tests {
+    use crate::utility::Args;
+
+    use super::*;
+
+    #[test]
+    fn splits_safe_from_major() {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = None;
+        // location:name@wanted_version:name@current_version:name@latest_version:project
+        let packages: Vec<Package> = [
+            "location:left-pad@1.3.0:left-pad@1.2.0:left-pad@1.3.0:test",
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:@jonshort/cenv@1.0.3:@jonshort/cenv@1.0.2:@jonshort/cenv@1.0.3:test",
+            "location:debug@3.2.7:MISSING:debug@4.3.4:test",
+        ]
+        .iter()
+        .map(|s| Package::new(String::from(*s), &config).unwrap())
+        .collect();
+
+        let (install, report) = partition_by_major(packages);
+        let names =
+            |pkgs: &[Package]| -> Vec<String> { pkgs.iter().map(|p| p.name.clone()).collect() };
+
+        assert_eq!(names(&install), vec!["left-pad", "@jonshort/cenv"]);
+        assert_eq!(names(&report), vec!["polished", "debug"]);
+    }
+
+    #[test]
+    fn handles_no_packages() {
+        let (install, report) = partition_by_major(vec![]);
+
+        assert!(install.is_empty());
+        assert!(report.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod nothing_to_bump_tests {
+    use crate::utility::Args;
+
+    use super::*;
+
+    fn skipped(reasons: &[SkipReason]) -> Vec<(Package, SkipReason)> {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = None;
+
+        reasons
+            .iter()
+            .map(|reason| {
+                let pkg = Package::new(
+                    String::from("location:debug@4.3.4:debug@4.3.2:debug@4.3.4:test"),
+                    &config,
+                )
+                .unwrap();
+                (pkg, *reason)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn up_to_date_when_npm_reports_nothing() {
+        assert_eq!(nothing_to_bump(&[], 0), NothingToBump::UpToDate);
+    }
+
+    #[test]
+    fn up_to_date_when_only_skipped_at_target() {
+        let skipped = skipped(&[
+            SkipReason::AtTarget,
+            SkipReason::OutOfRange,
+            SkipReason::WorkspaceDependency,
+        ]);
+
+        assert_eq!(nothing_to_bump(&skipped, 0), NothingToBump::UpToDate);
+    }
+
+    #[test]
+    fn filtered_when_excluded_by_options() {
+        let skipped = skipped(&[
+            SkipReason::AtTarget,
+            SkipReason::ExcludedByGlob,
+            SkipReason::ExcludedByType,
+        ]);
+
+        assert_eq!(nothing_to_bump(&skipped, 0), NothingToBump::AllFiltered(2));
+    }
+
+    #[test]
+    fn filtered_when_dropped_after_the_check() {
+        assert_eq!(nothing_to_bump(&[], 3), NothingToBump::AllFiltered(3));
+        assert_eq!(
+            nothing_to_bump(&skipped(&[SkipReason::ExcludedByRegex]), 1),
+            NothingToBump::AllFiltered(2)
+        );
+    }
+}
+
+#[cfg(test)]
+mod sort_by_name_tests {
+    use crate::utility::Args;
+    use clap::Parser;
+
+    use super::*;
+
+    fn packages(lines: &[&str]) -> Vec<Package> {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = None;
+
+        lines
+            .iter()
+            .map(|s| Package::new(String::from(*s), &config).unwrap())
+            .collect()
+    }
+
+    fn names_and_dirs(packages: &[Package]) -> Vec<(&str, &str)> {
+        packages
+            .iter()
+            .map(|pkg| (pkg.name.as_str(), pkg.install_dir_name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn same_order_however_npm_reports_them() {
+        // location:name@wanted_version:name@current_version:name@latest_version:project
+        let mut first = packages(&[
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:web",
+            "location:@jonshort/cenv@1.0.3:@jonshort/cenv@1.0.2:@jonshort/cenv@1.0.3:test",
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:api",
+        ]);
+        let mut second = packages(&[
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:api",
+            "location:@jonshort/cenv@1.0.3:@jonshort/cenv@1.0.2:@jonshort/cenv@1.0.3:test",
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:web",
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+        ]);
+
+        sort_by_name(&mut first);
+        sort_by_name(&mut second);
+
+        assert_eq!(names_and_dirs(&first), names_and_dirs(&second));
+        assert_eq!(
+            names_and_dirs(&first),
+            vec![
+                ("@jonshort/cenv", "test"),
+                ("debug", "api"),
+                ("debug", "web"),
+                ("polished", "test"),
+            ]
+        );
+    }
+
+    #[test]
+    fn sorts_unless_no_sort() {
+        let reported = [
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test",
+        ];
+        let mut sorted = packages(&reported);
+        let mut unsorted = packages(&reported);
+        let mut config = Config::new_from_args(Args::parse_from(["npm-bumpall", "--no-sort"]));
+        config.current_dir_name = None;
+
+        apply_listing_order(&mut sorted, &Config::new_from_args(Args::default()));
+        apply_listing_order(&mut unsorted, &config);
+
+        assert_eq!(
+            names_and_dirs(&sorted),
+            vec![("debug", "test"), ("polished", "test")]
+        );
+        assert_eq!(
+            names_and_dirs(&unsorted),
+            vec![("polished", "test"), ("debug", "test")]
+        );
+    }
+}
+
+#[cfg(test)]
+mod order_by_dependents_tests {
+    use crate::utility::Args;
+
+    use super::*;
+
+    fn packages(lines: &[&str]) -> Vec<Package> {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = None;
+
+        lines
+            .iter()
+            .map(|s| Package::new(String::from(*s), &config).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn counts_each_dependent_once() {
+        // location:name@wanted_version:name@current_version:name@latest_version:project
+        let reported = packages(&[
+            "location:debug@4.3.4:debug@4.3.2:debug@4.3.4:web",
+            "location:debug@4.3.4:debug@4.3.2:debug@4.3.4:api",
+            "location:debug@4.3.4:debug@4.3.1:debug@4.3.4:api",
+            "location:ms@2.1.3:ms@2.1.2:ms@2.1.3:web",
+        ]);
+        let counts = dependent_counts(&reported);
+
+        assert_eq!(counts.get("debug"), Some(&2));
+        assert_eq!(counts.get("ms"), Some(&1));
+        assert_eq!(counts.len(), 2);
+    }
+
+    #[test]
+    fn most_depended_upon_first() {
+        let reported = packages(&[
+            "location:ms@2.1.3:ms@2.1.2:ms@2.1.3:web",
+            "location:left-pad@1.3.0:left-pad@1.2.0:left-pad@1.3.0:web",
+            "location:debug@4.3.4:debug@4.3.2:debug@4.3.4:web",
+            "location:debug@4.3.4:debug@4.3.2:debug@4.3.4:api",
+            "location:debug@4.3.4:debug@4.3.2:debug@4.3.4:docs",
+            "location:left-pad@1.3.0:left-pad@1.2.0:left-pad@1.3.0:api",
+        ]);
+        let counts = dependent_counts(&reported);
+        let mut to_bump = packages(&[
+            "location:ms@2.1.3:ms@2.1.2:ms@2.1.3:web",
+            "location:left-pad@1.3.0:left-pad@1.2.0:left-pad@1.3.0:web",
+            "location:debug@4.3.4:debug@4.3.2:debug@4.3.4:web",
+            "location:polished@3.7.2:polished@3.6.5:polished@3.7.2:web",
+        ]);
+
+        order_by_dependents(&mut to_bump, &counts);
+
+        let names: Vec<&str> = to_bump.iter().map(|pkg| pkg.name.as_str()).collect();
+        // polished wasn't counted, so it goes last
+        assert_eq!(names, vec!["debug", "left-pad", "ms", "polished"]);
+    }
+}
+
+#[cfg(test)]
+mod group_majors_last_tests {
+    use crate::utility::Args;
+
+    use super::*;
+
+    fn names(packages: &[Package]) -> Vec<&str> {
+        packages.iter().map(|pkg| pkg.name.as_str()).collect()
+    }
+
+    #[test]
+    fn moves_majors_after_the_rest_in_order() {
+        let mut config = Config::new_from_args(Args {
+            latest: true,
+            ..Args::default()
+        });
+        config.current_dir_name = None;
+        // location:name@wanted_version:name@current_version:name@latest_version:project
+        let mut packages: Vec<Package> = [
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:left-pad@1.3.0:left-pad@1.2.0:left-pad@1.3.0:test",
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test",
+            "location:@jonshort/cenv@1.0.3:@jonshort/cenv@1.0.2:@jonshort/cenv@1.0.3:test",
+            "location:ms@2.1.3:ms@2.1.2:ms@2.1.3:test",
+        ]
+        .iter()
+        .map(|s| Package::new(String::from(*s), &config).unwrap())
+        .collect();
+
+        group_majors_last(&mut packages);
+
+        assert_eq!(
+            names(&packages),
+            vec!["left-pad", "@jonshort/cenv", "ms", "polished", "debug"]
+        );
+    }
+
+    #[test]
+    fn handles_no_packages() {
+        let mut packages: Vec<Package> = vec![];
+        group_majors_last(&mut packages);
+
+        assert!(packages.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod sort_by_popularity_tests {
+    use crate::utility::Args;
+
+    use super::*;
+
+    fn packages(lines: &[&str]) -> Vec<Package> {
+        // the upgrade type is only major when bumping to latest
+        let mut config = Config::new_from_args(Args {
+            latest: true,
+            ..Args::default()
+        });
+        config.current_dir_name = None;
+
+        lines
+            .iter()
+            .map(|s| Package::new(String::from(*s), &config).unwrap())
+            .collect()
+    }
+
+    fn names(packages: &[Package]) -> Vec<&str> {
+        packages.iter().map(|pkg| pkg.name.as_str()).collect()
+    }
+
+    fn downloads(counts: &[(&str, u64)]) -> HashMap<String, u64> {
+        counts
+            .iter()
+            .map(|(name, count)| (String::from(*name), *count))
+            .collect()
+    }
+
+    #[test]
+    fn most_downloaded_first() {
+        let mut majors = packages(&[
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test",
+            "location:chalk@4.1.2:chalk@4.1.0:chalk@5.3.0:test",
+        ]);
+
+        sort_by_popularity(
+            &mut majors,
+            &downloads(&[
+                ("polished", 4_000_000),
+                ("debug", 280_000_000),
+                ("chalk", 300_000_000),
+            ]),
+        );
+
+        assert_eq!(names(&majors), vec!["chalk", "debug", "polished"]);
+    }
+
+    #[test]
+    fn alphabetical_without_counts() {
+        let mut majors = packages(&[
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test",
+            "location:chalk@4.1.2:chalk@4.1.0:chalk@5.3.0:test",
+        ]);
+
+        // e.g. offline, where only some (or none) of the counts could be fetched
+        sort_by_popularity(&mut majors, &downloads(&[("polished", 4_000_000)]));
+        assert_eq!(names(&majors), vec!["polished", "chalk", "debug"]);
+
+        sort_by_popularity(&mut majors, &HashMap::new());
+        assert_eq!(names(&majors), vec!["chalk", "debug", "polished"]);
+    }
+
+    #[test]
+    fn leaves_other_updates_in_place() {
+        let mut to_bump = packages(&[
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:ms@2.1.3:ms@2.1.2:ms@2.1.3:test",
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test",
+        ]);
+
+        sort_majors_by_popularity(
+            &mut to_bump,
+            &downloads(&[
+                ("polished", 4_000_000),
+                ("debug", 280_000_000),
+                ("ms", 290_000_000),
+            ]),
+        );
+
+        assert_eq!(names(&to_bump), vec!["debug", "ms", "polished"]);
+    }
 }
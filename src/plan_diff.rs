@@ -0,0 +1,200 @@
+use serde::Deserialize;
+use std::{error::Error, fs, path::Path};
+
+use crate::package::Package;
+use crate::utility::{Config, UpgradeStyle};
+
+/// A package as listed in a plan written by --format json, --plan-fd or --output-dir
+#[derive(Debug, Deserialize)]
+struct PlannedPackage {
+    name: String,
+    current: String,
+    wanted: String,
+    latest: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Plan {
+    packages: Vec<PlannedPackage>,
+}
+
+/// The packages listed in a saved plan, with their targets worked out as for this run
+pub fn parse_plan(src: &str, config: &Config) -> Result<Vec<Package>, serde_json::Error> {
+    let plan: Plan = serde_json::from_str(src)?;
+    // the plan only lists the packages which were going to be bumped
+    let install_dir_name = config.current_dir_name.clone().unwrap_or_default();
+
+    Ok(plan
+        .packages
+        .into_iter()
+        .map(|pkg| {
+            Package::from_versions(
+                pkg.name,
+                pkg.wanted,
+                pkg.current,
+                pkg.latest,
+                install_dir_name.clone(),
+                config,
+            )
+        })
+        .collect())
+}
+
+pub fn load(path: &Path, config: &Config) -> Result<Vec<Package>, Box<dyn Error>> {
+    let src = fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read {} - {}", path.display(), err))?;
+
+    Ok(parse_plan(&src, config)?)
+}
+
+/// A package whose target version changed between the plans
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangedTarget {
+    pub name: String,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlanDiff {
+    /// Outdated now, but not in the earlier plan
+    pub added: Vec<Package>,
+    /// In the earlier plan, but no longer outdated
+    pub resolved: Vec<Package>,
+    pub changed: Vec<ChangedTarget>,
+}
+
+impl PlanDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.resolved.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// What changed between an earlier plan and this one, matching packages by name
+pub fn diff(before: &[Package], after: &[Package], upgrade_style: &UpgradeStyle) -> PlanDiff {
+    let mut plan_diff = PlanDiff::default();
+
+    for pkg in after.iter() {
+        match before.iter().find(|earlier| earlier.name == pkg.name) {
+            None => plan_diff.added.push(pkg.clone()),
+            Some(earlier) => {
+                let before_target = earlier.upgrade_version(upgrade_style);
+                let after_target = pkg.upgrade_version(upgrade_style);
+
+                if before_target != after_target {
+                    plan_diff.changed.push(ChangedTarget {
+                        name: pkg.name.clone(),
+                        before: before_target.to_string(),
+                        after: after_target.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    plan_diff.resolved = before
+        .iter()
+        .filter(|earlier| !after.iter().any(|pkg| pkg.name == earlier.name))
+        .cloned()
+        .collect();
+
+    plan_diff
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod diff_tests {
+    use super::*;
+    use crate::utility::Args;
+    use clap::Parser;
+
+    fn config() -> Config {
+        let mut config = Config::new_from_args(Args::parse_from(["npm-bumpall", "--latest"]));
+        config.current_dir_name = None;
+        config
+    }
+
+    fn packages(lines: &[&str]) -> Vec<Package> {
+        lines
+            .iter()
+            .map(|s| Package::new(String::from(*s), &config()).unwrap())
+            .collect()
+    }
+
+    fn names(packages: &[Package]) -> Vec<&str> {
+        packages.iter().map(|pkg| pkg.name.as_str()).collect()
+    }
+
+    #[test]
+    fn finds_added_resolved_and_changed() {
+        // location:name@wanted_version:name@current_version:name@latest_version:project
+        let before = packages(&[
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.1:test",
+            "location:ms@2.1.3:ms@2.1.2:ms@2.1.3:test",
+            "location:react@18.2.0:react@18.1.0:react@18.2.0:test",
+        ]);
+        let after = packages(&[
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:test",
+            "location:react@18.2.0:react@18.1.0:react@18.2.0:test",
+            "location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test",
+        ]);
+
+        let plan_diff = diff(&before, &after, &UpgradeStyle::Latest);
+
+        assert_eq!(names(&plan_diff.added), vec!["debug"]);
+        assert_eq!(names(&plan_diff.resolved), vec!["ms"]);
+        assert_eq!(
+            plan_diff.changed,
+            vec![ChangedTarget {
+                name: String::from("polished"),
+                before: String::from("4.2.1"),
+                after: String::from("4.2.2"),
+            }]
+        );
+    }
+
+    #[test]
+    fn nothing_changed() {
+        let plan = packages(&["location:ms@2.1.3:ms@2.1.2:ms@2.1.3:test"]);
+
+        assert!(diff(&plan, &plan, &UpgradeStyle::Latest).is_empty());
+    }
+
+    #[test]
+    fn targets_follow_the_upgrade_style() {
+        let before = packages(&["location:debug@3.2.7:debug@3.2.6:debug@4.3.3:test"]);
+        let after = packages(&["location:debug@3.2.7:debug@3.2.6:debug@4.3.4:test"]);
+
+        assert!(diff(&before, &after, &UpgradeStyle::Wanted).is_empty());
+        assert_eq!(
+            diff(&before, &after, &UpgradeStyle::Latest).changed.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn reads_a_saved_plan() {
+        let src = r#"{
+            "dry_run": true,
+            "upgrade_style": "latest",
+            "packages": [{
+                "name": "polished",
+                "current": "3.6.5",
+                "wanted": "3.7.2",
+                "latest": "4.2.2",
+                "target": "4.2.2",
+                "upgrade_type": "major"
+            }],
+            "install_args": ["polished@4.2.2"]
+        }"#;
+
+        let plan = parse_plan(src, &config()).unwrap();
+
+        assert_eq!(
+            plan,
+            packages(&["location:polished@3.7.2:polished@3.6.5:polished@4.2.2:"])
+        );
+        assert!(parse_plan("[]", &config()).is_err());
+    }
+}
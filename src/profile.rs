@@ -0,0 +1,183 @@
+use serde_json::Value;
+use std::{error::Error, ffi::OsString, fs};
+
+pub const PROFILE_FILE: &str = ".bumpallrc.json";
+
+// e.g. "legacy_peer_deps" or "legacyPeerDeps" -> "--legacy-peer-deps"
+fn flag_name(key: &str) -> String {
+    let mut flag = String::from("--");
+
+    for c in key.chars() {
+        match c {
+            '_' => flag.push('-'),
+            c if c.is_ascii_uppercase() => {
+                flag.push('-');
+                flag.push(c.to_ascii_lowercase());
+            }
+            c => flag.push(c),
+        }
+    }
+
+    flag
+}
+
+fn push_setting(args: &mut Vec<String>, flag: &str, value: &Value) -> Result<(), String> {
+    match value {
+        Value::Bool(true) => args.push(flag.to_string()),
+        // there's nothing to unset, the flag's default is already false
+        Value::Bool(false) | Value::Null => {}
+        Value::Number(n) => args.extend([flag.to_string(), n.to_string()]),
+        Value::String(s) => args.extend([flag.to_string(), s.to_string()]),
+        Value::Array(values) => {
+            for v in values.iter() {
+                push_setting(args, flag, v)?;
+            }
+        }
+        Value::Object(_) => return Err(format!("Unsupported value for {} in profile", flag)),
+    }
+
+    Ok(())
+}
+
+/// Turns the named profile from the config file into the command line arguments it stands for
+pub fn profile_args(src: &str, name: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let config: Value = serde_json::from_str(src)?;
+
+    let profile = config
+        .get("profiles")
+        .and_then(|p| p.get(name))
+        .and_then(|p| p.as_object())
+        .ok_or_else(|| format!("No profile named \"{}\" in {}", name, PROFILE_FILE))?;
+
+    let mut args = vec![];
+
+    for (key, value) in profile.iter() {
+        let flag = flag_name(key);
+
+        if flag == "--profile" {
+            return Err("Profiles can't select another profile".into());
+        }
+
+        push_setting(&mut args, &flag, value)?;
+    }
+
+    Ok(args)
+}
+
+pub fn load(name: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let src = fs::read_to_string(PROFILE_FILE)
+        .map_err(|err| format!("Unable to read {} - {}", PROFILE_FILE, err))?;
+
+    profile_args(&src, name)
+}
+
+/// Inserts the profile's arguments before the ones actually passed, so those still take precedence
+pub fn with_profile_args(
+    argv: impl IntoIterator<Item = OsString>,
+    profile_args: Vec<String>,
+) -> Vec<OsString> {
+    let mut argv = argv.into_iter();
+    let mut resolved: Vec<OsString> = argv.next().into_iter().collect();
+
+    resolved.extend(profile_args.into_iter().map(OsString::from));
+    resolved.extend(argv);
+
+    resolved
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod profile_args_tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "profiles": {
+            "safe": { "latest": false, "strictSemver": true },
+            "aggressive": { "latest": true, "retries": 2, "install_args": "--no-audit" },
+            "nested": { "include": { "glob": "*" } }
+        }
+    }"#;
+
+    #[test]
+    fn resolves_named_profile() {
+        assert_eq!(
+            profile_args(CONFIG, "aggressive").unwrap(),
+            vec!["--latest", "--retries", "2", "--install-args", "--no-audit"]
+        );
+        assert_eq!(
+            profile_args(CONFIG, "safe").unwrap(),
+            vec!["--strict-semver"]
+        );
+    }
+
+    #[test]
+    fn err_on_unknown_profile() {
+        let err = profile_args(CONFIG, "yolo").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            String::from("No profile named \"yolo\" in .bumpallrc.json")
+        );
+        assert!(profile_args("{}", "safe").is_err());
+    }
+
+    #[test]
+    fn err_on_unsupported_values() {
+        assert!(profile_args(CONFIG, "nested").is_err());
+        assert!(profile_args(r#"{ "profiles": { "a": { "profile": "b" } } }"#, "a").is_err());
+    }
+
+    #[test]
+    fn converts_key_styles() {
+        assert_eq!(flag_name("legacy_peer_deps"), "--legacy-peer-deps");
+        assert_eq!(flag_name("legacyPeerDeps"), "--legacy-peer-deps");
+        assert_eq!(flag_name("backoff-base-ms"), "--backoff-base-ms");
+    }
+}
+
+#[cfg(test)]
+mod with_profile_args_tests {
+    use super::*;
+    use crate::retry::BackoffStrategy;
+    use crate::utility::Args;
+    use clap::Parser;
+
+    fn argv(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn profile_args_come_first() {
+        assert_eq!(
+            with_profile_args(argv(&["npm-bumpall", "-u"]), vec![String::from("--latest")]),
+            argv(&["npm-bumpall", "--latest", "-u"])
+        );
+    }
+
+    #[test]
+    fn cli_overrides_profile() {
+        let profile = vec![
+            String::from("--retries"),
+            String::from("2"),
+            String::from("--backoff-strategy"),
+            String::from("fixed"),
+            String::from("--latest"),
+        ];
+        let args = Args::parse_from(with_profile_args(
+            argv(&[
+                "npm-bumpall",
+                "--profile",
+                "aggressive",
+                "--retries",
+                "5",
+                "--latest",
+            ]),
+            profile,
+        ));
+
+        assert_eq!(args.retries, 5);
+        assert_eq!(args.backoff_strategy, BackoffStrategy::Fixed);
+        assert!(args.latest);
+    }
+}
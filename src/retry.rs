@@ -0,0 +1,154 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{thread, time::Duration};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BackoffStrategy {
+    /// Wait the base delay between every attempt
+    Fixed,
+    /// Wait the base delay multiplied by the retry number
+    Linear,
+    /// Double the wait after every retry
+    #[default]
+    Exponential,
+}
+
+impl BackoffStrategy {
+    /// How long to wait before the given retry (the first retry is 1)
+    pub fn delay(&self, base: Duration, retry: u32) -> Duration {
+        let factor = match self {
+            BackoffStrategy::Fixed => 1,
+            BackoffStrategy::Linear => retry,
+            BackoffStrategy::Exponential => 2u32.saturating_pow(retry.saturating_sub(1)),
+        };
+
+        base.checked_mul(factor).unwrap_or(Duration::MAX)
+    }
+}
+
+/// Runs `attempt` until `is_done` passes or the retries are used up, returning the last result
+pub fn retry<T>(
+    retries: u32,
+    strategy: BackoffStrategy,
+    base: Duration,
+    mut attempt: impl FnMut() -> T,
+    is_done: impl Fn(&T) -> bool,
+) -> T {
+    let mut result = attempt();
+
+    for retry in 1..=retries {
+        if is_done(&result) {
+            break;
+        }
+
+        thread::sleep(strategy.delay(base, retry));
+        result = attempt();
+    }
+
+    result
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod delay_tests {
+    use super::*;
+
+    fn delays(strategy: BackoffStrategy) -> Vec<u64> {
+        (1..=5)
+            .map(|retry| {
+                strategy
+                    .delay(Duration::from_millis(100), retry)
+                    .as_millis() as u64
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fixed_sequence() {
+        assert_eq!(
+            delays(BackoffStrategy::Fixed),
+            vec![100, 100, 100, 100, 100]
+        );
+    }
+
+    #[test]
+    fn linear_sequence() {
+        assert_eq!(
+            delays(BackoffStrategy::Linear),
+            vec![100, 200, 300, 400, 500]
+        );
+    }
+
+    #[test]
+    fn exponential_sequence() {
+        assert_eq!(
+            delays(BackoffStrategy::Exponential),
+            vec![100, 200, 400, 800, 1600]
+        );
+    }
+
+    #[test]
+    fn saturates_instead_of_overflowing() {
+        assert_eq!(
+            BackoffStrategy::Exponential.delay(Duration::from_secs(u64::MAX / 2), 40),
+            Duration::MAX
+        );
+    }
+}
+
+#[cfg(test)]
+mod retry_tests {
+    use super::*;
+
+    #[test]
+    fn stops_on_success() {
+        let mut attempts = 0;
+        let result = retry(
+            5,
+            BackoffStrategy::Fixed,
+            Duration::ZERO,
+            || {
+                attempts += 1;
+                attempts
+            },
+            |n| *n == 2,
+        );
+
+        assert_eq!(result, 2);
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn gives_up_after_retries() {
+        let mut attempts = 0;
+        let result = retry(
+            3,
+            BackoffStrategy::Exponential,
+            Duration::ZERO,
+            || {
+                attempts += 1;
+                false
+            },
+            |ok| *ok,
+        );
+
+        assert!(!result);
+        assert_eq!(attempts, 4);
+    }
+
+    #[test]
+    fn no_retries_by_default() {
+        let mut attempts = 0;
+        retry(
+            0,
+            BackoffStrategy::default(),
+            Duration::ZERO,
+            || attempts += 1,
+            |_| false,
+        );
+
+        assert_eq!(attempts, 1);
+    }
+}
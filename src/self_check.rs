@@ -0,0 +1,229 @@
+use semver::Version;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    process,
+    sync::mpsc,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::npm_cmd::NPM;
+
+pub const PACKAGE_NAME: &str = env!("CARGO_PKG_NAME");
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// How long the end of the run waits for the check, if it's still going
+pub const REPORT_GRACE: Duration = Duration::from_millis(500);
+
+/// A newer npm-bumpall than the one running
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewerRelease {
+    pub latest: String,
+    /// Days since the first release after the running version
+    pub days_available: i64,
+}
+
+/// Days since the unix epoch for the date part of a timestamp, e.g. "2023-05-01T12:00:00.000Z"
+pub fn epoch_days(timestamp: &str) -> Option<i64> {
+    let mut parts = timestamp.get(..10)?.split('-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // counting years from March, so the leap day falls at the end
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    Some(era * 146097 + day_of_era - 719468)
+}
+
+/// The day each version was published, from `npm view <name> time --json`. Entries which aren't
+/// a version (e.g. "created") and prereleases are left out
+pub fn parse_release_days(src: &str) -> Result<BTreeMap<Version, i64>, serde_json::Error> {
+    let times: Value = serde_json::from_str(src)?;
+    let mut releases = BTreeMap::new();
+
+    if let Some(times) = times.as_object() {
+        for (version, time) in times.iter() {
+            let version = match Version::parse(version) {
+                Ok(version) if version.pre.is_empty() => version,
+                _ => continue,
+            };
+
+            if let Some(day) = time.as_str().and_then(epoch_days) {
+                releases.insert(version, day);
+            }
+        }
+    }
+
+    Ok(releases)
+}
+
+/// Days since the unix epoch, for comparing with release days
+pub fn today() -> Result<i64, Box<dyn Error>> {
+    Ok((SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() / SECONDS_PER_DAY) as i64)
+}
+
+/// The latest release, when a newer version than `current` has been out for at least
+/// `max_age_days`
+pub fn stale_release(
+    current: &str,
+    releases: &BTreeMap<Version, i64>,
+    today: i64,
+    max_age_days: u64,
+) -> Option<NewerRelease> {
+    let current = Version::parse(current).ok()?;
+    let (latest, _) = releases.iter().next_back()?;
+    let (_, first_newer) = releases.iter().find(|(version, _)| **version > current)?;
+    let days_available = today - first_newer;
+
+    if days_available < max_age_days as i64 {
+        return None;
+    }
+
+    Some(NewerRelease {
+        latest: latest.to_string(),
+        days_available,
+    })
+}
+
+fn check(max_age_days: u64) -> Result<Option<NewerRelease>, Box<dyn Error>> {
+    let output = process::Command::new(NPM)
+        .arg("view")
+        .arg(PACKAGE_NAME)
+        .arg("time")
+        .arg("--json")
+        .stderr(process::Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(format!("Unable to fetch the releases of {}", PACKAGE_NAME).into());
+    }
+
+    let releases = parse_release_days(&String::from_utf8(output.stdout)?)?;
+
+    Ok(stale_release(VERSION, &releases, today()?, max_age_days))
+}
+
+/// Checks the registry in the background, so the run isn't held up by it. Nothing is sent if
+/// the check fails, e.g. when offline
+pub fn spawn(max_age_days: u64) -> mpsc::Receiver<NewerRelease> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        if let Ok(Some(release)) = check(max_age_days) {
+            let _ = sender.send(release);
+        }
+    });
+
+    receiver
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod epoch_days_tests {
+    use super::*;
+
+    #[test]
+    fn counts_from_the_epoch() {
+        assert_eq!(epoch_days("1970-01-01T00:00:00.000Z"), Some(0));
+        assert_eq!(epoch_days("1970-03-01T00:00:00.000Z"), Some(59));
+        assert_eq!(epoch_days("2000-03-01T12:30:00.000Z"), Some(11017));
+        assert_eq!(epoch_days("2024-02-29T00:00:00.000Z"), Some(19782));
+        assert_eq!(epoch_days("2024-03-01"), Some(19783));
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        assert_eq!(epoch_days("modified"), None);
+        assert_eq!(epoch_days("2024-13-01T00:00:00.000Z"), None);
+        assert_eq!(epoch_days(""), None);
+    }
+}
+
+#[cfg(test)]
+mod stale_release_tests {
+    use super::*;
+
+    const TIMES: &str = r#"{
+        "created": "2023-01-01T00:00:00.000Z",
+        "modified": "2023-06-01T00:00:00.000Z",
+        "0.1.0": "2023-01-01T00:00:00.000Z",
+        "0.2.0": "2023-03-01T00:00:00.000Z",
+        "0.3.0-beta.1": "2023-04-01T00:00:00.000Z",
+        "0.2.1": "2023-05-01T00:00:00.000Z"
+    }"#;
+
+    fn releases() -> BTreeMap<Version, i64> {
+        parse_release_days(TIMES).unwrap()
+    }
+
+    #[test]
+    fn skips_non_versions_and_prereleases() {
+        let versions: Vec<String> = releases().keys().map(|v| v.to_string()).collect();
+
+        assert_eq!(versions, vec!["0.1.0", "0.2.0", "0.2.1"]);
+    }
+
+    #[test]
+    fn warns_once_past_the_max_age() {
+        let today = epoch_days("2023-03-31").unwrap();
+
+        assert_eq!(
+            stale_release("0.1.0", &releases(), today, 30),
+            Some(NewerRelease {
+                latest: String::from("0.2.1"),
+                days_available: 30,
+            })
+        );
+    }
+
+    #[test]
+    fn quiet_within_the_max_age() {
+        let today = epoch_days("2023-03-30").unwrap();
+
+        assert_eq!(stale_release("0.1.0", &releases(), today, 30), None);
+    }
+
+    #[test]
+    fn quiet_when_up_to_date() {
+        let today = epoch_days("2024-01-01").unwrap();
+
+        assert_eq!(stale_release("0.2.1", &releases(), today, 0), None);
+        // e.g. a local build ahead of the registry
+        assert_eq!(stale_release("0.4.0", &releases(), today, 0), None);
+    }
+
+    #[test]
+    fn counts_from_the_first_newer_release() {
+        let today = epoch_days("2023-05-11").unwrap();
+
+        assert_eq!(
+            stale_release("0.2.0", &releases(), today, 7),
+            Some(NewerRelease {
+                latest: String::from("0.2.1"),
+                days_available: 10,
+            })
+        );
+    }
+
+    #[test]
+    fn quiet_on_invalid_input() {
+        assert_eq!(stale_release("not-a-version", &releases(), 0, 0), None);
+        assert_eq!(stale_release("0.1.0", &BTreeMap::new(), 0, 0), None);
+        assert!(parse_release_days("not json").is_err());
+    }
+}
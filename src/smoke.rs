@@ -0,0 +1,113 @@
+use std::{error::Error, process};
+
+use crate::deadline::Deadline;
+use crate::utility::Config;
+
+pub const NODE: &str = "node";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SmokeResult {
+    pub name: String,
+    pub success: bool,
+}
+
+/// Everything passed to `node` to load a package, with the name quoted as a JS string
+pub fn require_args(name: &str) -> Vec<String> {
+    // a JSON string is a valid JS string literal
+    let name = serde_json::Value::from(name).to_string();
+
+    vec![String::from("-e"), format!("require({})", name)]
+}
+
+/// Checks each package in turn, carrying on past failures so they're all reported
+pub fn check_all<'a, E>(
+    names: impl IntoIterator<Item = &'a str>,
+    mut check: impl FnMut(&str) -> Result<bool, E>,
+) -> Result<Vec<SmokeResult>, E> {
+    let mut results = vec![];
+
+    for name in names {
+        results.push(SmokeResult {
+            name: name.to_string(),
+            success: check(name)?,
+        });
+    }
+
+    Ok(results)
+}
+
+pub fn failed_names(results: &[SmokeResult]) -> Vec<&str> {
+    results
+        .iter()
+        .filter(|result| !result.success)
+        .map(|result| result.name.as_str())
+        .collect()
+}
+
+/// Loads the package with node, from the project's node_modules
+pub fn require(name: &str, config: &Config, deadline: &Deadline) -> Result<bool, Box<dyn Error>> {
+    deadline.check()?;
+
+    let mut child = process::Command::new(NODE)
+        .stdout(process::Stdio::null())
+        .stderr(config.stderr_method())
+        .args(require_args(name))
+        .spawn()?;
+
+    Ok(deadline.wait(&mut child)?.success())
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod require_args_tests {
+    use super::*;
+
+    #[test]
+    fn requires_the_package() {
+        assert_eq!(require_args("react"), vec!["-e", "require(\"react\")"]);
+        assert_eq!(
+            require_args("@jonshort/cenv"),
+            vec!["-e", "require(\"@jonshort/cenv\")"]
+        );
+    }
+
+    #[test]
+    fn escapes_quotes() {
+        assert_eq!(
+            require_args("a\"); process.exit(0); (\""),
+            vec!["-e", "require(\"a\\\"); process.exit(0); (\\\"\")"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod check_all_tests {
+    use super::*;
+
+    #[test]
+    fn collects_each_result() {
+        let mut checked = vec![];
+        let results = check_all(["react", "broken", "lodash"], |name| {
+            checked.push(name.to_string());
+            Ok::<bool, ()>(name != "broken")
+        })
+        .unwrap();
+
+        assert_eq!(checked, vec!["react", "broken", "lodash"]);
+        assert_eq!(failed_names(&results), vec!["broken"]);
+    }
+
+    #[test]
+    fn stops_on_errors() {
+        let result = check_all(["react", "lodash"], |name| {
+            if name == "react" {
+                Err("node not found")
+            } else {
+                Ok(true)
+            }
+        });
+
+        assert_eq!(result, Err("node not found"));
+    }
+}
@@ -0,0 +1,131 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// npm commands which can be run once the packages are bumped
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Step {
+    /// npm dedupe
+    Dedupe,
+    /// npm audit
+    Audit,
+    /// npm test
+    Test,
+}
+
+impl Step {
+    pub fn command(&self) -> &'static str {
+        match self {
+            Step::Dedupe => "dedupe",
+            Step::Audit => "audit",
+            Step::Test => "test",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepResult {
+    pub step: Step,
+    pub success: bool,
+}
+
+/// Runs each step in order. Unless `is_keep_going`, the first failure stops the remaining steps
+/// from running, so they're left out of the results.
+pub fn run_steps<E>(
+    steps: &[Step],
+    is_keep_going: bool,
+    mut run: impl FnMut(Step) -> Result<bool, E>,
+) -> Result<Vec<StepResult>, E> {
+    let mut results = vec![];
+
+    for step in steps.iter() {
+        let success = run(*step)?;
+        results.push(StepResult {
+            step: *step,
+            success,
+        });
+
+        if !success && !is_keep_going {
+            break;
+        }
+    }
+
+    Ok(results)
+}
+
+pub fn failed_commands(results: &[StepResult]) -> Vec<&'static str> {
+    results
+        .iter()
+        .filter(|result| !result.success)
+        .map(|result| result.step.command())
+        .collect()
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod run_steps_tests {
+    use super::*;
+
+    const STEPS: [Step; 3] = [Step::Dedupe, Step::Audit, Step::Test];
+
+    // audit is the step which fails
+    fn fake_run(ran: &mut Vec<Step>) -> impl FnMut(Step) -> Result<bool, ()> + '_ {
+        move |step| {
+            ran.push(step);
+            Ok(step != Step::Audit)
+        }
+    }
+
+    #[test]
+    fn fail_fast_stops_at_first_failure() {
+        let mut ran = vec![];
+        let results = run_steps(&STEPS, false, fake_run(&mut ran)).unwrap();
+
+        assert_eq!(ran, vec![Step::Dedupe, Step::Audit]);
+        assert_eq!(
+            results,
+            vec![
+                StepResult {
+                    step: Step::Dedupe,
+                    success: true
+                },
+                StepResult {
+                    step: Step::Audit,
+                    success: false
+                },
+            ]
+        );
+        assert_eq!(failed_commands(&results), vec!["audit"]);
+    }
+
+    #[test]
+    fn keep_going_runs_every_step() {
+        let mut ran = vec![];
+        let results = run_steps(&STEPS, true, fake_run(&mut ran)).unwrap();
+
+        assert_eq!(ran, STEPS.to_vec());
+        assert_eq!(results.len(), 3);
+        assert!(results[2].success);
+        assert_eq!(failed_commands(&results), vec!["audit"]);
+    }
+
+    #[test]
+    fn errors_stop_every_mode() {
+        let mut ran = vec![];
+        let result = run_steps(&STEPS, true, |step| {
+            ran.push(step);
+            Err("timed out")
+        });
+
+        assert_eq!(result, Err("timed out"));
+        assert_eq!(ran, vec![Step::Dedupe]);
+    }
+
+    #[test]
+    fn handles_no_steps() {
+        let results = run_steps(&[], false, |_| Ok::<bool, ()>(false)).unwrap();
+
+        assert!(results.is_empty());
+    }
+}
@@ -0,0 +1,365 @@
+// the selection model is only driven by the dashboard, which needs the `tui` feature
+#![cfg_attr(not(feature = "tui"), allow(dead_code))]
+
+use std::error::Error;
+#[cfg(feature = "tui")]
+use std::io::{self, IsTerminal};
+
+use crate::package::Package;
+use crate::utility::Config;
+
+/// What was chosen in the dashboard
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Bump the packages with these names
+    Bump(Vec<String>),
+    /// Leave without bumping anything
+    Cancel,
+}
+
+/// Which packages are ticked, where the cursor is, and the filter narrowing the list.
+/// The cursor is a position within the visible (filtered) rows
+#[derive(Debug, Default)]
+pub struct Selection {
+    names: Vec<String>,
+    selected: Vec<bool>,
+    cursor: usize,
+    filter: String,
+}
+
+impl Selection {
+    /// Every package starts ticked, matching what a plain run would bump
+    pub fn new(names: Vec<String>) -> Selection {
+        let selected = vec![true; names.len()];
+
+        Selection {
+            names,
+            selected,
+            ..Selection::default()
+        }
+    }
+
+    /// Indices of the rows matching the filter (case insensitive)
+    pub fn visible(&self) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+
+        (0..self.names.len())
+            .filter(|&i| self.names[i].to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    pub fn is_selected(&self, index: usize) -> bool {
+        self.selected.get(index).copied().unwrap_or(false)
+    }
+
+    pub fn move_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.cursor + 1 < self.visible().len() {
+            self.cursor += 1;
+        }
+    }
+
+    /// Ticks or unticks the row under the cursor
+    pub fn toggle(&mut self) {
+        if let Some(&index) = self.visible().get(self.cursor) {
+            self.selected[index] = !self.selected[index];
+        }
+    }
+
+    /// Unticks every visible row if they're all ticked, otherwise ticks them all
+    pub fn toggle_visible(&mut self) {
+        let visible = self.visible();
+        let tick = !visible.iter().all(|&i| self.selected[i]);
+
+        for index in visible {
+            self.selected[index] = tick;
+        }
+    }
+
+    pub fn push_filter(&mut self, c: char) {
+        self.filter.push(c);
+        self.clamp_cursor();
+    }
+
+    pub fn pop_filter(&mut self) {
+        self.filter.pop();
+        self.clamp_cursor();
+    }
+
+    /// Names of the ticked packages, including any hidden by the filter
+    pub fn selected_names(&self) -> Vec<String> {
+        self.names
+            .iter()
+            .zip(self.selected.iter())
+            .filter(|(_, &selected)| selected)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    fn clamp_cursor(&mut self) {
+        self.cursor = self.cursor.min(self.visible().len().saturating_sub(1));
+    }
+}
+
+/// Shows the outdated packages in a dashboard to pick which to bump.
+/// None when the dashboard can't be shown (not a terminal, or built without the `tui` feature)
+#[cfg(feature = "tui")]
+pub fn run(packages: &[Package], config: &Config) -> Result<Option<Outcome>, Box<dyn Error>> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Ok(None);
+    }
+
+    let mut selection = Selection::new(packages.iter().map(|pkg| pkg.name.clone()).collect());
+    let mut terminal = ratatui::init();
+    let outcome = dashboard::event_loop(&mut terminal, &mut selection, packages, config);
+    ratatui::restore();
+
+    Ok(Some(outcome?))
+}
+
+#[cfg(not(feature = "tui"))]
+pub fn run(_packages: &[Package], _config: &Config) -> Result<Option<Outcome>, Box<dyn Error>> {
+    Ok(None)
+}
+
+#[cfg(feature = "tui")]
+mod dashboard {
+    use ratatui::{
+        crossterm::event::{self, Event, KeyCode, KeyEventKind},
+        layout::{Constraint, Layout},
+        style::{Modifier, Style},
+        widgets::{Block, Cell, Paragraph, Row, Table, TableState},
+        DefaultTerminal, Frame,
+    };
+    use std::io;
+
+    use super::{Outcome, Selection};
+    use crate::package::{Package, UpgradeType};
+    use crate::utility::Config;
+
+    pub fn event_loop(
+        terminal: &mut DefaultTerminal,
+        selection: &mut Selection,
+        packages: &[Package],
+        config: &Config,
+    ) -> io::Result<Outcome> {
+        let mut is_filtering = false;
+
+        loop {
+            terminal.draw(|frame| draw(frame, selection, packages, config, is_filtering))?;
+
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+
+            if is_filtering {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Esc => is_filtering = false,
+                    KeyCode::Backspace => selection.pop_filter(),
+                    KeyCode::Char(c) => selection.push_filter(c),
+                    _ => {}
+                }
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Up | KeyCode::Char('k') => selection.move_up(),
+                KeyCode::Down | KeyCode::Char('j') => selection.move_down(),
+                KeyCode::Char(' ') => selection.toggle(),
+                KeyCode::Char('a') => selection.toggle_visible(),
+                KeyCode::Char('/') => is_filtering = true,
+                KeyCode::Enter => return Ok(Outcome::Bump(selection.selected_names())),
+                KeyCode::Esc | KeyCode::Char('q') => return Ok(Outcome::Cancel),
+                _ => {}
+            }
+        }
+    }
+
+    fn draw(
+        frame: &mut Frame,
+        selection: &Selection,
+        packages: &[Package],
+        config: &Config,
+        is_filtering: bool,
+    ) {
+        let [table_area, footer_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+        let rows: Vec<Row> = selection
+            .visible()
+            .into_iter()
+            .map(|index| {
+                let pkg = &packages[index];
+                let tick = if selection.is_selected(index) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                let kind = match pkg.upgrade_type {
+                    UpgradeType::Safe => "safe",
+                    UpgradeType::Major => "major",
+                };
+
+                Row::new(vec![
+                    Cell::from(tick),
+                    Cell::from(pkg.name.as_str()),
+                    Cell::from(pkg.current_version.as_str()),
+                    Cell::from(pkg.upgrade_version(&config.upgrade_style)),
+                    Cell::from(kind),
+                ])
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(16),
+                Constraint::Length(16),
+                Constraint::Length(5),
+            ],
+        )
+        .header(
+            Row::new(vec!["", "Package", "Current", "Target", "Type"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::bordered().title(format!(
+            " Outdated packages ({} selected) ",
+            selection.selected_names().len()
+        )));
+
+        let mut state = TableState::default().with_selected(Some(selection.cursor()));
+        frame.render_stateful_widget(table, table_area, &mut state);
+
+        let footer = if is_filtering {
+            format!("filter: {}_  (enter to finish)", selection.filter())
+        } else {
+            format!(
+                "space toggle  a toggle all  / filter [{}]  enter bump  q quit",
+                selection.filter()
+            )
+        };
+        frame.render_widget(Paragraph::new(footer), footer_area);
+    }
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod selection_tests {
+    use super::*;
+
+    fn selection() -> Selection {
+        Selection::new(vec![
+            String::from("@babel/core"),
+            String::from("@babel/preset-env"),
+            String::from("debug"),
+            String::from("polished"),
+        ])
+    }
+
+    #[test]
+    fn starts_with_everything_selected() {
+        let selection = selection();
+
+        assert_eq!(selection.selected_names().len(), 4);
+        assert_eq!(selection.visible(), vec![0, 1, 2, 3]);
+        assert_eq!(selection.cursor(), 0);
+    }
+
+    #[test]
+    fn cursor_stays_in_bounds() {
+        let mut selection = selection();
+        selection.move_up();
+        assert_eq!(selection.cursor(), 0);
+
+        for _ in 0..10 {
+            selection.move_down();
+        }
+        assert_eq!(selection.cursor(), 3);
+    }
+
+    #[test]
+    fn toggles_row_under_cursor() {
+        let mut selection = selection();
+        selection.move_down();
+        selection.toggle();
+
+        assert!(!selection.is_selected(1));
+        assert_eq!(
+            selection.selected_names(),
+            vec!["@babel/core", "debug", "polished"]
+        );
+
+        selection.toggle();
+        assert!(selection.is_selected(1));
+    }
+
+    #[test]
+    fn filters_case_insensitively() {
+        let mut selection = selection();
+        selection.move_down();
+        selection.move_down();
+        selection.move_down();
+        "BABEL".chars().for_each(|c| selection.push_filter(c));
+
+        assert_eq!(selection.visible(), vec![0, 1]);
+        // clamped back onto the visible rows
+        assert_eq!(selection.cursor(), 1);
+
+        selection.toggle();
+        assert!(!selection.is_selected(1));
+
+        for _ in 0..5 {
+            selection.pop_filter();
+        }
+        assert_eq!(selection.visible().len(), 4);
+    }
+
+    #[test]
+    fn toggle_all_only_affects_visible_rows() {
+        let mut selection = selection();
+        "babel".chars().for_each(|c| selection.push_filter(c));
+
+        selection.toggle_visible();
+        assert_eq!(selection.selected_names(), vec!["debug", "polished"]);
+
+        selection.toggle_visible();
+        assert_eq!(selection.selected_names().len(), 4);
+    }
+
+    #[test]
+    fn toggle_all_ticks_when_partly_selected() {
+        let mut selection = selection();
+        selection.toggle();
+        selection.toggle_visible();
+
+        assert_eq!(selection.selected_names().len(), 4);
+    }
+
+    #[test]
+    fn empty_filter_result() {
+        let mut selection = selection();
+        "zzz".chars().for_each(|c| selection.push_filter(c));
+
+        assert!(selection.visible().is_empty());
+        assert_eq!(selection.cursor(), 0);
+        // nothing under the cursor to toggle
+        selection.toggle();
+        assert_eq!(selection.selected_names().len(), 4);
+    }
+}
@@ -1,12 +1,25 @@
-use clap::Parser;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use glob::Pattern;
-use std::any::type_name;
+use handlebars::{Template, TemplateError};
+use regex::Regex;
+use serde::Serialize;
 use std::env::current_dir;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::time::Duration;
+use std::{error::Error, fmt, fs, fs::File, io::Write};
+
+use crate::emojis::{Icon, Theme};
+use crate::format::OutputFormat;
+use crate::install::InstallOrder;
+use crate::manifest::DependencyType;
+use crate::retry::BackoffStrategy;
+use crate::steps::Step;
 
 /// Utility to bump npm packages, by default to the latest minor version.
-#[derive(Parser, Debug, Default)]
-#[command(author, version, about, long_about = None)]
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None, args_override_self = true)]
 pub struct Args {
     ///Bump dependencies to latest possible version (includes major changes)
     #[arg(short, long)]
@@ -16,14 +29,42 @@ pub struct Args {
     ///Update to latest patch version only (experimental)
     pub patch: bool,
 
+    #[arg(long)]
+    ///Keep package.json.bkup after a patch mode run, rather than removing it
+    pub keep_backup: bool,
+
     #[arg(long)]
     ///Apply --legacy-peer-deps to npm install
     pub legacy_peer_deps: bool,
 
+    #[arg(long)]
+    ///Apply --prefer-dedupe to npm install, so it reuses already installed versions where it can
+    pub prefer_dedupe: bool,
+
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(1..=3))]
+    ///Lockfile version npm install writes, e.g. 3
+    pub lockfile_version: Option<u8>,
+
+    #[arg(long, conflicts_with = "lockfile_version")]
+    ///Apply --no-package-lock to npm install, so no lockfile is written (e.g. for libraries)
+    pub no_lockfile: bool,
+
+    #[arg(long, value_enum)]
+    ///Range prefix npm install saves bumped versions with
+    pub save_prefix: Option<SavePrefix>,
+
+    #[arg(long, value_enum, requires = "patch")]
+    ///Range prefix patch mode rewrites package.json with before checking, e.g. ^ for minor updates [default: ~]
+    pub range_style: Option<SavePrefix>,
+
     #[arg(short, long)]
     ///Include all possible messages in console output (e.g. warnings from npm itself)
     pub verbose: bool,
 
+    #[arg(long)]
+    ///Print nothing at all when there are no outdated packages, e.g. for cron jobs
+    pub quiet_success: bool,
+
     #[arg(short, long)]
     ///Update outdated dependencies
     pub update: bool,
@@ -31,99 +72,1030 @@ pub struct Args {
     #[arg(short, long)]
     ///Only bumps packages which match the glob pattern provided
     pub include: Option<String>,
+
+    #[arg(long, alias = "only-name-matches", value_name = "PATTERN", value_parser = parse_name_regex)]
+    ///Only bumps packages whose name matches the regular expression provided, e.g. "^@babel/"
+    pub name_regex: Option<String>,
+
+    #[arg(long, value_enum, default_value_t)]
+    ///Format used when listing the updates
+    pub format: OutputFormat,
+
+    #[arg(long)]
+    ///List the major updates after all the others, keeping the existing order within each group
+    pub group_majors_last: bool,
+
+    #[arg(long)]
+    ///Order the major updates by their npm downloads over the last week, most popular first (alphabetical when the counts can't be fetched)
+    pub sort_majors_by_popularity: bool,
+
+    #[arg(long)]
+    ///Only list the major updates, e.g. for a review, while still bumping everything
+    pub report_only_major: bool,
+
+    #[arg(long)]
+    ///Also list the skipped packages, with why each was skipped, in the report and the JSON plan
+    pub report_include_skipped: bool,
+
+    #[arg(long)]
+    ///Keep the order npm reported the packages in, rather than sorting them by name
+    pub no_sort: bool,
+
+    #[arg(long, value_parser = parse_template, conflicts_with = "format")]
+    ///Handlebars template each update is listed with, e.g. "{{name}}: {{current}} -> {{latest}} ({{type}})"
+    pub template: Option<String>,
+
+    #[arg(long, value_parser = parse_install_args, allow_hyphen_values = true)]
+    ///Additional arguments passed through to npm install, e.g. "--no-audit --omit=optional"
+    pub install_args: Option<String>,
+
+    #[arg(long)]
+    ///Also report peer dependency conflicts found by npm ls, bumping them when updating
+    pub include_peer_outdated: bool,
+
+    #[arg(long, value_parser = clap::value_parser!(i32).range(3..))]
+    ///Write the plan as JSON to the given (already open) file descriptor, e.g. 3
+    pub plan_fd: Option<i32>,
+
+    #[arg(long)]
+    ///Skip packages whose versions from npm aren't valid semver (e.g. MISSING), with a warning
+    pub strict_semver: bool,
+
+    #[arg(long)]
+    ///Stop before doing anything if package-lock.json has unresolved git merge conflicts
+    pub abort_on_lockfile_conflict: bool,
+
+    #[arg(long, default_value_t = 0)]
+    ///Retry a failed npm install this many times
+    pub retries: u32,
+
+    #[arg(long, value_enum, default_value_t)]
+    ///How the wait between install retries grows
+    pub backoff_strategy: BackoffStrategy,
+
+    #[arg(long, default_value_t = 1000)]
+    ///Base wait between install retries, in milliseconds
+    pub backoff_base_ms: u64,
+
+    #[arg(long, visible_alias = "concurrency-metadata", default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..))]
+    ///How many packages to fetch registry metadata for at once (installs always run one at a time)
+    pub parallelism: u32,
+
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    ///Most registry metadata fetches to run at once against the same registry
+    pub max_concurrency_per_registry: Option<u32>,
+
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    ///Give up on a single registry metadata fetch after this many milliseconds, leaving out its details
+    pub registry_timeout: Option<u64>,
+
+    #[arg(long)]
+    ///Don't verify the registry's TLS certificate when fetching metadata (insecure, prefer --cacert)
+    pub insecure: bool,
+
+    #[arg(long, value_name = "PATH")]
+    ///CA certificate (PEM) to trust when fetching metadata, e.g. for a TLS-intercepting proxy
+    pub cacert: Option<PathBuf>,
+
+    #[arg(long)]
+    ///When npm install fails on peer dependency conflicts, retry including the peers it needs
+    pub resolve_peer_conflicts: bool,
+
+    #[arg(long)]
+    ///Install each package with its own npm install, so one failure doesn't block the rest
+    pub isolated: bool,
+
+    #[arg(long, requires = "isolated", conflicts_with_all = ["keep_range_width", "fallback_to_wanted_on_major_fail"])]
+    ///With --isolated, git commit package.json and package-lock.json after each package installs, one commit per bump
+    pub commit_per_package: bool,
+
+    #[arg(long, value_parser = parse_template, requires = "commit_per_package")]
+    ///Handlebars template for each --commit-per-package message, with the same fields as --template [default: "Bump {{name}} from {{current}} to {{target}}"]
+    pub commit_message: Option<String>,
+
+    #[arg(long)]
+    ///With --latest, retry any package whose major upgrade fails at its wanted version instead
+    pub fallback_to_wanted_on_major_fail: bool,
+
+    #[arg(long)]
+    ///Don't use colors in the output
+    pub no_color: bool,
+
+    #[arg(long)]
+    ///Exit with an error if any package can be updated within its declared range, without updating
+    pub frozen: bool,
+
+    #[arg(long)]
+    ///Show a desktop notification when finished (requires the desktop-notify feature)
+    pub notify: bool,
+
+    #[arg(long, value_enum)]
+    ///Print the completion script for the given shell and exit
+    pub shell_completion: Option<Shell>,
+
+    #[arg(long, value_name = "SECONDS")]
+    ///Give up (killing npm and exiting with code 124) if the whole run takes longer than this
+    pub max_runtime: Option<u64>,
+
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    ///Only install when at least this many packages would be bumped, e.g. for scheduled jobs
+    pub min_bumps: Option<u32>,
+
+    #[arg(long, value_enum)]
+    ///Order the packages are passed to npm install in, which can matter for peer resolution [default: the listed order]
+    pub install_order: Option<InstallOrder>,
+
+    #[arg(long, value_name = "SEED", num_args = 0..=1, hide = true, conflicts_with = "install_order")]
+    ///Shuffle the install order, with a random seed unless one is given, to surface installs which depend on the order
+    pub randomize_order: Option<Option<u64>>,
+
+    #[arg(long, value_name = "DAYS")]
+    ///Warn at the end of the run if a newer npm-bumpall has been out for at least this many days (checks the npm registry)
+    pub max_age_warn: Option<u64>,
+
+    #[arg(long, value_name = "DAYS")]
+    ///Exit with an error, without bumping, if any dependency's installed version was published more than this many days ago (checks the npm registry)
+    pub max_outdated_age_fail: Option<u64>,
+
+    #[arg(long, conflicts_with_all = ["latest", "widen"])]
+    ///Bump packages with only in-range updates, and just list the ones with a new major available
+    pub wanted_then_report_latest: bool,
+
+    #[arg(long, conflicts_with_all = ["patch", "isolated"])]
+    ///Rewrite the ranges in package.json to a caret range of the latest version (e.g. ^1.0.0 -> ^2.0.0), then install
+    pub widen: bool,
+
+    #[arg(long, conflicts_with = "widen")]
+    ///After bumping, put back each declared range's original ^, ~ or exact operator in package.json
+    pub keep_range_width: bool,
+
+    #[arg(long)]
+    ///For each major update, show the version jump and any changelog/homepage/issues links from npm
+    pub explain_major: bool,
+
+    #[arg(short, long)]
+    ///Also run in every workspace listed in package.json (or pnpm-workspace.yaml)
+    pub recursive: bool,
+
+    #[arg(long, requires = "recursive")]
+    ///With --recursive, skip the workspaces which have no outdated packages
+    pub skip_unchanged_workspaces: bool,
+
+    #[arg(long)]
+    ///Print the resolved configuration as JSON and exit
+    pub print_config: bool,
+
+    #[arg(long, conflicts_with_all = ["widen", "isolated", "tui"])]
+    ///Print the npm install command which would bump the packages, to run yourself, instead of running it
+    pub print_install_cmd_only: bool,
+
+    #[arg(long, value_name = "NAME")]
+    ///Apply the named profile from .bumpallrc.json, before any other options passed
+    pub profile: Option<String>,
+
+    #[arg(long)]
+    ///Only bump packages which fix a vulnerability reported by npm audit
+    pub only_security: bool,
+
+    #[arg(long)]
+    ///List the packages depended on by the most workspaces first
+    pub order_by_dependents: bool,
+
+    #[arg(long, value_enum, default_value_t)]
+    ///Markers used in the output
+    pub theme: Theme,
+
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "TYPE")]
+    ///Leave dependencies of this type alone, can be repeated, e.g. --exclude-type dev
+    pub exclude_type: Vec<DependencyType>,
+
+    #[arg(long, conflicts_with = "dev_dependencies_only")]
+    ///Only bump dependencies (not devDependencies), installing with --omit=dev
+    pub dependencies_only: bool,
+
+    #[arg(long)]
+    ///Only bump devDependencies, installing with --include=dev
+    pub dev_dependencies_only: bool,
+
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "STEP")]
+    ///npm commands to run (in order) once the packages are bumped, e.g. --then dedupe,test
+    pub then: Vec<Step>,
+
+    #[arg(long, overrides_with = "keep_going")]
+    ///Stop at the first failing step (default)
+    pub fail_fast: bool,
+
+    #[arg(long, overrides_with = "fail_fast")]
+    ///Run every step even if an earlier one (or the install) failed, then report all failures
+    pub keep_going: bool,
+
+    #[arg(long, value_name = "DIR")]
+    ///Write generated artifacts (e.g. the plan as plan.json) into this directory, creating it if needed
+    pub output_dir: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH")]
+    ///Also write a JSON summary of the run (counts and bumped packages) to this file
+    pub summary_json: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["recursive", "all_projects"])]
+    ///Manifest patch mode rewrites instead of package.json, with npm run from its directory
+    pub manifest: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH")]
+    ///JSON file of approved version ranges by package name, packages are only bumped within them
+    pub allowlist_file: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH")]
+    ///Compare the plan with one saved earlier (e.g. with --output-dir), listing what changed
+    pub report_diff_against: Option<PathBuf>,
+
+    #[arg(long)]
+    ///Pretty-print JSON output (the plan with --format json, --plan-fd or --output-dir), rather than one compact line
+    pub json_pretty: bool,
+
+    #[arg(long)]
+    ///Exit with an error, without bumping, if any package's target version is deprecated (checks the npm registry)
+    pub fail_on_deprecated: bool,
+
+    #[arg(long)]
+    ///Skip packages whose only updates are within their current major, e.g. with --latest for major migrations
+    pub exclude_current_major: bool,
+
+    #[arg(long, value_name = "N")]
+    ///Skip major upgrades which jump more than this many major versions at once, e.g. 2 to skip 2.x -> 6.x
+    pub exclude_majors_over: Option<u64>,
+
+    #[arg(long)]
+    ///Bump packages already at the version they'd be bumped to, so package.json gets that exact version
+    pub ignore_range_mismatch: bool,
+
+    #[arg(long, value_name = "LABEL")]
+    ///Prefix every line of output with this label, e.g. to tell apart the logs of parallel runs
+    pub batch_label: Option<String>,
+
+    #[arg(long)]
+    ///When there's no package.json here, run in every project found in the directories below
+    pub all_projects: bool,
+
+    #[arg(long)]
+    ///Reuse the last outdated check while package.json and the lockfile are unchanged
+    pub cache_outdated: bool,
+
+    #[arg(long)]
+    ///Pick the packages to bump from a dashboard (needs the tui feature and a terminal)
+    pub tui: bool,
+
+    #[arg(long)]
+    ///After bumping, check again for outdated packages and report any which weren't bumped
+    pub verify: bool,
+
+    #[arg(long)]
+    ///After bumping, check package-lock.json has a well-formed sha512 integrity hash for each bumped package
+    pub verify_checksums: bool,
+
+    #[arg(long)]
+    ///After bumping, check each bumped package can still be loaded with node's require()
+    pub smoke_test: bool,
+
+    #[arg(long, conflicts_with_all = ["update", "tui", "recursive", "all_projects"])]
+    ///Check again each time package.json changes, without installing (needs the watch feature)
+    pub watch: bool,
+
+    #[arg(long, value_name = "REF")]
+    ///List the dependencies whose range or installed version changed since the given git ref, then exit
+    pub compare_with: Option<String>,
+
+    #[arg(long, value_name = "TAG")]
+    ///Only bump dependencies added or changed in package.json since the given git tag
+    pub since_tag: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Maintenance commands, run instead of checking for updates
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Subcommand)]
+pub enum Command {
+    /// Run npm dedupe to flatten the dependency tree, without bumping anything
+    Dedupe,
+}
+
+impl Default for Args {
+    // keeps the defaults in one place - the clap attributes above
+    fn default() -> Self {
+        Args::parse_from(["npm-bumpall"])
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenizeError;
+
+impl fmt::Display for TokenizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unterminated quote or escape")
+    }
+}
+
+impl Error for TokenizeError {}
+
+/// Splits a raw argument string the way a shell would, respecting quotes and backslash escapes
+pub fn tokenize_args(raw: &str) -> Result<Vec<String>, TokenizeError> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut chars = raw.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err(TokenizeError),
+                    }
+                }
+            }
+            '"' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err(TokenizeError),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(TokenizeError),
+                    }
+                }
+            }
+            '\\' => {
+                in_token = true;
+                match chars.next() {
+                    Some(c) => current.push(c),
+                    None => return Err(TokenizeError),
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                in_token = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_token {
+        tokens.push(current);
+    }
+
+    Ok(tokens)
+}
+
+/// Quotes an argument for a shell if needed, so `tokenize_args` would split it back out as is
+pub fn quote_arg(arg: &str) -> String {
+    let is_plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@/._-=:+^~,%".contains(c));
+
+    if is_plain {
+        String::from(arg)
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+fn parse_install_args(raw: &str) -> Result<String, TokenizeError> {
+    tokenize_args(raw)?;
+    Ok(raw.to_string())
+}
+
+fn parse_name_regex(raw: &str) -> Result<String, regex::Error> {
+    Regex::new(raw)?;
+    Ok(raw.to_string())
+}
+
+/// The message each package is committed with by --commit-per-package
+pub const DEFAULT_COMMIT_MESSAGE: &str = "Bump {{name}} from {{current}} to {{target}}";
+
+fn parse_template(raw: &str) -> Result<String, TemplateError> {
+    Template::compile(raw)?;
+    Ok(raw.to_string())
+}
+
+/// A compiled `--name-regex`, compared by its source pattern
+#[derive(Clone, Debug)]
+pub struct NameRegex(pub Regex);
+
+impl NameRegex {
+    pub fn is_match(&self, name: &str) -> bool {
+        self.0.is_match(name)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl PartialEq for NameRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+/// npm's `save-prefix`, the range written to package.json for installed versions
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ValueEnum)]
+pub enum SavePrefix {
+    /// Compatible with the version, e.g. ^1.2.3
+    #[value(name = "^")]
+    #[serde(rename = "^")]
+    Caret,
+    /// Patch updates only, e.g. ~1.2.3
+    #[value(name = "~")]
+    #[serde(rename = "~")]
+    Tilde,
+    /// The exact version, e.g. 1.2.3
+    #[value(name = "exact")]
+    #[serde(rename = "exact")]
+    Exact,
+}
+
+impl SavePrefix {
+    /// What goes in front of the version, e.g. `^`
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            SavePrefix::Caret => "^",
+            SavePrefix::Tilde => "~",
+            SavePrefix::Exact => "",
+        }
+    }
+
+    pub fn install_arg(&self) -> String {
+        format!("--save-prefix={}", self.prefix())
+    }
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum UpgradeStyle {
     Latest,
+    #[default]
     Wanted,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Config {
     pub additional_install_args: Vec<String>,
+    pub allowlist_file: Option<PathBuf>,
+    pub backoff_base: Duration,
+    pub backoff_strategy: BackoffStrategy,
+    pub batch_label: Option<String>,
+    pub cacert: Option<PathBuf>,
+    pub commit_message: Option<String>,
+    pub compare_with: Option<String>,
     pub current_dir_name: Option<String>,
+    pub exclude_majors_over: Option<u64>,
+    pub excluded_types: Vec<DependencyType>,
+    pub follow_up_steps: Vec<Step>,
+    pub github_output: Option<PathBuf>,
+    pub ignore_range_mismatch: bool,
     pub include_glob: Option<Pattern>,
+    pub include_peer_outdated: bool,
+    pub install_order: Option<InstallOrder>,
+    pub is_abort_on_lockfile_conflict: bool,
+    pub is_all_projects: bool,
+    pub is_cache_outdated: bool,
+    pub is_color_enabled: bool,
+    pub is_dependencies_only: bool,
+    pub is_dev_dependencies_only: bool,
     pub is_dry_run: bool,
+    pub is_exclude_current_major: bool,
+    pub is_explain_major: bool,
+    pub is_fail_on_deprecated: bool,
+    pub is_fallback_to_wanted: bool,
+    pub is_frozen: bool,
+    pub is_group_majors_last: bool,
+    pub is_insecure: bool,
+    pub is_isolated: bool,
+    pub is_json_pretty: bool,
+    pub is_keep_backup: bool,
+    pub is_keep_going: bool,
+    pub is_keep_range_width: bool,
+    pub is_no_lockfile: bool,
+    pub is_notify_enabled: bool,
+    pub is_only_security: bool,
+    pub is_order_by_dependents: bool,
     pub is_patch_mode: bool,
-    pub stderr_method: Stdio,
-    pub stdout_method: Stdio,
+    pub is_print_install_cmd_only: bool,
+    pub is_quiet_success: bool,
+    pub is_recursive: bool,
+    pub is_report_include_skipped: bool,
+    pub is_report_only_major: bool,
+    pub is_resolve_peer_conflicts: bool,
+    pub is_skip_unchanged_workspaces: bool,
+    pub is_smoke_test: bool,
+    pub is_sort_majors_by_popularity: bool,
+    pub is_sorted: bool,
+    pub is_strict_semver: bool,
+    pub is_tui: bool,
+    pub is_verbose: bool,
+    pub is_verify: bool,
+    pub is_verify_checksums: bool,
+    pub is_wanted_then_report_latest: bool,
+    pub is_watch: bool,
+    pub is_widen_mode: bool,
+    pub lockfile_version: Option<u8>,
+    pub manifest: Option<PathBuf>,
+    pub max_age_warn_days: Option<u64>,
+    pub max_concurrency_per_registry: Option<usize>,
+    pub max_outdated_age_fail_days: Option<u64>,
+    pub max_runtime: Option<Duration>,
+    pub min_bumps: Option<usize>,
+    pub name_regex: Option<NameRegex>,
+    pub output_dir: Option<PathBuf>,
+    pub output_format: OutputFormat,
+    pub parallelism: usize,
+    pub plan_fd: Option<i32>,
+    pub randomize_seed: Option<u64>,
+    pub range_style: Option<SavePrefix>,
+    pub registry_timeout: Option<Duration>,
+    pub report_diff_against: Option<PathBuf>,
+    pub retries: u32,
+    pub save_prefix: Option<SavePrefix>,
+    pub since_tag: Option<String>,
+    pub summary_json: Option<PathBuf>,
+    pub template: Option<String>,
+    pub theme: Theme,
     pub upgrade_style: UpgradeStyle,
+    pub workspace_dir: Option<PathBuf>,
 }
 
-fn print_type_of<T>(_: &T) -> &str {
-    type_name::<T>()
-}
-
-impl PartialEq for Config {
-    fn eq(&self, other: &Self) -> bool {
-        let a = self.additional_install_args == other.additional_install_args;
-        let cdr = self.current_dir_name == other.current_dir_name;
-        let dr = self.is_dry_run == other.is_dry_run;
-        let pm = self.is_patch_mode == other.is_patch_mode;
-        // This doesn't effectively check anything, but better than nothing
-        let e = print_type_of(&self.stderr_method) == print_type_of(&other.stderr_method);
-        let o = print_type_of(&self.stdout_method) == print_type_of(&other.stdout_method);
-        let u = self.upgrade_style == other.upgrade_style;
-        let i = self.include_glob == other.include_glob;
-
-        a && cdr && dr && pm && e && o && u && i
+impl Default for Config {
+    fn default() -> Self {
+        Self::new_from_args(Args::default())
     }
 }
 
 impl Config {
-    pub fn create_config() -> Config {
-        let args = Args::parse();
-        Self::new_from_args(args)
-    }
-
     pub fn new_from_args(args: Args) -> Config {
         let mut additional_install_args = vec![];
-        let mut stderr_method = Stdio::null();
-        let mut stdout_method = Stdio::null();
         let mut upgrade_style = UpgradeStyle::Wanted;
         let mut include_glob = None;
 
-        if args.latest {
+        if args.latest || args.widen {
             upgrade_style = UpgradeStyle::Latest;
         }
 
-        if args.verbose {
-            stdout_method = Stdio::inherit();
-            stderr_method = Stdio::inherit();
-        }
-
         if args.legacy_peer_deps {
             additional_install_args.push(String::from("--legacy-peer-deps"));
         }
 
+        if args.prefer_dedupe {
+            additional_install_args.push(String::from("--prefer-dedupe"));
+        }
+
+        if let Some(raw) = args.install_args {
+            additional_install_args.extend(tokenize_args(&raw).unwrap_or_default());
+        }
+
         if let Some(g) = args.include {
             if let Ok(ptn) = Pattern::new(&g) {
                 include_glob = Some(ptn);
             }
         }
 
-        let current_dir_name = match current_dir().unwrap_or_default().file_name() {
+        // so the plan only lists what the install is scoped to
+        let mut excluded_types = args.exclude_type;
+        let scope_exclusions: &[DependencyType] = if args.dependencies_only {
+            &[DependencyType::Dev]
+        } else if args.dev_dependencies_only {
+            &[
+                DependencyType::Prod,
+                DependencyType::Optional,
+                DependencyType::Peer,
+            ]
+        } else {
+            &[]
+        };
+
+        for dep_type in scope_exclusions.iter() {
+            if !excluded_types.contains(dep_type) {
+                excluded_types.push(*dep_type);
+            }
+        }
+
+        let cwd = current_dir().unwrap_or_default();
+        let manifest = args.manifest.map(|path| cwd.join(path));
+        // npm is run from the manifest's directory, so that's the one workspaces are told apart by
+        let project_dir = manifest
+            .as_deref()
+            .and_then(|path| path.parent())
+            .unwrap_or(&cwd);
+        let current_dir_name = match project_dir.file_name() {
             Some(d) => d.to_str().map(String::from),
             None => None,
         };
 
         Config {
             additional_install_args,
+            allowlist_file: args.allowlist_file.map(|path| cwd.join(path)),
+            backoff_base: Duration::from_millis(args.backoff_base_ms),
+            backoff_strategy: args.backoff_strategy,
+            batch_label: args.batch_label,
+            cacert: args.cacert.map(|path| cwd.join(path)),
+            commit_message: if args.commit_per_package {
+                Some(
+                    args.commit_message
+                        .unwrap_or_else(|| String::from(DEFAULT_COMMIT_MESSAGE)),
+                )
+            } else {
+                None
+            },
+            compare_with: args.compare_with,
             current_dir_name,
+            exclude_majors_over: args.exclude_majors_over,
+            excluded_types,
+            follow_up_steps: args.then,
+            // read from the environment by main, so tests never write to a real step output
+            github_output: None,
+            ignore_range_mismatch: args.ignore_range_mismatch,
             include_glob,
+            include_peer_outdated: args.include_peer_outdated,
+            install_order: args.install_order,
+            is_abort_on_lockfile_conflict: args.abort_on_lockfile_conflict,
+            is_all_projects: args.all_projects,
+            is_cache_outdated: args.cache_outdated,
+            is_color_enabled: !args.no_color,
+            is_dependencies_only: args.dependencies_only,
+            is_dev_dependencies_only: args.dev_dependencies_only,
             is_dry_run: !args.update,
+            is_exclude_current_major: args.exclude_current_major,
+            is_explain_major: args.explain_major,
+            is_fail_on_deprecated: args.fail_on_deprecated,
+            is_fallback_to_wanted: args.fallback_to_wanted_on_major_fail,
+            is_frozen: args.frozen,
+            is_group_majors_last: args.group_majors_last,
+            is_insecure: args.insecure,
+            is_isolated: args.isolated,
+            is_json_pretty: args.json_pretty,
+            is_keep_backup: args.keep_backup,
+            is_keep_going: args.keep_going,
+            is_keep_range_width: args.keep_range_width,
+            is_no_lockfile: args.no_lockfile,
+            is_notify_enabled: args.notify,
+            is_only_security: args.only_security,
+            is_order_by_dependents: args.order_by_dependents,
             is_patch_mode: args.patch,
-            stderr_method,
-            stdout_method,
+            is_print_install_cmd_only: args.print_install_cmd_only,
+            is_quiet_success: args.quiet_success,
+            is_recursive: args.recursive,
+            is_report_include_skipped: args.report_include_skipped,
+            is_report_only_major: args.report_only_major,
+            is_resolve_peer_conflicts: args.resolve_peer_conflicts,
+            is_skip_unchanged_workspaces: args.skip_unchanged_workspaces,
+            is_smoke_test: args.smoke_test,
+            is_sort_majors_by_popularity: args.sort_majors_by_popularity,
+            is_sorted: !args.no_sort,
+            is_strict_semver: args.strict_semver,
+            is_tui: args.tui,
+            is_verbose: args.verbose,
+            is_verify: args.verify,
+            is_verify_checksums: args.verify_checksums,
+            is_wanted_then_report_latest: args.wanted_then_report_latest,
+            is_watch: args.watch,
+            is_widen_mode: args.widen,
+            lockfile_version: args.lockfile_version,
+            manifest,
+            max_age_warn_days: args.max_age_warn,
+            max_concurrency_per_registry: args.max_concurrency_per_registry.map(|n| n as usize),
+            max_outdated_age_fail_days: args.max_outdated_age_fail,
+            max_runtime: args.max_runtime.map(Duration::from_secs),
+            min_bumps: args.min_bumps.map(|n| n as usize),
+            // already validated while parsing the args
+            name_regex: args
+                .name_regex
+                .and_then(|r| Regex::new(&r).ok())
+                .map(NameRegex),
+            // resolved now, as recursive runs change directory
+            output_dir: args.output_dir.map(|dir| cwd.join(dir)),
+            output_format: args.format,
+            parallelism: args.parallelism as usize,
+            plan_fd: args.plan_fd,
+            randomize_seed: args
+                .randomize_order
+                .map(|seed| seed.unwrap_or_else(|| fastrand::u64(..))),
+            range_style: args.range_style,
+            registry_timeout: args.registry_timeout.map(Duration::from_millis),
+            report_diff_against: args.report_diff_against.map(|path| cwd.join(path)),
+            retries: args.retries,
+            save_prefix: args.save_prefix,
+            since_tag: args.since_tag,
+            summary_json: args.summary_json.map(|path| cwd.join(path)),
+            template: args.template,
+            theme: args.theme,
             upgrade_style,
+            // set for each directory of a run across several
+            workspace_dir: None,
+        }
+    }
+
+    /// Where an artifact is written under --output-dir, kept apart per workspace when recursive
+    pub fn artifact_path(&self, name: &str) -> Option<PathBuf> {
+        let dir = self.output_dir.as_ref()?;
+
+        match &self.workspace_dir {
+            Some(workspace) if self.is_recursive => Some(dir.join(workspace).join(name)),
+            _ => Some(dir.join(name)),
+        }
+    }
+
+    pub fn stdout_method(&self) -> Stdio {
+        if self.is_verbose && self.output_format == OutputFormat::Json {
+            // kept off stdout, which is only the JSON
+            Stdio::from(std::io::stderr())
+        } else if self.is_verbose {
+            Stdio::inherit()
+        } else {
+            Stdio::null()
+        }
+    }
+
+    pub fn stderr_method(&self) -> Stdio {
+        if self.is_verbose {
+            Stdio::inherit()
+        } else {
+            Stdio::null()
+        }
+    }
+}
+
+/// Serializable view of the resolved `Config`, for --print-config
+#[derive(Debug, Serialize)]
+pub struct ConfigView<'a> {
+    pub additional_install_args: &'a [String],
+    pub allowlist_file: Option<&'a Path>,
+    pub backoff_base_ms: u64,
+    pub backoff_strategy: BackoffStrategy,
+    pub batch_label: Option<&'a str>,
+    pub cacert: Option<&'a Path>,
+    pub commit_message: Option<&'a str>,
+    pub compare_with: Option<&'a str>,
+    pub current_dir_name: Option<&'a str>,
+    pub exclude_majors_over: Option<u64>,
+    pub excluded_types: &'a [DependencyType],
+    pub follow_up_steps: &'a [Step],
+    pub github_output: Option<&'a Path>,
+    pub ignore_range_mismatch: bool,
+    pub include_glob: Option<&'a str>,
+    pub include_peer_outdated: bool,
+    pub install_order: Option<InstallOrder>,
+    pub is_abort_on_lockfile_conflict: bool,
+    pub is_all_projects: bool,
+    pub is_cache_outdated: bool,
+    pub is_color_enabled: bool,
+    pub is_dependencies_only: bool,
+    pub is_dev_dependencies_only: bool,
+    pub is_dry_run: bool,
+    pub is_exclude_current_major: bool,
+    pub is_explain_major: bool,
+    pub is_fail_on_deprecated: bool,
+    pub is_fallback_to_wanted: bool,
+    pub is_frozen: bool,
+    pub is_group_majors_last: bool,
+    pub is_insecure: bool,
+    pub is_isolated: bool,
+    pub is_json_pretty: bool,
+    pub is_keep_backup: bool,
+    pub is_keep_going: bool,
+    pub is_keep_range_width: bool,
+    pub is_no_lockfile: bool,
+    pub is_notify_enabled: bool,
+    pub is_only_security: bool,
+    pub is_order_by_dependents: bool,
+    pub is_patch_mode: bool,
+    pub is_print_install_cmd_only: bool,
+    pub is_quiet_success: bool,
+    pub is_recursive: bool,
+    pub is_report_include_skipped: bool,
+    pub is_report_only_major: bool,
+    pub is_resolve_peer_conflicts: bool,
+    pub is_skip_unchanged_workspaces: bool,
+    pub is_smoke_test: bool,
+    pub is_sort_majors_by_popularity: bool,
+    pub is_sorted: bool,
+    pub is_strict_semver: bool,
+    pub is_tui: bool,
+    pub is_verbose: bool,
+    pub is_verify: bool,
+    pub is_verify_checksums: bool,
+    pub is_wanted_then_report_latest: bool,
+    pub is_watch: bool,
+    pub is_widen_mode: bool,
+    pub lockfile_version: Option<u8>,
+    pub manifest: Option<&'a Path>,
+    pub max_age_warn_days: Option<u64>,
+    pub max_concurrency_per_registry: Option<usize>,
+    pub max_outdated_age_fail_days: Option<u64>,
+    pub max_runtime_secs: Option<u64>,
+    pub min_bumps: Option<usize>,
+    pub name_regex: Option<&'a str>,
+    pub output_dir: Option<&'a Path>,
+    pub output_format: OutputFormat,
+    pub parallelism: usize,
+    pub plan_fd: Option<i32>,
+    pub randomize_seed: Option<u64>,
+    pub range_style: Option<SavePrefix>,
+    pub registry_timeout_ms: Option<u64>,
+    pub report_diff_against: Option<&'a Path>,
+    pub retries: u32,
+    pub save_prefix: Option<SavePrefix>,
+    pub since_tag: Option<&'a str>,
+    pub summary_json: Option<&'a Path>,
+    pub template: Option<&'a str>,
+    pub theme: Theme,
+    pub upgrade_style: UpgradeStyle,
+    pub workspace_dir: Option<&'a Path>,
+}
+
+impl<'a> From<&'a Config> for ConfigView<'a> {
+    fn from(config: &'a Config) -> Self {
+        ConfigView {
+            additional_install_args: &config.additional_install_args,
+            allowlist_file: config.allowlist_file.as_deref(),
+            // always set from whole milliseconds
+            backoff_base_ms: config.backoff_base.as_millis() as u64,
+            backoff_strategy: config.backoff_strategy,
+            batch_label: config.batch_label.as_deref(),
+            cacert: config.cacert.as_deref(),
+            commit_message: config.commit_message.as_deref(),
+            compare_with: config.compare_with.as_deref(),
+            current_dir_name: config.current_dir_name.as_deref(),
+            exclude_majors_over: config.exclude_majors_over,
+            excluded_types: &config.excluded_types,
+            follow_up_steps: &config.follow_up_steps,
+            github_output: config.github_output.as_deref(),
+            ignore_range_mismatch: config.ignore_range_mismatch,
+            include_glob: config.include_glob.as_ref().map(|g| g.as_str()),
+            include_peer_outdated: config.include_peer_outdated,
+            install_order: config.install_order,
+            is_abort_on_lockfile_conflict: config.is_abort_on_lockfile_conflict,
+            is_all_projects: config.is_all_projects,
+            is_cache_outdated: config.is_cache_outdated,
+            is_color_enabled: config.is_color_enabled,
+            is_dependencies_only: config.is_dependencies_only,
+            is_dev_dependencies_only: config.is_dev_dependencies_only,
+            is_dry_run: config.is_dry_run,
+            is_exclude_current_major: config.is_exclude_current_major,
+            is_explain_major: config.is_explain_major,
+            is_fail_on_deprecated: config.is_fail_on_deprecated,
+            is_fallback_to_wanted: config.is_fallback_to_wanted,
+            is_frozen: config.is_frozen,
+            is_group_majors_last: config.is_group_majors_last,
+            is_insecure: config.is_insecure,
+            is_isolated: config.is_isolated,
+            is_json_pretty: config.is_json_pretty,
+            is_keep_backup: config.is_keep_backup,
+            is_keep_going: config.is_keep_going,
+            is_keep_range_width: config.is_keep_range_width,
+            is_no_lockfile: config.is_no_lockfile,
+            is_notify_enabled: config.is_notify_enabled,
+            is_only_security: config.is_only_security,
+            is_order_by_dependents: config.is_order_by_dependents,
+            is_patch_mode: config.is_patch_mode,
+            is_print_install_cmd_only: config.is_print_install_cmd_only,
+            is_quiet_success: config.is_quiet_success,
+            is_recursive: config.is_recursive,
+            is_report_include_skipped: config.is_report_include_skipped,
+            is_report_only_major: config.is_report_only_major,
+            is_resolve_peer_conflicts: config.is_resolve_peer_conflicts,
+            is_skip_unchanged_workspaces: config.is_skip_unchanged_workspaces,
+            is_smoke_test: config.is_smoke_test,
+            is_sort_majors_by_popularity: config.is_sort_majors_by_popularity,
+            is_sorted: config.is_sorted,
+            is_strict_semver: config.is_strict_semver,
+            is_tui: config.is_tui,
+            is_verbose: config.is_verbose,
+            is_verify: config.is_verify,
+            is_verify_checksums: config.is_verify_checksums,
+            is_wanted_then_report_latest: config.is_wanted_then_report_latest,
+            is_watch: config.is_watch,
+            is_widen_mode: config.is_widen_mode,
+            lockfile_version: config.lockfile_version,
+            manifest: config.manifest.as_deref(),
+            max_age_warn_days: config.max_age_warn_days,
+            max_concurrency_per_registry: config.max_concurrency_per_registry,
+            max_outdated_age_fail_days: config.max_outdated_age_fail_days,
+            max_runtime_secs: config.max_runtime.map(|d| d.as_secs()),
+            min_bumps: config.min_bumps,
+            name_regex: config.name_regex.as_ref().map(|r| r.as_str()),
+            output_dir: config.output_dir.as_deref(),
+            output_format: config.output_format,
+            parallelism: config.parallelism,
+            plan_fd: config.plan_fd,
+            randomize_seed: config.randomize_seed,
+            range_style: config.range_style,
+            // always set from whole milliseconds
+            registry_timeout_ms: config.registry_timeout.map(|d| d.as_millis() as u64),
+            report_diff_against: config.report_diff_against.as_deref(),
+            retries: config.retries,
+            save_prefix: config.save_prefix,
+            since_tag: config.since_tag.as_deref(),
+            summary_json: config.summary_json.as_deref(),
+            template: config.template.as_deref(),
+            theme: config.theme,
+            upgrade_style: config.upgrade_style,
+            workspace_dir: config.workspace_dir.as_deref(),
         }
     }
 }
 
-pub fn print_message(message: &str, emoji: &char) {
-    println!("{} {} {}", emoji, message, emoji);
-    println!();
+pub fn write_completion(shell: Shell, out: &mut dyn Write) {
+    clap_complete::generate(shell, &mut Args::command(), "npm-bumpall", out);
+}
+
+pub const PLAN_ARTIFACT: &str = "plan.json";
+
+pub const OUTCOME_ARTIFACT: &str = "outcome.json";
+
+pub fn write_artifact(path: &Path, contents: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+/// Writes to a file descriptor inherited from the parent process, leaving it open for the next
+/// run (e.g. each project with --recursive or --watch)
+#[cfg(unix)]
+pub fn write_to_fd(fd: i32, contents: &str) -> Result<(), Box<dyn Error>> {
+    use std::{mem::ManuallyDrop, os::unix::io::FromRawFd};
+
+    // from_raw_fd requires the descriptor to be open, so check before borrowing it
+    if std::fs::symlink_metadata(format!("/dev/fd/{}", fd)).is_err() {
+        return Err(format!("File descriptor {} is not open", fd).into());
+    }
+
+    // never dropped, as the descriptor belongs to the parent process rather than this file
+    let mut file = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+    file.write_all(contents.as_bytes())?;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn write_to_fd(_fd: i32, _contents: &str) -> Result<(), Box<dyn Error>> {
+    Err("Writing to a file descriptor is only supported on unix".into())
+}
+
+/// Prefixes every non-empty line with the `--batch-label`, if there is one
+pub fn label_lines(text: &str, config: &Config) -> String {
+    let label = match &config.batch_label {
+        Some(label) => label,
+        None => return text.to_string(),
+    };
+
+    text.split_inclusive('\n')
+        .map(|line| {
+            if line.trim().is_empty() {
+                line.to_string()
+            } else {
+                format!("[{}] {}", label, line)
+            }
+        })
+        .collect()
+}
+
+/// Prints human-readable output, labelled with `label_lines`. With --format json it goes to
+/// stderr instead, so stdout is only the JSON and can be piped
+pub fn print_labelled(text: &str, config: &Config) {
+    let text = label_lines(text, config);
+
+    if config.output_format == OutputFormat::Json {
+        eprint!("{}", text);
+    } else {
+        print!("{}", text);
+    }
+}
+
+pub fn print_message(message: &str, icon: Icon, config: &Config) {
+    print_labelled(
+        &format!("{}\n\n", config.theme.decorate(message, icon)),
+        config,
+    );
 }
 
 // Tests --------------------------------------------------------------
@@ -138,10 +1110,19 @@ mod config_tests {
     #[test]
     #[parallel]
     fn verify_cli() {
-        use clap::CommandFactory;
         Args::command().debug_assert()
     }
 
+    #[test]
+    #[parallel]
+    fn parses_dedupe_command() {
+        let args = Args::parse_from(["npm-bumpall", "--verbose", "dedupe"]);
+
+        assert_eq!(args.command, Some(Command::Dedupe));
+        assert!(args.verbose);
+        assert_eq!(Args::default().command, None);
+    }
+
     #[test]
     #[parallel]
     fn default_on_no_args() {
@@ -149,13 +1130,88 @@ mod config_tests {
         let result = Config::new_from_args(args);
         let expected = Config {
             additional_install_args: vec![],
+            allowlist_file: None,
+            backoff_base: Duration::from_millis(1000),
+            backoff_strategy: BackoffStrategy::Exponential,
+            batch_label: None,
+            cacert: None,
+            commit_message: None,
+            compare_with: None,
             current_dir_name: Some(String::from("npm-bumpall")),
+            exclude_majors_over: None,
+            excluded_types: vec![],
+            follow_up_steps: vec![],
+            github_output: None,
+            ignore_range_mismatch: false,
             include_glob: None,
+            include_peer_outdated: false,
+            install_order: None,
+            is_abort_on_lockfile_conflict: false,
+            is_all_projects: false,
+            is_cache_outdated: false,
+            is_color_enabled: true,
+            is_dependencies_only: false,
+            is_dev_dependencies_only: false,
             is_dry_run: true,
+            is_exclude_current_major: false,
+            is_explain_major: false,
+            is_fail_on_deprecated: false,
+            is_fallback_to_wanted: false,
+            is_frozen: false,
+            is_group_majors_last: false,
+            is_insecure: false,
+            is_isolated: false,
+            is_json_pretty: false,
+            is_keep_backup: false,
+            is_keep_going: false,
+            is_keep_range_width: false,
+            is_no_lockfile: false,
+            is_notify_enabled: false,
+            is_only_security: false,
+            is_order_by_dependents: false,
             is_patch_mode: false,
-            stderr_method: Stdio::null(),
-            stdout_method: Stdio::null(),
+            is_print_install_cmd_only: false,
+            is_quiet_success: false,
+            is_recursive: false,
+            is_report_include_skipped: false,
+            is_report_only_major: false,
+            is_resolve_peer_conflicts: false,
+            is_skip_unchanged_workspaces: false,
+            is_smoke_test: false,
+            is_sort_majors_by_popularity: false,
+            is_sorted: true,
+            is_strict_semver: false,
+            is_tui: false,
+            is_verbose: false,
+            is_verify: false,
+            is_verify_checksums: false,
+            is_wanted_then_report_latest: false,
+            is_watch: false,
+            is_widen_mode: false,
+            lockfile_version: None,
+            manifest: None,
+            max_age_warn_days: None,
+            max_concurrency_per_registry: None,
+            max_outdated_age_fail_days: None,
+            max_runtime: None,
+            min_bumps: None,
+            name_regex: None,
+            output_dir: None,
+            output_format: OutputFormat::Text,
+            parallelism: 8,
+            plan_fd: None,
+            randomize_seed: None,
+            range_style: None,
+            registry_timeout: None,
+            report_diff_against: None,
+            retries: 0,
+            save_prefix: None,
+            since_tag: None,
+            summary_json: None,
+            template: None,
+            theme: Theme::Emoji,
             upgrade_style: UpgradeStyle::Wanted,
+            workspace_dir: None,
         };
         assert_eq!(result, expected)
     }
@@ -172,11 +1228,15 @@ mod config_tests {
             additional_install_args: vec![],
             current_dir_name: Some(String::from("npm-bumpall")),
             include_glob: None,
+            include_peer_outdated: false,
             is_dry_run: true,
             is_patch_mode: false,
-            stderr_method: Stdio::null(),
-            stdout_method: Stdio::null(),
+            is_strict_semver: false,
+            is_verbose: false,
+            output_format: OutputFormat::Text,
+            plan_fd: None,
             upgrade_style: UpgradeStyle::Latest,
+            ..Config::default()
         };
         assert_eq!(result_a, expected);
     }
@@ -193,16 +1253,19 @@ mod config_tests {
             additional_install_args: vec![String::from("--legacy-peer-deps")],
             current_dir_name: Some(String::from("npm-bumpall")),
             include_glob: None,
+            include_peer_outdated: false,
             is_dry_run: true,
             is_patch_mode: false,
-            stderr_method: Stdio::null(),
-            stdout_method: Stdio::null(),
+            is_strict_semver: false,
+            is_verbose: false,
+            output_format: OutputFormat::Text,
+            plan_fd: None,
             upgrade_style: UpgradeStyle::Wanted,
+            ..Config::default()
         };
         assert_eq!(result_a, expected);
     }
 
-    // doesn't actually check the value at the moment, but have left it here for completeness
     #[test]
     #[parallel]
     fn handles_verbose_arg() {
@@ -215,11 +1278,15 @@ mod config_tests {
             additional_install_args: vec![],
             current_dir_name: Some(String::from("npm-bumpall")),
             include_glob: None,
+            include_peer_outdated: false,
             is_dry_run: true,
             is_patch_mode: false,
-            stderr_method: Stdio::inherit(),
-            stdout_method: Stdio::inherit(),
+            is_strict_semver: false,
+            is_verbose: true,
+            output_format: OutputFormat::Text,
+            plan_fd: None,
             upgrade_style: UpgradeStyle::Wanted,
+            ..Config::default()
         };
         assert_eq!(result_a, expected);
     }
@@ -236,11 +1303,15 @@ mod config_tests {
             additional_install_args: vec![],
             current_dir_name: Some(String::from("npm-bumpall")),
             include_glob: None,
+            include_peer_outdated: false,
             is_dry_run: false,
             is_patch_mode: false,
-            stderr_method: Stdio::null(),
-            stdout_method: Stdio::null(),
+            is_strict_semver: false,
+            is_verbose: false,
+            output_format: OutputFormat::Text,
+            plan_fd: None,
             upgrade_style: UpgradeStyle::Wanted,
+            ..Config::default()
         };
         assert_eq!(result_a, expected);
     }
@@ -256,11 +1327,15 @@ mod config_tests {
             additional_install_args: vec![],
             current_dir_name: Some(String::from("test_files")),
             include_glob: None,
+            include_peer_outdated: false,
             is_dry_run: true,
             is_patch_mode: false,
-            stderr_method: Stdio::null(),
-            stdout_method: Stdio::null(),
+            is_strict_semver: false,
+            is_verbose: false,
+            output_format: OutputFormat::Text,
+            plan_fd: None,
             upgrade_style: UpgradeStyle::Wanted,
+            ..Config::default()
         };
 
         assert_eq!(result, expected);
@@ -279,11 +1354,15 @@ mod config_tests {
             additional_install_args: vec![],
             current_dir_name: Some(String::from("npm-bumpall")),
             include_glob: None,
+            include_peer_outdated: false,
             is_dry_run: true,
             is_patch_mode: true,
-            stderr_method: Stdio::null(),
-            stdout_method: Stdio::null(),
+            is_strict_semver: false,
+            is_verbose: false,
+            output_format: OutputFormat::Text,
+            plan_fd: None,
             upgrade_style: UpgradeStyle::Wanted,
+            ..Config::default()
         };
         assert_eq!(result_a, expected);
     }
@@ -300,37 +1379,1876 @@ mod config_tests {
             additional_install_args: vec![],
             current_dir_name: Some(String::from("npm-bumpall")),
             include_glob: Some(Pattern::new("hello").unwrap()),
+            include_peer_outdated: false,
             is_dry_run: true,
             is_patch_mode: false,
-            stderr_method: Stdio::null(),
-            stdout_method: Stdio::null(),
+            is_strict_semver: false,
+            is_verbose: false,
+            output_format: OutputFormat::Text,
+            plan_fd: None,
             upgrade_style: UpgradeStyle::Wanted,
+            ..Config::default()
         };
         assert_eq!(result_a, expected);
     }
 
     #[test]
     #[parallel]
-    fn handles_combo_args() {
+    fn handles_format_arg() {
         let args_a = Args {
-            update: true,
-            include: Some(String::from(".*")),
-            latest: true,
-            legacy_peer_deps: true,
-            patch: true,
-            verbose: true,
+            format: OutputFormat::Diff,
+            ..Args::default()
         };
         let result_a = Config::new_from_args(args_a);
         let expected = Config {
-            additional_install_args: vec![String::from("--legacy-peer-deps")],
+            additional_install_args: vec![],
             current_dir_name: Some(String::from("npm-bumpall")),
-            include_glob: Some(Pattern::new(".*").unwrap()),
-            is_dry_run: false,
-            is_patch_mode: true,
-            stderr_method: Stdio::inherit(),
-            stdout_method: Stdio::inherit(),
-            upgrade_style: UpgradeStyle::Latest,
+            include_glob: None,
+            include_peer_outdated: false,
+            is_dry_run: true,
+            is_patch_mode: false,
+            is_strict_semver: false,
+            is_verbose: false,
+            output_format: OutputFormat::Diff,
+            plan_fd: None,
+            upgrade_style: UpgradeStyle::Wanted,
+            ..Config::default()
         };
         assert_eq!(result_a, expected);
     }
+
+    #[test]
+    #[parallel]
+    fn handles_install_args_arg() {
+        let args_a = Args {
+            install_args: Some(String::from("--no-audit --omit=optional")),
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            additional_install_args: vec![
+                String::from("--no-audit"),
+                String::from("--omit=optional"),
+            ],
+            current_dir_name: Some(String::from("npm-bumpall")),
+            include_glob: None,
+            include_peer_outdated: false,
+            is_dry_run: true,
+            is_patch_mode: false,
+            is_strict_semver: false,
+            is_verbose: false,
+            output_format: OutputFormat::Text,
+            plan_fd: None,
+            upgrade_style: UpgradeStyle::Wanted,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn rejects_unterminated_install_args() {
+        let result = Args::try_parse_from(["npm-bumpall", "--install-args", "--tag 'oops"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_include_peer_outdated_arg() {
+        let args_a = Args {
+            include_peer_outdated: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            additional_install_args: vec![],
+            current_dir_name: Some(String::from("npm-bumpall")),
+            include_glob: None,
+            include_peer_outdated: true,
+            is_dry_run: true,
+            is_patch_mode: false,
+            is_strict_semver: false,
+            is_verbose: false,
+            output_format: OutputFormat::Text,
+            plan_fd: None,
+            upgrade_style: UpgradeStyle::Wanted,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_plan_fd_arg() {
+        let args_a = Args::try_parse_from(["npm-bumpall", "--plan-fd", "3"]).unwrap();
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            additional_install_args: vec![],
+            current_dir_name: Some(String::from("npm-bumpall")),
+            include_glob: None,
+            include_peer_outdated: false,
+            is_dry_run: true,
+            is_patch_mode: false,
+            is_strict_semver: false,
+            is_verbose: false,
+            output_format: OutputFormat::Text,
+            plan_fd: Some(3),
+            upgrade_style: UpgradeStyle::Wanted,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn rejects_standard_streams_as_plan_fd() {
+        assert!(Args::try_parse_from(["npm-bumpall", "--plan-fd", "1"]).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_strict_semver_arg() {
+        let args_a = Args {
+            strict_semver: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            additional_install_args: vec![],
+            current_dir_name: Some(String::from("npm-bumpall")),
+            include_glob: None,
+            include_peer_outdated: false,
+            is_dry_run: true,
+            is_patch_mode: false,
+            is_strict_semver: true,
+            is_verbose: false,
+            output_format: OutputFormat::Text,
+            plan_fd: None,
+            upgrade_style: UpgradeStyle::Wanted,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_retry_args() {
+        let args_a = Args {
+            retries: 3,
+            backoff_strategy: BackoffStrategy::Fixed,
+            backoff_base_ms: 250,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            backoff_base: Duration::from_millis(250),
+            backoff_strategy: BackoffStrategy::Fixed,
+            current_dir_name: Some(String::from("npm-bumpall")),
+            retries: 3,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_fallback_to_wanted_on_major_fail_arg() {
+        let args_a = Args {
+            fallback_to_wanted_on_major_fail: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_fallback_to_wanted: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_keep_range_width_arg() {
+        let args_a = Args {
+            keep_range_width: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_keep_range_width: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        assert!(Args::try_parse_from(["npm-bumpall", "--keep-range-width", "--widen"]).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_abort_on_lockfile_conflict_arg() {
+        let args_a = Args {
+            abort_on_lockfile_conflict: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_abort_on_lockfile_conflict: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_isolated_arg() {
+        let args_a = Args {
+            isolated: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_isolated: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_commit_per_package_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--isolated", "--commit-per-package"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected_a = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_isolated: true,
+            commit_message: Some(String::from(DEFAULT_COMMIT_MESSAGE)),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected_a);
+
+        let args_b = Args::parse_from([
+            "npm-bumpall",
+            "--isolated",
+            "--commit-per-package",
+            "--commit-message",
+            "chore(deps): {{name}}@{{target}}",
+        ]);
+        let result_b = Config::new_from_args(args_b);
+        let expected_b = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_isolated: true,
+            commit_message: Some(String::from("chore(deps): {{name}}@{{target}}")),
+            ..Config::default()
+        };
+        assert_eq!(result_b, expected_b);
+    }
+
+    #[test]
+    #[parallel]
+    fn commit_per_package_requires_isolated() {
+        let result = Args::try_parse_from(["npm-bumpall", "--commit-per-package"]);
+        assert!(result.is_err());
+
+        let result = Args::try_parse_from(["npm-bumpall", "--commit-message", "{{name}}"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_no_color_arg() {
+        let args_a = Args {
+            no_color: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_color_enabled: false,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_frozen_arg() {
+        let args_a = Args {
+            frozen: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_frozen: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_notify_arg() {
+        let args_a = Args {
+            notify: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_notify_enabled: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_max_age_warn_arg() {
+        let args_a = Args {
+            max_age_warn: Some(30),
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            max_age_warn_days: Some(30),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_registry_timeout_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--registry-timeout", "750"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            registry_timeout: Some(Duration::from_millis(750)),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        assert!(Args::try_parse_from(["npm-bumpall", "--registry-timeout", "0"]).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_max_runtime_arg() {
+        let args_a = Args {
+            max_runtime: Some(300),
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            max_runtime: Some(Duration::from_secs(300)),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_widen_arg() {
+        let args_a = Args {
+            widen: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_widen_mode: true,
+            upgrade_style: UpgradeStyle::Latest,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_explain_major_arg() {
+        let args_a = Args {
+            explain_major: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_explain_major: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_recursive_arg() {
+        let args_a = Args {
+            recursive: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_recursive: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_only_security_arg() {
+        let args_a = Args {
+            only_security: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_only_security: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_theme_arg() {
+        let args_a = Args {
+            theme: Theme::Minimal,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            theme: Theme::Minimal,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_then_arg() {
+        let result_a = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--then",
+            "dedupe,audit",
+            "--then",
+            "test",
+        ]));
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            follow_up_steps: vec![Step::Dedupe, Step::Audit, Step::Test],
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_keep_going_arg() {
+        let result_a = Config::new_from_args(Args::parse_from(["npm-bumpall", "--keep-going"]));
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_keep_going: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        // whichever comes last wins
+        let result_b = Config::new_from_args(Args::parse_from([
+            "npm-bumpall",
+            "--keep-going",
+            "--fail-fast",
+        ]));
+        assert!(!result_b.is_keep_going);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_output_dir_arg() {
+        let args_a = Args {
+            output_dir: Some(PathBuf::from("artifacts")),
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            output_dir: Some(env::current_dir().unwrap().join("artifacts")),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_ignore_range_mismatch_arg() {
+        let args_a = Args {
+            ignore_range_mismatch: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            ignore_range_mismatch: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_since_tag_arg() {
+        let args_a = Args {
+            since_tag: Some(String::from("v2.3.0")),
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            since_tag: Some(String::from("v2.3.0")),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_parallelism_arg() {
+        let args_a = Args {
+            parallelism: 2,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            parallelism: 2,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_concurrency_metadata_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--concurrency-metadata", "2"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            parallelism: 2,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        let help = Args::command().render_help().to_string();
+        assert!(help.contains("concurrency-metadata"));
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_keep_backup_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--patch", "--keep-backup"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_keep_backup: true,
+            is_patch_mode: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_no_lockfile_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--no-lockfile"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_no_lockfile: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        let conflicting = ["npm-bumpall", "--no-lockfile", "--lockfile-version", "3"];
+        assert!(Args::try_parse_from(conflicting).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_prefer_dedupe_arg() {
+        let args_a = Args::parse_from([
+            "npm-bumpall",
+            "--install-args",
+            "--no-audit",
+            "--prefer-dedupe",
+            "--legacy-peer-deps",
+        ]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            additional_install_args: vec![
+                String::from("--legacy-peer-deps"),
+                String::from("--prefer-dedupe"),
+                String::from("--no-audit"),
+            ],
+            current_dir_name: Some(String::from("npm-bumpall")),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_exclude_type_arg() {
+        let args_a = Args::parse_from([
+            "npm-bumpall",
+            "--exclude-type",
+            "dev",
+            "--exclude-type",
+            "peer",
+        ]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            excluded_types: vec![DependencyType::Dev, DependencyType::Peer],
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_fail_on_deprecated_arg() {
+        let args_a = Args {
+            fail_on_deprecated: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_fail_on_deprecated: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_group_majors_last_arg() {
+        let args_a = Args {
+            group_majors_last: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_group_majors_last: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_sort_majors_by_popularity_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--sort-majors-by-popularity"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_sort_majors_by_popularity: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_report_include_skipped_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--report-include-skipped"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_report_include_skipped: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_json_pretty_arg() {
+        let args_a = Args {
+            json_pretty: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_json_pretty: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_smoke_test_arg() {
+        let args_a = Args {
+            smoke_test: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_smoke_test: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_verify_arg() {
+        let args_a = Args {
+            verify: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_verify: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_verify_checksums_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--verify-checksums"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_verify_checksums: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_template_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--template", "{{name}}@{{latest}}"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            template: Some(String::from("{{name}}@{{latest}}")),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn rejects_invalid_template() {
+        let result = Args::try_parse_from(["npm-bumpall", "--template", "{{#if type}}{{name}}"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_name_regex_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--name-regex", "^@babel/"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            name_regex: Some(NameRegex(Regex::new("^@babel/").unwrap())),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        let args_b = Args::parse_from(["npm-bumpall", "--only-name-matches", "^@babel/"]);
+        assert_eq!(Config::new_from_args(args_b), expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn rejects_invalid_name_regex() {
+        let err = Args::try_parse_from(["npm-bumpall", "--name-regex", "(unclosed"]).unwrap_err();
+
+        assert!(err.to_string().contains("--name-regex"));
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_lockfile_version_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--lockfile-version", "3"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            lockfile_version: Some(3),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        assert!(Args::try_parse_from(["npm-bumpall", "--lockfile-version", "4"]).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_tui_arg() {
+        let args_a = Args {
+            tui: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_tui: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_max_concurrency_per_registry_arg() {
+        let args_a = Args {
+            max_concurrency_per_registry: Some(2),
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            max_concurrency_per_registry: Some(2),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_batch_label_arg() {
+        let args_a = Args {
+            batch_label: Some(String::from("repo-a")),
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            batch_label: Some(String::from("repo-a")),
+            current_dir_name: Some(String::from("npm-bumpall")),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_resolve_peer_conflicts_arg() {
+        let args_a = Args {
+            resolve_peer_conflicts: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_resolve_peer_conflicts: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_save_prefix_arg() {
+        for (raw, prefix) in [
+            ("^", SavePrefix::Caret),
+            ("~", SavePrefix::Tilde),
+            ("exact", SavePrefix::Exact),
+        ] {
+            let args_a = Args::parse_from(["npm-bumpall", "--save-prefix", raw]);
+            let result_a = Config::new_from_args(args_a);
+            let expected = Config {
+                current_dir_name: Some(String::from("npm-bumpall")),
+                save_prefix: Some(prefix),
+                ..Config::default()
+            };
+            assert_eq!(result_a, expected);
+        }
+
+        assert!(Args::try_parse_from(["npm-bumpall", "--save-prefix", ">="]).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_range_style_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--patch", "--range-style", "^"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_patch_mode: true,
+            range_style: Some(SavePrefix::Caret),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        // only rewrites ranges in patch mode
+        assert!(Args::try_parse_from(["npm-bumpall", "--range-style", "exact"]).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_cache_outdated_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--cache-outdated"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_cache_outdated: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_exclude_majors_over_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--exclude-majors-over", "2"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            exclude_majors_over: Some(2),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_exclude_current_major_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--exclude-current-major"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_exclude_current_major: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_dependencies_only_arg() {
+        let args_a = Args::parse_from([
+            "npm-bumpall",
+            "--dependencies-only",
+            "--exclude-type",
+            "dev,peer",
+        ]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            excluded_types: vec![DependencyType::Dev, DependencyType::Peer],
+            is_dependencies_only: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        let conflicting = [
+            "npm-bumpall",
+            "--dependencies-only",
+            "--dev-dependencies-only",
+        ];
+        assert!(Args::try_parse_from(conflicting).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_dev_dependencies_only_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--dev-dependencies-only"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            excluded_types: vec![
+                DependencyType::Prod,
+                DependencyType::Optional,
+                DependencyType::Peer,
+            ],
+            is_dev_dependencies_only: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_allowlist_file_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--allowlist-file", "approved.json"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            allowlist_file: Some(env::current_dir().unwrap().join("approved.json")),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_report_diff_against_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--report-diff-against", "plan.json"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            report_diff_against: Some(env::current_dir().unwrap().join("plan.json")),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_manifest_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--manifest", "app/manifest.json"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("app")),
+            manifest: Some(env::current_dir().unwrap().join("app/manifest.json")),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        assert!(Args::try_parse_from(["npm-bumpall", "--manifest", "m.json", "-r"]).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_summary_json_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--summary-json", "summary.json"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            summary_json: Some(env::current_dir().unwrap().join("summary.json")),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_tls_args() {
+        let args_a = Args::parse_from(["npm-bumpall", "--insecure", "--cacert", "proxy.pem"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            cacert: Some(env::current_dir().unwrap().join("proxy.pem")),
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_insecure: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_print_install_cmd_only_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--print-install-cmd-only"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_print_install_cmd_only: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_skip_unchanged_workspaces_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "-r", "--skip-unchanged-workspaces"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_recursive: true,
+            is_skip_unchanged_workspaces: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        assert!(Args::try_parse_from(["npm-bumpall", "--skip-unchanged-workspaces"]).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_order_by_dependents_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--order-by-dependents"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_order_by_dependents: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_max_outdated_age_fail_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--max-outdated-age-fail", "730"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            max_outdated_age_fail_days: Some(730),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_min_bumps_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--min-bumps", "5"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            min_bumps: Some(5),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        assert!(Args::try_parse_from(["npm-bumpall", "--min-bumps", "0"]).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_install_order_arg() {
+        for (raw, order) in [
+            ("declared", InstallOrder::Declared),
+            ("alphabetical", InstallOrder::Alphabetical),
+            ("safe-first", InstallOrder::SafeFirst),
+        ] {
+            let args_a = Args::parse_from(["npm-bumpall", "--install-order", raw]);
+            let result_a = Config::new_from_args(args_a);
+            let expected = Config {
+                current_dir_name: Some(String::from("npm-bumpall")),
+                install_order: Some(order),
+                ..Config::default()
+            };
+            assert_eq!(result_a, expected);
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_randomize_order_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--randomize-order", "42"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            randomize_seed: Some(42),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        let args_b = Args::parse_from(["npm-bumpall", "--randomize-order"]);
+        let result_b = Config::new_from_args(args_b);
+        assert!(result_b.randomize_seed.is_some());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_report_only_major_arg() {
+        let args_a = Args {
+            report_only_major: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_report_only_major: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_no_sort_arg() {
+        let args_a = Args {
+            no_sort: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_sorted: false,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_quiet_success_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--quiet-success"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_quiet_success: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_watch_arg() {
+        let args_a = Args::parse_from(["npm-bumpall", "--watch"]);
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_watch: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        // never installs
+        assert!(Args::try_parse_from(["npm-bumpall", "--watch", "-u"]).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_all_projects_arg() {
+        let args_a = Args {
+            all_projects: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_all_projects: true,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_compare_with_arg() {
+        let args_a = Args {
+            compare_with: Some(String::from("origin/main")),
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            compare_with: Some(String::from("origin/main")),
+            current_dir_name: Some(String::from("npm-bumpall")),
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_wanted_then_report_latest_arg() {
+        let args_a = Args {
+            wanted_then_report_latest: true,
+            ..Args::default()
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            current_dir_name: Some(String::from("npm-bumpall")),
+            is_wanted_then_report_latest: true,
+            upgrade_style: UpgradeStyle::Wanted,
+            ..Config::default()
+        };
+        assert_eq!(result_a, expected);
+
+        assert!(
+            Args::try_parse_from(["npm-bumpall", "--wanted-then-report-latest", "--latest"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn handles_combo_args() {
+        let args_a = Args {
+            update: true,
+            include: Some(String::from(".*")),
+            name_regex: Some(String::from("^(polished|debug)$")),
+            latest: true,
+            legacy_peer_deps: true,
+            prefer_dedupe: true,
+            lockfile_version: Some(3),
+            no_lockfile: false,
+            range_style: Some(SavePrefix::Caret),
+            save_prefix: Some(SavePrefix::Exact),
+            patch: true,
+            verbose: true,
+            quiet_success: true,
+            format: OutputFormat::Diff,
+            template: Some(String::from("{{name}}@{{latest}}")),
+            install_args: Some(String::from("--no-audit")),
+            include_peer_outdated: true,
+            plan_fd: Some(3),
+            strict_semver: true,
+            abort_on_lockfile_conflict: true,
+            retries: 2,
+            backoff_strategy: BackoffStrategy::Linear,
+            backoff_base_ms: 10,
+            parallelism: 4,
+            max_concurrency_per_registry: Some(2),
+            registry_timeout: Some(1500),
+            insecure: false,
+            cacert: None,
+            resolve_peer_conflicts: true,
+            isolated: true,
+            commit_per_package: false,
+            commit_message: None,
+            fallback_to_wanted_on_major_fail: true,
+            json_pretty: true,
+            no_color: true,
+            frozen: true,
+            group_majors_last: true,
+            sort_majors_by_popularity: true,
+            report_only_major: true,
+            report_include_skipped: true,
+            no_sort: true,
+            notify: true,
+            shell_completion: None,
+            max_runtime: Some(60),
+            min_bumps: Some(3),
+            randomize_order: None,
+            max_age_warn: Some(30),
+            max_outdated_age_fail: Some(365),
+            install_order: Some(InstallOrder::SafeFirst),
+            widen: false,
+            keep_range_width: true,
+            wanted_then_report_latest: false,
+            explain_major: true,
+            fail_on_deprecated: true,
+            recursive: true,
+            skip_unchanged_workspaces: false,
+            print_config: false,
+            print_install_cmd_only: false,
+            profile: None,
+            only_security: true,
+            order_by_dependents: false,
+            theme: Theme::Minimal,
+            then: vec![Step::Dedupe, Step::Test],
+            dependencies_only: false,
+            dev_dependencies_only: false,
+            exclude_type: vec![DependencyType::Dev, DependencyType::Optional],
+            fail_fast: false,
+            keep_backup: false,
+            keep_going: true,
+            output_dir: None,
+            exclude_current_major: false,
+            exclude_majors_over: Some(2),
+            ignore_range_mismatch: true,
+            since_tag: Some(String::from("v1.0.0")),
+            summary_json: None,
+            manifest: None,
+            allowlist_file: None,
+            report_diff_against: None,
+            batch_label: Some(String::from("repo-a")),
+            compare_with: Some(String::from("origin/main")),
+            all_projects: true,
+            cache_outdated: true,
+            tui: true,
+            verify: true,
+            verify_checksums: true,
+            smoke_test: true,
+            watch: false,
+            command: None,
+        };
+        let result_a = Config::new_from_args(args_a);
+        let expected = Config {
+            additional_install_args: vec![
+                String::from("--legacy-peer-deps"),
+                String::from("--prefer-dedupe"),
+                String::from("--no-audit"),
+            ],
+            allowlist_file: None,
+            backoff_base: Duration::from_millis(10),
+            backoff_strategy: BackoffStrategy::Linear,
+            batch_label: Some(String::from("repo-a")),
+            cacert: None,
+            commit_message: None,
+            compare_with: Some(String::from("origin/main")),
+            current_dir_name: Some(String::from("npm-bumpall")),
+            exclude_majors_over: Some(2),
+            excluded_types: vec![DependencyType::Dev, DependencyType::Optional],
+            follow_up_steps: vec![Step::Dedupe, Step::Test],
+            github_output: None,
+            ignore_range_mismatch: true,
+            include_glob: Some(Pattern::new(".*").unwrap()),
+            include_peer_outdated: true,
+            install_order: Some(InstallOrder::SafeFirst),
+            is_abort_on_lockfile_conflict: true,
+            is_all_projects: true,
+            is_cache_outdated: true,
+            is_color_enabled: false,
+            is_dependencies_only: false,
+            is_dev_dependencies_only: false,
+            is_dry_run: false,
+            is_exclude_current_major: false,
+            is_explain_major: true,
+            is_fail_on_deprecated: true,
+            is_fallback_to_wanted: true,
+            is_frozen: true,
+            is_group_majors_last: true,
+            is_insecure: false,
+            is_isolated: true,
+            is_json_pretty: true,
+            is_keep_backup: false,
+            is_keep_going: true,
+            is_keep_range_width: true,
+            is_no_lockfile: false,
+            is_notify_enabled: true,
+            is_only_security: true,
+            is_order_by_dependents: false,
+            is_patch_mode: true,
+            is_print_install_cmd_only: false,
+            is_quiet_success: true,
+            is_recursive: true,
+            is_report_include_skipped: true,
+            is_report_only_major: true,
+            is_resolve_peer_conflicts: true,
+            is_skip_unchanged_workspaces: false,
+            is_smoke_test: true,
+            is_sort_majors_by_popularity: true,
+            is_sorted: false,
+            is_strict_semver: true,
+            is_tui: true,
+            is_verbose: true,
+            is_verify: true,
+            is_verify_checksums: true,
+            is_wanted_then_report_latest: false,
+            is_watch: false,
+            is_widen_mode: false,
+            lockfile_version: Some(3),
+            manifest: None,
+            max_age_warn_days: Some(30),
+            max_concurrency_per_registry: Some(2),
+            max_outdated_age_fail_days: Some(365),
+            max_runtime: Some(Duration::from_secs(60)),
+            min_bumps: Some(3),
+            name_regex: Some(NameRegex(Regex::new("^(polished|debug)$").unwrap())),
+            output_dir: None,
+            output_format: OutputFormat::Diff,
+            parallelism: 4,
+            plan_fd: Some(3),
+            randomize_seed: None,
+            range_style: Some(SavePrefix::Caret),
+            registry_timeout: Some(Duration::from_millis(1500)),
+            report_diff_against: None,
+            retries: 2,
+            save_prefix: Some(SavePrefix::Exact),
+            since_tag: Some(String::from("v1.0.0")),
+            summary_json: None,
+            template: Some(String::from("{{name}}@{{latest}}")),
+            theme: Theme::Minimal,
+            upgrade_style: UpgradeStyle::Latest,
+            workspace_dir: None,
+        };
+        assert_eq!(result_a, expected);
+    }
+}
+
+#[cfg(test)]
+mod config_view_tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn reflects_resolved_config() {
+        let config = Config::new_from_args(Args {
+            latest: true,
+            legacy_peer_deps: true,
+            install_args: Some(String::from("--no-audit")),
+            include: Some(String::from("@babel/*")),
+            backoff_strategy: BackoffStrategy::Fixed,
+            backoff_base_ms: 250,
+            retries: 3,
+            save_prefix: Some(SavePrefix::Tilde),
+            max_runtime: Some(90),
+            registry_timeout: Some(2000),
+            format: OutputFormat::Diff,
+            update: true,
+            ..Args::default()
+        });
+
+        let mut expected = serde_json::Map::new();
+        for part in [
+            json!({
+                "additional_install_args": ["--legacy-peer-deps", "--no-audit"],
+                "allowlist_file": null,
+                "backoff_base_ms": 250,
+                "backoff_strategy": "fixed",
+                "batch_label": null,
+                "cacert": null,
+                "commit_message": null,
+                "compare_with": null,
+                "current_dir_name": config.current_dir_name,
+                "exclude_majors_over": null,
+                "excluded_types": [],
+                "follow_up_steps": [],
+                "github_output": null,
+                "ignore_range_mismatch": false,
+                "include_glob": "@babel/*",
+                "include_peer_outdated": false,
+                "install_order": null,
+                "is_abort_on_lockfile_conflict": false,
+                "is_all_projects": false,
+                "is_cache_outdated": false,
+                "is_color_enabled": true,
+                "is_dependencies_only": false,
+                "is_dev_dependencies_only": false,
+                "is_dry_run": false,
+                "is_exclude_current_major": false,
+                "is_explain_major": false,
+                "is_fail_on_deprecated": false,
+                "is_fallback_to_wanted": false,
+            }),
+            json!({
+                "is_frozen": false,
+                "is_group_majors_last": false,
+                "is_insecure": false,
+                "is_isolated": false,
+                "is_json_pretty": false,
+                "is_keep_backup": false,
+                "is_keep_going": false,
+                "is_keep_range_width": false,
+                "is_no_lockfile": false,
+                "is_notify_enabled": false,
+                "is_only_security": false,
+                "is_order_by_dependents": false,
+                "is_patch_mode": false,
+                "is_print_install_cmd_only": false,
+                "is_quiet_success": false,
+                "is_recursive": false,
+                "is_report_include_skipped": false,
+                "is_report_only_major": false,
+                "is_resolve_peer_conflicts": false,
+                "is_skip_unchanged_workspaces": false,
+                "is_smoke_test": false,
+                "is_sort_majors_by_popularity": false,
+                "is_sorted": true,
+                "is_strict_semver": false,
+                "is_tui": false,
+                "is_verbose": false,
+                "is_verify": false,
+                "is_verify_checksums": false,
+            }),
+            json!({
+                "is_wanted_then_report_latest": false,
+                "is_watch": false,
+                "is_widen_mode": false,
+                "lockfile_version": null,
+                "manifest": null,
+                "max_age_warn_days": null,
+                "max_concurrency_per_registry": null,
+                "max_outdated_age_fail_days": null,
+                "max_runtime_secs": 90,
+                "min_bumps": null,
+                "name_regex": null,
+                "output_dir": null,
+                "output_format": "diff",
+                "parallelism": 8,
+                "plan_fd": null,
+                "randomize_seed": null,
+                "range_style": null,
+                "registry_timeout_ms": 2000,
+                "report_diff_against": null,
+                "retries": 3,
+                "save_prefix": "~",
+                "since_tag": null,
+                "summary_json": null,
+                "template": null,
+                "theme": "emoji",
+                "upgrade_style": "latest",
+                "workspace_dir": null,
+            }),
+        ] {
+            if let Value::Object(fields) = part {
+                expected.extend(fields);
+            }
+        }
+
+        assert_eq!(
+            serde_json::to_value(ConfigView::from(&config)).unwrap(),
+            Value::Object(expected)
+        );
+    }
+}
+
+#[cfg(test)]
+mod quote_arg_tests {
+    use super::*;
+
+    #[test]
+    fn leaves_plain_args() {
+        assert_eq!(quote_arg("@jonshort/cenv@^1.0.3"), "@jonshort/cenv@^1.0.3");
+        assert_eq!(quote_arg("--omit=dev"), "--omit=dev");
+    }
+
+    #[test]
+    fn quotes_for_the_shell() {
+        assert_eq!(quote_arg("react@>=17 <19"), "'react@>=17 <19'");
+        assert_eq!(quote_arg(""), "''");
+        assert_eq!(quote_arg("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn round_trips() {
+        let args = [
+            "react@>=17 <19",
+            "it's",
+            "--cafile=/my certs/ca.pem",
+            "debug@4",
+        ];
+        let line: Vec<String> = args.iter().map(|arg| quote_arg(arg)).collect();
+
+        assert_eq!(tokenize_args(&line.join(" ")).unwrap(), args);
+    }
+}
+
+#[cfg(test)]
+mod tokenize_args_tests {
+    use super::*;
+
+    fn strings(tokens: &[&str]) -> Vec<String> {
+        tokens.iter().map(|t| String::from(*t)).collect()
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(
+            tokenize_args("--no-audit   --omit=dev\t--foreground-scripts"),
+            Ok(strings(&[
+                "--no-audit",
+                "--omit=dev",
+                "--foreground-scripts"
+            ]))
+        );
+    }
+
+    #[test]
+    fn handles_empty() {
+        assert_eq!(tokenize_args(""), Ok(vec![]));
+        assert_eq!(tokenize_args("   "), Ok(vec![]));
+    }
+
+    #[test]
+    fn handles_quoted_segments() {
+        assert_eq!(
+            tokenize_args(r#"--message "hello world" --tag 'a b'"#),
+            Ok(strings(&["--message", "hello world", "--tag", "a b"]))
+        );
+        assert_eq!(
+            tokenize_args(r#"--registry="https://example.com/a b""#),
+            Ok(strings(&["--registry=https://example.com/a b"]))
+        );
+        assert_eq!(tokenize_args(r#"'' """#), Ok(strings(&["", ""])));
+    }
+
+    #[test]
+    fn handles_escaped_segments() {
+        assert_eq!(
+            tokenize_args(r#"hello\ world "say \"hi\"" 'no\escape' back\\slash"#),
+            Ok(strings(&[
+                "hello world",
+                "say \"hi\"",
+                "no\\escape",
+                "back\\slash"
+            ]))
+        );
+    }
+
+    #[test]
+    fn err_on_unterminated() {
+        assert_eq!(tokenize_args("'oops"), Err(TokenizeError));
+        assert_eq!(tokenize_args("\"oops"), Err(TokenizeError));
+        assert_eq!(tokenize_args("oops\\"), Err(TokenizeError));
+    }
+}
+
+#[cfg(test)]
+mod label_lines_tests {
+    use super::*;
+
+    fn config(label: Option<&str>) -> Config {
+        Config {
+            batch_label: label.map(String::from),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn prefixes_every_line() {
+        let output = label_lines(
+            "Updates required\n-> debug 3.2.6 -> 3.2.7\n\nAll packages bumped\n",
+            &config(Some("repo-a")),
+        );
+
+        assert_eq!(
+            output,
+            "[repo-a] Updates required\n[repo-a] -> debug 3.2.6 -> 3.2.7\n\n[repo-a] All packages bumped\n"
+        );
+        assert!(output
+            .lines()
+            .filter(|line| !line.is_empty())
+            .all(|line| line.starts_with("[repo-a] ")));
+    }
+
+    #[test]
+    fn prefixes_line_without_newline() {
+        assert_eq!(
+            label_lines("Checking for outdated packages...", &config(Some("ci"))),
+            "[ci] Checking for outdated packages..."
+        );
+    }
+
+    #[test]
+    fn unchanged_without_label() {
+        let text = "Updates required\n-> debug 3.2.6 -> 3.2.7\n";
+
+        assert_eq!(label_lines(text, &config(None)), text);
+    }
+}
+
+#[cfg(test)]
+mod write_artifact_tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn artifacts_land_in_output_dir() {
+        let output_dir =
+            env::temp_dir().join(format!("npm-bumpall-artifacts-{}", std::process::id()));
+        let config = Config {
+            output_dir: Some(output_dir.clone()),
+            ..Config::default()
+        };
+
+        let path = config.artifact_path(PLAN_ARTIFACT).unwrap();
+        write_artifact(&path, "{\"packages\":[]}").unwrap();
+
+        assert_eq!(path, output_dir.join("plan.json"));
+        assert_eq!(
+            fs::read_to_string(output_dir.join("plan.json")).unwrap(),
+            "{\"packages\":[]}"
+        );
+
+        fs::remove_dir_all(output_dir).unwrap();
+    }
+
+    #[test]
+    fn separates_workspaces_when_recursive() {
+        let config = Config {
+            current_dir_name: Some(String::from("web")),
+            is_recursive: true,
+            output_dir: Some(PathBuf::from("/tmp/artifacts")),
+            workspace_dir: Some(PathBuf::from("web")),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            config.artifact_path(PLAN_ARTIFACT),
+            Some(PathBuf::from("/tmp/artifacts/web/plan.json"))
+        );
+    }
+
+    #[test]
+    fn separates_workspaces_sharing_a_name() {
+        let workspace = |path: &str| Config {
+            current_dir_name: Some(String::from("utils")),
+            is_recursive: true,
+            output_dir: Some(PathBuf::from("/tmp/artifacts")),
+            workspace_dir: Some(PathBuf::from(path)),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            workspace("packages/a/utils").artifact_path(PLAN_ARTIFACT),
+            Some(PathBuf::from("/tmp/artifacts/packages/a/utils/plan.json"))
+        );
+        assert_eq!(
+            workspace("apps/utils").artifact_path(PLAN_ARTIFACT),
+            Some(PathBuf::from("/tmp/artifacts/apps/utils/plan.json"))
+        );
+    }
+
+    #[test]
+    fn no_path_without_output_dir() {
+        assert_eq!(Config::default().artifact_path(PLAN_ARTIFACT), None);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod write_to_fd_tests {
+    use super::*;
+    use std::io::Read;
+    use std::os::unix::io::AsRawFd;
+
+    #[test]
+    fn writes_to_pipe() {
+        let (mut reader, writer) = std::io::pipe().unwrap();
+
+        write_to_fd(writer.as_raw_fd(), "{\"packages\":[]}").unwrap();
+        drop(writer);
+
+        let mut output = String::new();
+        reader.read_to_string(&mut output).unwrap();
+        assert_eq!(output, "{\"packages\":[]}");
+    }
+
+    #[test]
+    fn leaves_fd_open_for_another_write() {
+        let (mut reader, writer) = std::io::pipe().unwrap();
+
+        write_to_fd(writer.as_raw_fd(), "{\"packages\":[]}\n").unwrap();
+        write_to_fd(writer.as_raw_fd(), "{\"packages\":[]}\n").unwrap();
+        drop(writer);
+
+        let mut output = String::new();
+        reader.read_to_string(&mut output).unwrap();
+        assert_eq!(output, "{\"packages\":[]}\n{\"packages\":[]}\n");
+    }
+
+    #[test]
+    fn err_on_closed_fd() {
+        assert!(write_to_fd(987, "hello").is_err());
+    }
+}
+
+#[cfg(test)]
+mod write_completion_tests {
+    use super::*;
+
+    #[test]
+    fn generates_for_each_shell() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let mut out = vec![];
+            write_completion(shell, &mut out);
+
+            let script = String::from_utf8(out).unwrap();
+            assert!(script.contains("npm-bumpall"), "{:?}", shell);
+            assert!(script.contains("legacy-peer-deps"), "{:?}", shell);
+        }
+    }
+
+    #[test]
+    fn parses_shell_arg() {
+        let args = Args::try_parse_from(["npm-bumpall", "--shell-completion", "zsh"]).unwrap();
+        assert_eq!(args.shell_completion, Some(Shell::Zsh));
+
+        assert!(Args::try_parse_from(["npm-bumpall", "--shell-completion", "cmd"]).is_err());
+    }
 }
@@ -0,0 +1,406 @@
+use semver::{Version, VersionReq};
+use std::{fmt, ops::AddAssign};
+
+pub fn is_valid(version: &str) -> bool {
+    Version::parse(version).is_ok()
+}
+
+// The range of versions npm treats as compatible with `version`, e.g. 4.x or 0.3.x
+fn compatible_line(version: &Version) -> String {
+    match (version.major, version.minor) {
+        (0, 0) => format!("0.0.{}", version.patch),
+        (0, minor) => format!("0.{}.x", minor),
+        (major, _) => format!("{}.x", major),
+    }
+}
+
+/// e.g. "4.x -> 5.x" for 4.2.1 and 5.0.0, or None if either version isn't valid semver
+pub fn major_jump(from: &str, to: &str) -> Option<String> {
+    let from = Version::parse(from).ok()?;
+    let to = Version::parse(to).ok()?;
+
+    Some(format!(
+        "{} -> {}",
+        compatible_line(&from),
+        compatible_line(&to)
+    ))
+}
+
+/// Whether `to` is outside the compatible line of `from` (so 0.3.x -> 0.4.x counts, as it does
+/// for npm), or None if either version isn't valid semver
+pub fn crosses_major(from: &str, to: &str) -> Option<bool> {
+    let from = Version::parse(from).ok()?;
+    let to = Version::parse(to).ok()?;
+
+    Some(compatible_line(&from) != compatible_line(&to))
+}
+
+/// How far a set of bumps moved, counting each version line crossed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Distance {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Distance {
+    pub fn is_zero(&self) -> bool {
+        *self == Distance::default()
+    }
+}
+
+impl AddAssign for Distance {
+    fn add_assign(&mut self, other: Distance) {
+        self.major += other.major;
+        self.minor += other.minor;
+        self.patch += other.patch;
+    }
+}
+
+impl fmt::Display for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} major, {} minor, {} patch versions",
+            self.major, self.minor, self.patch
+        )
+    }
+}
+
+/// The distance between two versions, only counting the most significant part which changed,
+/// e.g. 1.2.3 -> 3.0.1 is 2 major. None if either isn't valid semver, or it's a downgrade
+pub fn distance(from: &str, to: &str) -> Option<Distance> {
+    let from = Version::parse(from).ok()?;
+    let to = Version::parse(to).ok()?;
+
+    if to < from {
+        return None;
+    }
+
+    let distance = if to.major != from.major {
+        Distance {
+            major: to.major - from.major,
+            ..Distance::default()
+        }
+    } else if to.minor != from.minor {
+        Distance {
+            minor: to.minor - from.minor,
+            ..Distance::default()
+        }
+    } else {
+        Distance {
+            patch: to.patch - from.patch,
+            ..Distance::default()
+        }
+    };
+
+    Some(distance)
+}
+
+/// Sums the distance of every `(from, to)` bump, ignoring any which can't be measured
+pub fn total_distance<'a>(bumps: impl IntoIterator<Item = (&'a str, &'a str)>) -> Distance {
+    let mut total = Distance::default();
+
+    for (from, to) in bumps {
+        if let Some(distance) = distance(from, to) {
+            total += distance;
+        }
+    }
+
+    total
+}
+
+/// How a target version relates to the range declared for it in package.json
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangeFit {
+    InRange,
+    /// Bumping to it widens the declared range
+    OutOfRange,
+}
+
+impl fmt::Display for RangeFit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RangeFit::InRange => write!(f, "in range"),
+            RangeFit::OutOfRange => write!(f, "out of range"),
+        }
+    }
+}
+
+/// An npm range, e.g. "^1.2.0" or ">=1.0.0 <2 || 3.x", read as the semver requirements it's
+/// satisfied by any one of
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NpmRange {
+    alternatives: Vec<VersionReq>,
+}
+
+impl NpmRange {
+    pub fn parse(range: &str) -> Result<NpmRange, semver::Error> {
+        let alternatives = range
+            .split("||")
+            .map(|alternative| VersionReq::parse(&npm_comparators(alternative)))
+            .collect::<Result<Vec<VersionReq>, semver::Error>>()?;
+
+        Ok(NpmRange { alternatives })
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        self.alternatives.iter().any(|req| req.matches(version))
+    }
+}
+
+/// Whether `version` satisfies the declared npm `range`, e.g. "^1.2.0" or ">=1.0.0 <2 || 3.x",
+/// or None if either can't be read (e.g. a git url, or a MISSING version)
+pub fn range_fit(range: &str, version: &str) -> Option<RangeFit> {
+    let version = Version::parse(version).ok()?;
+    let range = NpmRange::parse(range).ok()?;
+
+    if range.matches(&version) {
+        Some(RangeFit::InRange)
+    } else {
+        Some(RangeFit::OutOfRange)
+    }
+}
+
+// npm separates comparators with spaces where semver takes commas, e.g. ">= 1.0.0 <2" is
+// ">=1.0.0, <2", and a hyphen range "1.0.0 - 2.0.0" is ">=1.0.0, <=2.0.0"
+fn npm_comparators(range: &str) -> String {
+    if let Some((from, to)) = range.split_once(" - ") {
+        return format!(">={}, <={}", from.trim(), to.trim());
+    }
+
+    let mut comparators: Vec<String> = vec![];
+    let mut operator = String::new();
+
+    for token in range.split_whitespace() {
+        if token.chars().all(|c| "<>=~^".contains(c)) {
+            operator.push_str(token);
+        } else if operator.is_empty() && token.starts_with(|c: char| c.is_ascii_digit()) {
+            comparators.push(bare_comparator(token));
+        } else {
+            comparators.push(format!("{}{}", operator, token));
+            operator.clear();
+        }
+    }
+
+    if comparators.is_empty() {
+        String::from("*")
+    } else {
+        comparators.join(", ")
+    }
+}
+
+// semver reads a bare version as a caret range, where npm reads "1.0.3" as exactly that version
+// and "1.2" as 1.2.x
+fn bare_comparator(token: &str) -> String {
+    let is_wildcard = token.contains(['x', 'X', '*']);
+
+    match token.split('.').count() {
+        _ if is_wildcard => token.into(),
+        3 => format!("={}", token),
+        _ => format!("~{}", token),
+    }
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod is_valid_tests {
+    use super::*;
+
+    #[test]
+    fn accepts_semver() {
+        assert!(is_valid("1.2.3"));
+        assert!(is_valid("0.0.1"));
+        assert!(is_valid("2.0.0-beta.1"));
+        assert!(is_valid("1.0.0+build.5"));
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        assert!(!is_valid("MISSING"));
+        assert!(!is_valid("linked"));
+        assert!(!is_valid("1.2"));
+        assert!(!is_valid("v1.2.3"));
+        assert!(!is_valid(""));
+    }
+}
+
+#[cfg(test)]
+mod major_jump_tests {
+    use super::*;
+
+    #[test]
+    fn shows_major_lines() {
+        assert_eq!(
+            major_jump("4.2.1", "5.0.0"),
+            Some(String::from("4.x -> 5.x"))
+        );
+        assert_eq!(
+            major_jump("1.0.0", "3.1.4"),
+            Some(String::from("1.x -> 3.x"))
+        );
+        assert_eq!(
+            major_jump("2.0.0-beta.1", "2.0.0"),
+            Some(String::from("2.x -> 2.x"))
+        );
+    }
+
+    #[test]
+    fn treats_zero_versions_like_npm() {
+        assert_eq!(
+            major_jump("0.3.1", "0.4.0"),
+            Some(String::from("0.3.x -> 0.4.x"))
+        );
+        assert_eq!(
+            major_jump("0.0.3", "0.0.4"),
+            Some(String::from("0.0.3 -> 0.0.4"))
+        );
+        assert_eq!(
+            major_jump("0.9.0", "1.0.0"),
+            Some(String::from("0.9.x -> 1.x"))
+        );
+    }
+
+    #[test]
+    fn none_on_invalid_versions() {
+        assert_eq!(major_jump("MISSING", "1.0.0"), None);
+        assert_eq!(major_jump("1.0.0", "latest"), None);
+    }
+}
+
+#[cfg(test)]
+mod crosses_major_tests {
+    use super::*;
+
+    #[test]
+    fn compares_major_lines() {
+        assert_eq!(crosses_major("4.2.1", "5.0.0"), Some(true));
+        assert_eq!(crosses_major("4.2.1", "4.9.0"), Some(false));
+        assert_eq!(crosses_major("4.2.1", "4.2.2"), Some(false));
+    }
+
+    #[test]
+    fn treats_zero_versions_like_npm() {
+        assert_eq!(crosses_major("0.3.1", "0.4.0"), Some(true));
+        assert_eq!(crosses_major("0.3.1", "0.3.9"), Some(false));
+        assert_eq!(crosses_major("0.0.3", "0.0.4"), Some(true));
+    }
+
+    #[test]
+    fn none_on_invalid_versions() {
+        assert_eq!(crosses_major("MISSING", "1.0.0"), None);
+    }
+}
+
+#[cfg(test)]
+mod distance_tests {
+    use super::*;
+
+    #[test]
+    fn counts_most_significant_change() {
+        assert_eq!(
+            distance("1.2.3", "3.0.1"),
+            Some(Distance {
+                major: 2,
+                ..Distance::default()
+            })
+        );
+        assert_eq!(
+            distance("1.2.3", "1.5.0"),
+            Some(Distance {
+                minor: 3,
+                ..Distance::default()
+            })
+        );
+        assert_eq!(
+            distance("1.2.3", "1.2.9"),
+            Some(Distance {
+                patch: 6,
+                ..Distance::default()
+            })
+        );
+    }
+
+    #[test]
+    fn none_for_invalid_or_downgrade() {
+        assert_eq!(distance("MISSING", "1.0.0"), None);
+        assert_eq!(distance("2.0.0", "1.9.9"), None);
+        assert_eq!(distance("1.0.0", "1.0.0"), Some(Distance::default()));
+    }
+
+    #[test]
+    fn sums_mixed_set() {
+        let total = total_distance(vec![
+            ("3.6.5", "4.2.2"),
+            ("1.0.0", "3.0.0"),
+            ("1.2.0", "1.3.0"),
+            ("4.3.2", "4.7.0"),
+            ("1.0.2", "1.0.3"),
+            ("0.5.0", "0.5.8"),
+            ("MISSING", "2.0.0"),
+        ]);
+
+        assert_eq!(
+            total,
+            Distance {
+                major: 3,
+                minor: 5,
+                patch: 9,
+            }
+        );
+        assert_eq!(total.to_string(), "3 major, 5 minor, 9 patch versions");
+    }
+
+    #[test]
+    fn empty_set_is_zero() {
+        assert!(total_distance(vec![]).is_zero());
+    }
+}
+
+#[cfg(test)]
+mod range_fit_tests {
+    use super::*;
+
+    #[test]
+    fn in_range() {
+        assert_eq!(range_fit("^4.3.2", "4.3.4"), Some(RangeFit::InRange));
+        assert_eq!(range_fit("~1.2.0", "1.2.9"), Some(RangeFit::InRange));
+        assert_eq!(range_fit("1.x", "1.9.0"), Some(RangeFit::InRange));
+        assert_eq!(range_fit("1.2", "1.2.7"), Some(RangeFit::InRange));
+        assert_eq!(range_fit("*", "9.0.0"), Some(RangeFit::InRange));
+        assert_eq!(range_fit("", "9.0.0"), Some(RangeFit::InRange));
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(range_fit("^3.2.6", "4.3.4"), Some(RangeFit::OutOfRange));
+        assert_eq!(range_fit("~1.2.0", "1.3.0"), Some(RangeFit::OutOfRange));
+        assert_eq!(range_fit("1.0.3", "1.0.4"), Some(RangeFit::OutOfRange));
+        assert_eq!(range_fit("1.2", "1.3.0"), Some(RangeFit::OutOfRange));
+    }
+
+    #[test]
+    fn reads_npm_range_syntax() {
+        assert_eq!(range_fit(">=1.0.0 <2", "1.5.0"), Some(RangeFit::InRange));
+        assert_eq!(
+            range_fit(">= 1.0.0 < 2", "2.0.0"),
+            Some(RangeFit::OutOfRange)
+        );
+        assert_eq!(range_fit("1.0.0 - 2.0.0", "2.0.0"), Some(RangeFit::InRange));
+        assert_eq!(
+            range_fit("^1.0.0 || ^2.0.0", "2.1.0"),
+            Some(RangeFit::InRange)
+        );
+        assert_eq!(
+            range_fit("^1.0.0 || ^2.0.0", "3.0.0"),
+            Some(RangeFit::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn none_when_unreadable() {
+        assert_eq!(range_fit("github:debug-js/debug", "4.3.4"), None);
+        assert_eq!(range_fit("^4.3.2", "MISSING"), None);
+    }
+}
@@ -0,0 +1,147 @@
+// the debounce logic is only driven by the watcher, which needs the `watch` feature
+#![cfg_attr(not(feature = "watch"), allow(dead_code))]
+
+use std::{
+    error::Error,
+    time::{Duration, Instant},
+};
+
+/// How long package.json has to be left alone before checking again, as editors and npm
+/// often write it several times in quick succession
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Collapses a burst of changes into one, once no more have come in for the window
+#[derive(Debug)]
+pub struct Debouncer {
+    window: Duration,
+    last_change: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration) -> Debouncer {
+        Debouncer {
+            window,
+            last_change: None,
+        }
+    }
+
+    pub fn record(&mut self, at: Instant) {
+        self.last_change = Some(at);
+    }
+
+    /// How long until a pending change is due, None when nothing is pending
+    pub fn time_left(&self, now: Instant) -> Option<Duration> {
+        self.last_change
+            .map(|last| (last + self.window).saturating_duration_since(now))
+    }
+
+    /// Whether a change is due, clearing it if so
+    pub fn take_due(&mut self, now: Instant) -> bool {
+        match self.time_left(now) {
+            Some(left) if left.is_zero() => {
+                self.last_change = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Calls `on_change` each time package.json in the current directory changes, until the
+/// watcher stops
+#[cfg(feature = "watch")]
+pub fn run(mut on_change: impl FnMut()) -> Result<(), Box<dyn Error>> {
+    use notify::{RecursiveMode, Watcher};
+    use std::{
+        path::Path,
+        sync::mpsc::{self, RecvTimeoutError},
+    };
+
+    // no change to wait for, but still wakes up now and then
+    const IDLE: Duration = Duration::from_secs(60);
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    // the directory rather than the file, as editors often save by replacing the file
+    watcher.watch(Path::new("."), RecursiveMode::NonRecursive)?;
+
+    let mut debouncer = Debouncer::new(DEBOUNCE);
+
+    loop {
+        let timeout = debouncer.time_left(Instant::now()).unwrap_or(IDLE);
+
+        match receiver.recv_timeout(timeout) {
+            Ok(Ok(event)) => {
+                let is_manifest = event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name().is_some_and(|name| name == "package.json"));
+
+                if is_manifest && !event.kind.is_access() {
+                    debouncer.record(Instant::now());
+                }
+            }
+            Ok(Err(err)) => return Err(err.into()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+
+        if debouncer.take_due(Instant::now()) {
+            on_change();
+            // e.g. patch mode rewriting package.json while checking
+            while receiver.try_recv().is_ok() {}
+        }
+    }
+}
+
+#[cfg(not(feature = "watch"))]
+pub fn run(_on_change: impl FnMut()) -> Result<(), Box<dyn Error>> {
+    Err("--watch needs npm-bumpall built with the watch feature".into())
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod debouncer_tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_millis(300);
+
+    #[test]
+    fn nothing_pending() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(WINDOW);
+
+        assert_eq!(debouncer.time_left(start), None);
+        assert!(!debouncer.take_due(start + WINDOW));
+    }
+
+    #[test]
+    fn due_after_the_window() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(WINDOW);
+        debouncer.record(start);
+
+        assert_eq!(
+            debouncer.time_left(start + Duration::from_millis(100)),
+            Some(Duration::from_millis(200))
+        );
+        assert!(!debouncer.take_due(start + Duration::from_millis(299)));
+        assert!(debouncer.take_due(start + WINDOW));
+        // only once per burst
+        assert!(!debouncer.take_due(start + WINDOW * 2));
+    }
+
+    #[test]
+    fn rapid_changes_push_it_back() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(WINDOW);
+
+        for offset in [0, 100, 200, 300] {
+            debouncer.record(start + Duration::from_millis(offset));
+        }
+
+        assert!(!debouncer.take_due(start + Duration::from_millis(500)));
+        assert!(debouncer.take_due(start + Duration::from_millis(600)));
+    }
+}
@@ -0,0 +1,424 @@
+use glob::glob;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::manifest::MANIFEST;
+use crate::package::Package;
+
+const PNPM_WORKSPACE: &str = "pnpm-workspace.yaml";
+
+/// Reads the `workspaces` patterns from a root package.json, in either the npm/yarn array form
+/// or yarn's `{ "packages": [...] }` form
+pub fn manifest_patterns(manifest: &str) -> Result<Vec<String>, serde_json::Error> {
+    let manifest: Value = serde_json::from_str(manifest)?;
+
+    let patterns = match manifest.get("workspaces") {
+        Some(Value::Array(patterns)) => patterns,
+        Some(Value::Object(obj)) => match obj.get("packages").and_then(|p| p.as_array()) {
+            Some(patterns) => patterns,
+            None => return Ok(vec![]),
+        },
+        _ => return Ok(vec![]),
+    };
+
+    Ok(patterns
+        .iter()
+        .filter_map(|p| p.as_str().map(String::from))
+        .collect())
+}
+
+/// Reads the `packages` list from a pnpm-workspace.yaml, which is all pnpm keeps in there
+pub fn pnpm_patterns(src: &str) -> Vec<String> {
+    let mut patterns = vec![];
+    let mut in_packages = false;
+
+    for line in src.lines() {
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+
+        if let Some(item) = trimmed.strip_prefix('-').filter(|_| in_packages) {
+            let item = item.split(" #").next().unwrap_or_default().trim();
+            patterns.push(item.trim_matches(['\'', '"']).to_string());
+        }
+    }
+
+    patterns
+}
+
+/// Expands the workspace patterns relative to `root`, keeping each directory with a package.json.
+/// Patterns starting with `!` exclude whatever they match.
+pub fn expand_patterns(root: &Path, patterns: &[String]) -> Vec<PathBuf> {
+    let expand = |pattern: &str| -> Vec<PathBuf> {
+        let pattern = root.join(pattern.trim_end_matches('/'));
+
+        match glob(&pattern.to_string_lossy()) {
+            Ok(paths) => paths.filter_map(Result::ok).collect(),
+            Err(_) => vec![],
+        }
+    };
+
+    let (excludes, includes): (Vec<&String>, Vec<&String>) =
+        patterns.iter().partition(|p| p.starts_with('!'));
+
+    let excluded: Vec<PathBuf> = excludes.iter().flat_map(|p| expand(&p[1..])).collect();
+
+    let mut dirs: Vec<PathBuf> = includes
+        .iter()
+        .flat_map(|p| expand(p))
+        .filter(|dir| dir.join(MANIFEST).is_file() && !excluded.contains(dir))
+        .collect();
+
+    dirs.sort();
+    dirs.dedup();
+
+    dirs
+}
+
+/// Finds every workspace directory below `root`, from its package.json or pnpm-workspace.yaml
+pub fn discover(root: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut patterns = manifest_patterns(&fs::read_to_string(root.join(MANIFEST))?)?;
+
+    if let Ok(src) = fs::read_to_string(root.join(PNPM_WORKSPACE)) {
+        patterns.extend(pnpm_patterns(&src));
+    }
+
+    Ok(expand_patterns(root, &patterns))
+}
+
+/// Groups outdated packages by the directory name of the workspace they're installed for
+pub fn group_by_workspace(packages: &[Package]) -> BTreeMap<&str, Vec<&Package>> {
+    let mut groups: BTreeMap<&str, Vec<&Package>> = BTreeMap::new();
+
+    for pkg in packages.iter() {
+        groups
+            .entry(pkg.install_dir_name.as_str())
+            .or_default()
+            .push(pkg);
+    }
+
+    groups
+}
+
+/// The workspaces with any outdated packages, going by the root's outdated check (which
+/// reports every workspace). Those without a group are dropped, so nothing is run in them
+pub fn with_outdated(workspaces: Vec<PathBuf>, packages: &[Package]) -> Vec<PathBuf> {
+    let groups = group_by_workspace(packages);
+
+    workspaces
+        .into_iter()
+        .filter(|dir| {
+            dir.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| groups.contains_key(name))
+        })
+        .collect()
+}
+
+/// How far below the current directory to look for projects when it has no package.json
+pub const PROJECT_SEARCH_DEPTH: usize = 3;
+
+/// Finds the directories below `root` with a package.json, up to `max_depth` levels down.
+/// A project's own subdirectories aren't searched (those are its workspaces), and neither are
+/// node_modules or hidden directories
+pub fn find_projects(root: &Path, max_depth: usize) -> Vec<PathBuf> {
+    let mut projects = vec![];
+    let mut pending = vec![(root.to_path_buf(), 0)];
+
+    while let Some((dir, depth)) = pending.pop() {
+        if depth >= max_depth {
+            continue;
+        }
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            if !path.is_dir() || name == "node_modules" || name.starts_with('.') {
+                continue;
+            }
+
+            if path.join(MANIFEST).is_file() {
+                projects.push(path);
+            } else {
+                pending.push((path, depth + 1));
+            }
+        }
+    }
+
+    projects.sort();
+    projects
+}
+
+/// Parses which of `count` listed projects to bump, from e.g. "1,3", "2 4" or "all".
+/// None when the answer is empty or doesn't make sense
+pub fn parse_project_choice(input: &str, count: usize) -> Option<Vec<usize>> {
+    let input = input.trim();
+
+    if input.eq_ignore_ascii_case("a") || input.eq_ignore_ascii_case("all") {
+        return Some((0..count).collect());
+    }
+
+    let mut chosen = vec![];
+
+    for part in input.split([',', ' ']).filter(|p| !p.is_empty()) {
+        match part.parse::<usize>() {
+            Ok(n) if (1..=count).contains(&n) => chosen.push(n - 1),
+            _ => return None,
+        }
+    }
+
+    chosen.sort_unstable();
+    chosen.dedup();
+
+    if chosen.is_empty() {
+        None
+    } else {
+        Some(chosen)
+    }
+}
+
+// Tests --------------------------------------------------------------
+
+#[cfg(test)]
+mod manifest_patterns_tests {
+    use super::*;
+
+    #[test]
+    fn reads_array_form() {
+        assert_eq!(
+            manifest_patterns(r#"{ "workspaces": ["packages/*", "apps/web"] }"#).unwrap(),
+            vec![String::from("packages/*"), String::from("apps/web")]
+        );
+    }
+
+    #[test]
+    fn reads_yarn_object_form() {
+        let src = r#"{ "workspaces": { "packages": ["packages/*"], "nohoist": ["**/react"] } }"#;
+
+        assert_eq!(
+            manifest_patterns(src).unwrap(),
+            vec![String::from("packages/*")]
+        );
+    }
+
+    #[test]
+    fn handles_no_workspaces() {
+        assert_eq!(
+            manifest_patterns(r#"{ "name": "test" }"#).unwrap(),
+            Vec::<String>::new()
+        );
+        assert!(manifest_patterns("not json").is_err());
+    }
+}
+
+#[cfg(test)]
+mod pnpm_patterns_tests {
+    use super::*;
+
+    #[test]
+    fn reads_packages_list() {
+        let src = r#"
+# all packages in direct subdirs of packages/
+packages:
+  - 'packages/*'
+  - "components/**" # nested too
+  - '!**/test/**'
+catalog:
+  - 'not-a-package'
+"#;
+
+        assert_eq!(
+            pnpm_patterns(src),
+            vec![
+                String::from("packages/*"),
+                String::from("components/**"),
+                String::from("!**/test/**"),
+            ]
+        );
+    }
+
+    #[test]
+    fn handles_empty() {
+        assert_eq!(pnpm_patterns(""), Vec::<String>::new());
+    }
+}
+
+#[cfg(test)]
+mod discover_tests {
+    use super::*;
+
+    fn root() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test_files/workspace")
+    }
+
+    #[test]
+    fn finds_workspace_dirs() {
+        let root = root();
+        let expected = vec![
+            root.join("packages/a"),
+            root.join("packages/b"),
+            root.join("tools/cli"),
+        ];
+
+        assert_eq!(discover(&root).unwrap(), expected);
+    }
+
+    #[test]
+    fn dedupes_overlapping_patterns() {
+        let root = root();
+        let patterns = vec![String::from("packages/*"), String::from("packages/a/")];
+
+        assert_eq!(
+            expand_patterns(&root, &patterns),
+            vec![
+                root.join("packages/a"),
+                root.join("packages/b"),
+                root.join("packages/private"),
+            ]
+        );
+    }
+
+    #[test]
+    fn err_without_manifest() {
+        assert!(discover(&root().join("packages/docs")).is_err());
+    }
+}
+
+#[cfg(test)]
+mod with_outdated_tests {
+    use super::*;
+    use crate::utility::{Args, Config};
+
+    fn packages() -> Vec<Package> {
+        let mut config = Config::new_from_args(Args::default());
+        config.current_dir_name = None;
+
+        [
+            "location:debug@4.3.4:debug@4.3.2:debug@4.3.4:web",
+            "location:polished@3.7.2:polished@3.6.5:polished@4.2.2:web",
+            "location:react@18.3.1:react@18.2.0:react@18.3.1:api",
+        ]
+        .iter()
+        .map(|line| Package::new(String::from(*line), &config).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn groups_by_workspace() {
+        let packages = packages();
+        let groups = group_by_workspace(&packages);
+        let names: Vec<(&str, usize)> = groups
+            .iter()
+            .map(|(dir, group)| (*dir, group.len()))
+            .collect();
+
+        assert_eq!(names, vec![("api", 1), ("web", 2)]);
+    }
+
+    #[test]
+    fn drops_workspaces_without_outdated_packages() {
+        let workspaces = vec![
+            PathBuf::from("/repo/packages/web"),
+            PathBuf::from("/repo/packages/docs"),
+            PathBuf::from("/repo/apps/api"),
+        ];
+
+        assert_eq!(
+            with_outdated(workspaces, &packages()),
+            vec![
+                PathBuf::from("/repo/packages/web"),
+                PathBuf::from("/repo/apps/api"),
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_every_workspace_when_up_to_date() {
+        let workspaces = vec![PathBuf::from("/repo/packages/web")];
+
+        assert!(with_outdated(workspaces, &[]).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod find_projects_tests {
+    use super::*;
+
+    fn fixture() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test_files/projects")
+    }
+
+    #[test]
+    fn finds_nearby_projects() {
+        let root = fixture();
+
+        assert_eq!(
+            find_projects(&root, PROJECT_SEARCH_DEPTH),
+            vec![root.join("apps/web"), root.join("libs/ui")]
+        );
+    }
+
+    #[test]
+    fn respects_depth() {
+        let root = fixture();
+
+        assert!(find_projects(&root, 1).is_empty());
+        assert!(find_projects(&root, 4).contains(&root.join("deep/a/b/c")));
+    }
+
+    #[test]
+    fn stops_at_project_boundary() {
+        let root = fixture();
+
+        assert!(!find_projects(&root, 10).contains(&root.join("apps/web/packages/inner")));
+    }
+
+    #[test]
+    fn handles_missing_root() {
+        assert!(find_projects(&fixture().join("missing"), PROJECT_SEARCH_DEPTH).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod parse_project_choice_tests {
+    use super::*;
+
+    #[test]
+    fn picks_listed_numbers() {
+        assert_eq!(parse_project_choice("1,3", 3), Some(vec![0, 2]));
+        assert_eq!(parse_project_choice(" 3 1 3\n", 3), Some(vec![0, 2]));
+    }
+
+    #[test]
+    fn picks_all() {
+        assert_eq!(parse_project_choice("all", 2), Some(vec![0, 1]));
+        assert_eq!(parse_project_choice("A\n", 2), Some(vec![0, 1]));
+    }
+
+    #[test]
+    fn rejects_nonsense() {
+        assert_eq!(parse_project_choice("", 2), None);
+        assert_eq!(parse_project_choice("0", 2), None);
+        assert_eq!(parse_project_choice("3", 2), None);
+        assert_eq!(parse_project_choice("web", 2), None);
+    }
+}